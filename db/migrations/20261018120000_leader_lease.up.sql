@@ -0,0 +1,8 @@
+-- 複数インスタンスのうち1台だけが定期処理を実行するためのリース
+
+CREATE TABLE leader_lease
+(
+    name       VARCHAR(64) PRIMARY KEY,
+    holder     VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ  NOT NULL
+);
@@ -0,0 +1 @@
+DROP TABLE birthday_notification;
@@ -0,0 +1,22 @@
+-- 誕生日通知の送信状況 (アウトボックス)
+-- 送信前に1年1件の行を作成し、Discord への各操作が終わるたびに進捗を記録する
+
+CREATE TABLE birthday_notification
+(
+    guild_id                BIGINT      NOT NULL,
+    member_id               BIGINT      NOT NULL,
+    year                    INTEGER     NOT NULL,
+    notify_date             DATE        NOT NULL,
+    status                  TEXT        NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'skipped', 'failed')),
+    channel_id              BIGINT,
+    announcement_message_id BIGINT,
+    reacted                 BOOLEAN     NOT NULL DEFAULT FALSE,
+    reply_message_id        BIGINT,
+    attempts                INTEGER     NOT NULL DEFAULT 0,
+    last_error              TEXT,
+    -- 送信中のインスタンスが処理を確保している期限
+    claimed_until           TIMESTAMPTZ,
+    completed_at            TIMESTAMPTZ,
+    PRIMARY KEY (guild_id, member_id, year)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE birthday_notification
+    DROP COLUMN reaction_count;
@@ -0,0 +1,4 @@
+-- 誕生日通知の履歴として、お知らせについたリアクションの数を記録する
+
+ALTER TABLE birthday_notification
+    ADD COLUMN reaction_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE guild
+    DROP COLUMN locale;
@@ -0,0 +1,4 @@
+-- ボットのメッセージの言語 (NULL の場合は日本語)
+
+ALTER TABLE guild
+    ADD COLUMN locale VARCHAR(16);
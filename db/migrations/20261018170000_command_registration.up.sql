@@ -0,0 +1,8 @@
+-- 最後に Discord に登録したスラッシュコマンド定義のハッシュ (変更がない場合は登録を省く)
+
+CREATE TABLE command_registration
+(
+    scope         VARCHAR(64) PRIMARY KEY,
+    hash          VARCHAR(64) NOT NULL,
+    registered_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,3 @@
+ALTER TABLE birthday_notification
+    DROP CONSTRAINT birthday_notification_pkey,
+    ADD PRIMARY KEY (guild_id, member_id, year);
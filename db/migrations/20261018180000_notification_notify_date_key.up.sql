@@ -0,0 +1,6 @@
+-- 誕生日を解除して別の日付で登録し直した場合に、送信済みの記録を残したまま同じ年にもう一度通知できるよう、
+-- 通知日を主キーに含める
+
+ALTER TABLE birthday_notification
+    DROP CONSTRAINT birthday_notification_pkey,
+    ADD PRIMARY KEY (guild_id, member_id, year, notify_date);
@@ -0,0 +1,17 @@
+-- Add up migration script here
+
+CREATE TABLE guild
+(
+    guild_id INTEGER PRIMARY KEY,
+    name     TEXT NOT NULL
+);
+
+CREATE TABLE guild_member
+(
+    member_id     INTEGER NOT NULL,
+    guild_id      INTEGER NOT NULL,
+    birth         DATE,
+    last_notified DATE,
+    PRIMARY KEY (member_id, guild_id),
+    FOREIGN KEY (guild_id) REFERENCES guild (guild_id)
+);
@@ -0,0 +1,9 @@
+-- 複数インスタンスのうち1台だけが定期処理を実行するためのリース
+
+CREATE TABLE leader_lease
+(
+    name       TEXT PRIMARY KEY,
+    holder     TEXT    NOT NULL,
+    -- UNIX 時刻(秒)
+    expires_at INTEGER NOT NULL
+);
@@ -0,0 +1,22 @@
+-- 誕生日通知の送信状況 (アウトボックス)
+-- 送信前に1年1件の行を作成し、Discord への各操作が終わるたびに進捗を記録する
+
+CREATE TABLE birthday_notification
+(
+    guild_id                INTEGER NOT NULL,
+    member_id               INTEGER NOT NULL,
+    year                    INTEGER NOT NULL,
+    notify_date             DATE    NOT NULL,
+    status                  TEXT    NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'skipped', 'failed')),
+    channel_id              INTEGER,
+    announcement_message_id INTEGER,
+    reacted                 INTEGER NOT NULL DEFAULT 0,
+    reply_message_id        INTEGER,
+    attempts                INTEGER NOT NULL DEFAULT 0,
+    last_error              TEXT,
+    -- 送信中のインスタンスが処理を確保している期限 (UNIX 時刻(秒))
+    claimed_until           INTEGER,
+    completed_at            TEXT,
+    PRIMARY KEY (guild_id, member_id, year)
+);
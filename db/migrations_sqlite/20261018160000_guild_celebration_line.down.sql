@@ -0,0 +1 @@
+DROP TABLE guild_celebration_line;
@@ -0,0 +1,10 @@
+-- ギルドが登録したお祝いのセリフ (組み込みの言い回しと合わせて、リプライの候補にする)
+
+CREATE TABLE guild_celebration_line
+(
+    guild_id INTEGER NOT NULL,
+    -- ギルド内の通し番号 (削除時に指定する)
+    line_no  INTEGER NOT NULL,
+    line     TEXT    NOT NULL,
+    PRIMARY KEY (guild_id, line_no)
+);
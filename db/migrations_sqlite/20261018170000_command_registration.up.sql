@@ -0,0 +1,9 @@
+-- 最後に Discord に登録したスラッシュコマンド定義のハッシュ (変更がない場合は登録を省く)
+
+CREATE TABLE command_registration
+(
+    scope         TEXT PRIMARY KEY,
+    hash          TEXT    NOT NULL,
+    -- UNIX 時刻(秒)
+    registered_at INTEGER NOT NULL
+);
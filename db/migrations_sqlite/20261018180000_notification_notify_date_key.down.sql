@@ -0,0 +1,30 @@
+CREATE TABLE birthday_notification_new
+(
+    guild_id                INTEGER NOT NULL,
+    member_id               INTEGER NOT NULL,
+    year                    INTEGER NOT NULL,
+    notify_date             DATE    NOT NULL,
+    status                  TEXT    NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'skipped', 'failed')),
+    channel_id              INTEGER,
+    announcement_message_id INTEGER,
+    reacted                 INTEGER NOT NULL DEFAULT 0,
+    reply_message_id        INTEGER,
+    attempts                INTEGER NOT NULL DEFAULT 0,
+    last_error              TEXT,
+    -- 送信中のインスタンスが処理を確保している期限 (UNIX 時刻(秒))
+    claimed_until           INTEGER,
+    completed_at            TEXT,
+    reaction_count          INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (guild_id, member_id, year)
+);
+
+INSERT INTO birthday_notification_new
+SELECT guild_id, member_id, year, notify_date, status, channel_id, announcement_message_id,
+       reacted, reply_message_id, attempts, last_error, claimed_until, completed_at, reaction_count
+FROM birthday_notification;
+
+DROP TABLE birthday_notification;
+
+ALTER TABLE birthday_notification_new
+    RENAME TO birthday_notification;
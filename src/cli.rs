@@ -0,0 +1,357 @@
+// コマンドライン引数の解析
+
+use crate::models::birth_date::parse_birth_date;
+use crate::res::messages::Locale;
+use chrono::NaiveDate;
+use std::path::PathBuf;
+//
+// 引数なしで起動した場合は serve と同じくボットを起動する。
+
+pub const USAGE: &str = "\
+Usage:
+  zunda-bot-rs [serve] [--allow-migration-failure]
+  zunda-bot-rs migrate up
+  zunda-bot-rs migrate down [N]
+  zunda-bot-rs migrate status
+  zunda-bot-rs copy-store <FROM_URL> <TO_URL>
+  zunda-bot-rs backup <FILE>
+  zunda-bot-rs restore <FILE>
+  zunda-bot-rs admin guilds
+  zunda-bot-rs admin locale <GUILD_ID> <ja|en|none>
+  zunda-bot-rs admin members <GUILD_ID>
+  zunda-bot-rs admin birth get <GUILD_ID> <MEMBER_ID>
+  zunda-bot-rs admin birth set <GUILD_ID> <MEMBER_ID> <MM/DD|none>
+  zunda-bot-rs admin clear-notified <GUILD_ID> [MEMBER_ID] [--year YEAR]
+  zunda-bot-rs admin notify-dry-run <YYYY-MM-DD>
+  zunda-bot-rs admin failed-notifications [GUILD_ID]
+  zunda-bot-rs admin retry-notification <GUILD_ID> <MEMBER_ID> <YEAR>";
+
+#[derive(Debug, PartialEq)]
+pub enum CliCommand {
+    /// ボットを起動する
+    ///
+    /// マイグレーションに失敗した場合は起動しない。`allow_migration_failure`が有効な場合のみ、ログを出して起動を続ける。
+    Serve {
+        allow_migration_failure: bool,
+    },
+    Migrate(MigrateCommand),
+    /// ストア間でデータをコピーする
+    CopyStore {
+        from_url: String,
+        to_url: String,
+    },
+    /// 全データを JSON ファイルに書き出す
+    Backup {
+        path: PathBuf,
+    },
+    /// JSON ファイルからデータを取り込む
+    Restore {
+        path: PathBuf,
+    },
+    /// Discord に接続せずにデータを確認・修正する
+    Admin(AdminCommand),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MigrateCommand {
+    Up,
+    /// 新しいものから指定した件数のマイグレーションを取り消す
+    Down(usize),
+    Status,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AdminCommand {
+    Guilds,
+    /// `locale` が `None` の場合は設定を消して日本語に戻す
+    SetLocale {
+        guild_id: i64,
+        locale: Option<Locale>,
+    },
+    Members {
+        guild_id: i64,
+    },
+    GetBirth {
+        guild_id: i64,
+        member_id: i64,
+    },
+    /// `birth` が `None` の場合は誕生日を解除する
+    SetBirth {
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    },
+    /// `member_id` を省略した場合はギルドの全メンバー、`year` を省略した場合は今年が対象
+    ClearNotified {
+        guild_id: i64,
+        member_id: Option<i64>,
+        year: Option<i32>,
+    },
+    NotifyDryRun {
+        today: NaiveDate,
+    },
+    /// 送信に失敗した誕生日通知 (再試行中のものを含む)。`guild_id` を省略した場合は全ギルドが対象
+    FailedNotifications {
+        guild_id: Option<i64>,
+    },
+    /// 送信に失敗した・見送った誕生日通知を送信待ちに戻す
+    RetryNotification {
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    },
+}
+
+/// プログラム名を除いた引数を解析する
+pub fn parse_args(args: &[String]) -> anyhow::Result<CliCommand> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let command = match args.as_slice() {
+        [] | ["serve"] => CliCommand::Serve {
+            allow_migration_failure: false,
+        },
+        ["--allow-migration-failure"] | ["serve", "--allow-migration-failure"] => {
+            CliCommand::Serve {
+                allow_migration_failure: true,
+            }
+        }
+        ["migrate", "up"] => CliCommand::Migrate(MigrateCommand::Up),
+        ["migrate", "down"] => CliCommand::Migrate(MigrateCommand::Down(1)),
+        ["migrate", "down", steps] => {
+            let steps = steps
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid number of migrations: {steps}\n\n{USAGE}"))?;
+            CliCommand::Migrate(MigrateCommand::Down(steps))
+        }
+        ["migrate", "status"] => CliCommand::Migrate(MigrateCommand::Status),
+        ["copy-store", from_url, to_url] => CliCommand::CopyStore {
+            from_url: from_url.to_string(),
+            to_url: to_url.to_string(),
+        },
+        ["backup", path] => CliCommand::Backup {
+            path: PathBuf::from(path),
+        },
+        ["restore", path] => CliCommand::Restore {
+            path: PathBuf::from(path),
+        },
+        ["admin", rest @ ..] => CliCommand::Admin(parse_admin_args(rest)?),
+        _ => anyhow::bail!("Unknown command: {}\n\n{USAGE}", args.join(" ")),
+    };
+    Ok(command)
+}
+
+fn parse_admin_args(args: &[&str]) -> anyhow::Result<AdminCommand> {
+    let command = match args {
+        ["guilds"] => AdminCommand::Guilds,
+        ["locale", guild_id, locale] => AdminCommand::SetLocale {
+            guild_id: parse_id(guild_id)?,
+            locale: match *locale {
+                "none" => None,
+                locale => Some(locale.parse()?),
+            },
+        },
+        ["members", guild_id] => AdminCommand::Members {
+            guild_id: parse_id(guild_id)?,
+        },
+        ["birth", "get", guild_id, member_id] => AdminCommand::GetBirth {
+            guild_id: parse_id(guild_id)?,
+            member_id: parse_id(member_id)?,
+        },
+        ["birth", "set", guild_id, member_id, birth] => AdminCommand::SetBirth {
+            guild_id: parse_id(guild_id)?,
+            member_id: parse_id(member_id)?,
+            birth: parse_birth(birth)?,
+        },
+        ["clear-notified", guild_id, rest @ ..] => {
+            let (member_id, year) = match rest {
+                [] => (None, None),
+                [member_id] => (Some(*member_id), None),
+                ["--year", year] => (None, Some(*year)),
+                [member_id, "--year", year] => (Some(*member_id), Some(*year)),
+                _ => anyhow::bail!("Unknown command: admin {}\n\n{USAGE}", args.join(" ")),
+            };
+            AdminCommand::ClearNotified {
+                guild_id: parse_id(guild_id)?,
+                member_id: member_id.map(parse_id).transpose()?,
+                year: year.map(parse_year).transpose()?,
+            }
+        }
+        ["notify-dry-run", today] => AdminCommand::NotifyDryRun {
+            today: NaiveDate::parse_from_str(today, "%Y-%m-%d")
+                .map_err(|_| anyhow::anyhow!("Invalid date (expected YYYY-MM-DD): {today}"))?,
+        },
+        ["failed-notifications"] => AdminCommand::FailedNotifications { guild_id: None },
+        ["failed-notifications", guild_id] => AdminCommand::FailedNotifications {
+            guild_id: Some(parse_id(guild_id)?),
+        },
+        ["retry-notification", guild_id, member_id, year] => AdminCommand::RetryNotification {
+            guild_id: parse_id(guild_id)?,
+            member_id: parse_id(member_id)?,
+            year: parse_year(year)?,
+        },
+        _ => anyhow::bail!("Unknown command: admin {}\n\n{USAGE}", args.join(" ")),
+    };
+    Ok(command)
+}
+
+fn parse_id(value: &str) -> anyhow::Result<i64> {
+    value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid ID: {value}"))
+}
+
+fn parse_year(value: &str) -> anyhow::Result<i32> {
+    value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid year: {value}"))
+}
+
+/// 誕生日は `/birth` の登録と同じ書き方 (MM/DD など) で受け取る (`none` は解除)
+fn parse_birth(value: &str) -> anyhow::Result<Option<NaiveDate>> {
+    if value == "none" {
+        return Ok(None);
+    }
+    let birth = parse_birth_date(value)
+        .map_err(|e| anyhow::anyhow!("Invalid birthday (expected MM/DD or none): {e}"))?;
+    Ok(Some(birth.to_date()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, AdminCommand, CliCommand, MigrateCommand};
+    use crate::res::messages::Locale;
+    use chrono::NaiveDate;
+
+    fn parse(args: &[&str]) -> anyhow::Result<CliCommand> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn no_arguments_means_serve() {
+        assert_eq!(
+            parse(&[]).unwrap(),
+            CliCommand::Serve {
+                allow_migration_failure: false
+            }
+        );
+        assert_eq!(
+            parse(&["serve", "--allow-migration-failure"]).unwrap(),
+            CliCommand::Serve {
+                allow_migration_failure: true
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_down_defaults_to_one_step() {
+        assert_eq!(
+            parse(&["migrate", "down"]).unwrap(),
+            CliCommand::Migrate(MigrateCommand::Down(1))
+        );
+        assert_eq!(
+            parse(&["migrate", "down", "3"]).unwrap(),
+            CliCommand::Migrate(MigrateCommand::Down(3))
+        );
+        assert!(parse(&["migrate", "down", "-1"]).is_err());
+    }
+
+    #[test]
+    fn unknown_command_is_rejected() {
+        assert!(parse(&["migrate"]).is_err());
+        assert!(parse(&["serve", "now"]).is_err());
+    }
+
+    #[test]
+    fn admin_birth_set_accepts_month_day_or_none() {
+        assert_eq!(
+            parse(&["admin", "birth", "set", "1", "10", "02/29"]).unwrap(),
+            CliCommand::Admin(AdminCommand::SetBirth {
+                guild_id: 1,
+                member_id: 10,
+                birth: NaiveDate::from_ymd_opt(2000, 2, 29),
+            })
+        );
+        assert_eq!(
+            parse(&["admin", "birth", "set", "1", "10", "none"]).unwrap(),
+            CliCommand::Admin(AdminCommand::SetBirth {
+                guild_id: 1,
+                member_id: 10,
+                birth: None,
+            })
+        );
+        assert!(parse(&["admin", "birth", "set", "1", "10", "13/01"]).is_err());
+        assert!(parse(&["admin", "birth", "set", "guild", "10", "none"]).is_err());
+    }
+
+    #[test]
+    fn admin_clear_notified_takes_an_optional_member_and_year() {
+        assert_eq!(
+            parse(&["admin", "clear-notified", "1"]).unwrap(),
+            CliCommand::Admin(AdminCommand::ClearNotified {
+                guild_id: 1,
+                member_id: None,
+                year: None,
+            })
+        );
+        assert_eq!(
+            parse(&["admin", "clear-notified", "1", "--year", "2025"]).unwrap(),
+            CliCommand::Admin(AdminCommand::ClearNotified {
+                guild_id: 1,
+                member_id: None,
+                year: Some(2025),
+            })
+        );
+        assert_eq!(
+            parse(&["admin", "clear-notified", "1", "10", "--year", "2025"]).unwrap(),
+            CliCommand::Admin(AdminCommand::ClearNotified {
+                guild_id: 1,
+                member_id: Some(10),
+                year: Some(2025),
+            })
+        );
+        assert!(parse(&["admin", "clear-notified", "1", "10", "2025"]).is_err());
+        assert!(parse(&["admin", "clear-notified", "1", "--year"]).is_err());
+    }
+
+    #[test]
+    fn admin_failed_notifications_takes_an_optional_guild() {
+        assert_eq!(
+            parse(&["admin", "failed-notifications"]).unwrap(),
+            CliCommand::Admin(AdminCommand::FailedNotifications { guild_id: None })
+        );
+        assert_eq!(
+            parse(&["admin", "failed-notifications", "1"]).unwrap(),
+            CliCommand::Admin(AdminCommand::FailedNotifications { guild_id: Some(1) })
+        );
+        assert!(parse(&["admin", "failed-notifications", "1", "2"]).is_err());
+        assert_eq!(
+            parse(&["admin", "retry-notification", "1", "10", "2025"]).unwrap(),
+            CliCommand::Admin(AdminCommand::RetryNotification {
+                guild_id: 1,
+                member_id: 10,
+                year: 2025,
+            })
+        );
+        assert!(parse(&["admin", "retry-notification", "1", "10"]).is_err());
+        assert!(parse(&["admin", "retry-notification", "1", "10", "this-year"]).is_err());
+    }
+
+    #[test]
+    fn admin_locale_accepts_supported_locales_or_none() {
+        assert_eq!(
+            parse(&["admin", "locale", "1", "en"]).unwrap(),
+            CliCommand::Admin(AdminCommand::SetLocale {
+                guild_id: 1,
+                locale: Some(Locale::En),
+            })
+        );
+        assert_eq!(
+            parse(&["admin", "locale", "1", "none"]).unwrap(),
+            CliCommand::Admin(AdminCommand::SetLocale {
+                guild_id: 1,
+                locale: None,
+            })
+        );
+        assert!(parse(&["admin", "locale", "1", "fr"]).is_err());
+    }
+}
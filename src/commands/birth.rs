@@ -1,77 +1,95 @@
 use crate::models::common::{Context, Error};
-use poise::ChoiceParameter;
-use poise::CreateReply;
+use poise::serenity_prelude as serenity;
 use std::time::Instant;
 
-#[derive(Debug, ChoiceParameter)]
-pub enum BirthAction {
-    #[name = "List: サーバー内メンバーの誕生日リスト表示"]
-    List,
-    #[name = "Signup: 自身の誕生日の通知登録"]
-    Signup,
-    #[name = "Reset: 自身の誕生日の通知解除"]
-    Reset,
+/// 誕生日コマンド birth
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("list", "signup", "reset", "show", "history"),
+    subcommand_required,
+    description_localized("en-US", "Birthday commands")
+)]
+pub async fn birth(_ctx: Context<'_>) -> anyhow::Result<(), Error> {
+    Ok(())
 }
 
-/// 誕生日コマンド birth
-#[poise::command(slash_command)]
-pub async fn birth(
+/// サーバー内メンバーの誕生日リストを表示する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Show birthdays of server members")
+)]
+async fn list(ctx: Context<'_>) -> anyhow::Result<(), Error> {
+    // List はギルド同期が重くなることがあるため、先に interaction を確定させる
+    ctx.defer_ephemeral().await?;
+    let sync_start = Instant::now();
+    if let Err(e) = ctx.data().guild_update_usecase.invoke().await {
+        tracing::warn!("Guild sync failed before birth list: {}", e);
+    }
+    tracing::info!(
+        elapsed_ms = sync_start.elapsed().as_millis(),
+        "guild sync finished for birth list"
+    );
+    ctx.data().birth_list_usecase.invoke(ctx).await
+}
+
+/// 自身の誕生日を通知登録する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Register your birthday")
+)]
+async fn signup(
     ctx: Context<'_>,
-    #[description = "操作"] action: BirthAction,
+    #[description = "誕生日 (02/01・2月1日・2000/02/01 など)。省略するとフォームで入力する"]
+    #[description_localized(
+        "en-US",
+        "Birthday (02/01, Feb 1, 2000/02/01, ...). Opens a form if omitted"
+    )]
+    date: Option<String>,
 ) -> anyhow::Result<(), Error> {
-    async fn report_command_error(ctx: Context<'_>, action: &BirthAction, e: &Error) {
-        tracing::error!(action = ?action, "birth command failed: {}", e);
-        if let Err(send_err) = ctx
-            .send(
-                CreateReply::default()
-                    .content("コマンドの実行中にエラーが発生したのだ。時間をおいて再実行してほしいのだ。")
-                    .ephemeral(true),
-            )
-            .await
-        {
-            tracing::warn!("failed to send fallback error response: {}", send_err);
-        }
-    }
+    ctx.data().birth_signup_usecase.invoke(ctx, date).await
+}
 
-    let start = Instant::now();
-    tracing::info!(action = ?action, "birth command received");
+/// 自身の誕生日の通知登録を解除する
+#[poise::command(slash_command, description_localized("en-US", "Remove your birthday"))]
+async fn reset(ctx: Context<'_>) -> anyhow::Result<(), Error> {
+    // Reset は後続でボタン操作が発生するため、先に defer してタイムアウトを避ける
+    ctx.defer_ephemeral().await?;
+    ctx.data().birth_reset_usecase.invoke(ctx).await
+}
 
-    match action {
-        BirthAction::List => {
-            // List はギルド同期が重くなることがあるため、先に interaction を確定させる
-            ctx.defer_ephemeral().await?;
-            let sync_start = Instant::now();
-            if let Err(e) = ctx.data().guild_update_usecase.invoke().await {
-                tracing::warn!("Guild sync failed before birth list: {}", e);
-            }
-            tracing::info!(
-                elapsed_ms = sync_start.elapsed().as_millis(),
-                "guild sync finished for birth list"
-            );
-            if let Err(e) = ctx.data().birth_list_usecase.invoke(ctx).await {
-                report_command_error(ctx, &action, &e).await;
-                return Ok(());
-            }
-        }
-        BirthAction::Signup => {
-            if let Err(e) = ctx.data().birth_signup_usecase.invoke(ctx).await {
-                report_command_error(ctx, &action, &e).await;
-                return Ok(());
-            }
-        }
-        BirthAction::Reset => {
-            // Reset は後続でボタン操作が発生するため、先に defer してタイムアウトを避ける
-            ctx.defer_ephemeral().await?;
-            if let Err(e) = ctx.data().birth_reset_usecase.invoke(ctx).await {
-                report_command_error(ctx, &action, &e).await;
-                return Ok(());
-            }
-        }
-    }
-    tracing::info!(
-        action = ?action,
-        elapsed_ms = start.elapsed().as_millis(),
-        "birth command finished"
-    );
-    Ok(())
+/// メンバーの誕生日を表示する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Show a member's birthday")
+)]
+async fn show(
+    ctx: Context<'_>,
+    #[description = "表示するメンバー (省略時は自分)"]
+    #[description_localized("en-US", "Member to show (yourself if omitted)")]
+    member: Option<serenity::User>,
+) -> anyhow::Result<(), Error> {
+    ctx.data()
+        .birth_show_usecase
+        .invoke(ctx, member.map(|member| member.id))
+        .await
+}
+
+/// 過去の誕生日のお祝いを表示する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Show past birthday celebrations")
+)]
+async fn history(
+    ctx: Context<'_>,
+    #[description = "表示するメンバー (省略時はサーバー全体)"]
+    #[description_localized("en-US", "Member to show (whole server if omitted)")]
+    member: Option<serenity::User>,
+) -> anyhow::Result<(), Error> {
+    // History はお知らせごとにリアクション数を取得するため、先に defer する
+    ctx.defer_ephemeral().await?;
+    ctx.data()
+        .birth_history_usecase
+        .invoke(ctx, member.map(|member| member.id))
+        .await
 }
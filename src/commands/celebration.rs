@@ -0,0 +1,53 @@
+use crate::models::common::{Context, Error};
+
+/// サーバー独自のお祝いのセリフ
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("add", "list", "remove"),
+    subcommand_required,
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD",
+    description_localized("en-US", "Server's own celebration lines")
+)]
+pub async fn celebration(_ctx: Context<'_>) -> anyhow::Result<(), Error> {
+    Ok(())
+}
+
+/// お祝いのセリフを追加する ({mention} はメンションに置き換わる)
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Add a celebration line ({mention} becomes the mention)")
+)]
+async fn add(
+    ctx: Context<'_>,
+    #[description = "セリフ"]
+    #[description_localized("en-US", "Line")]
+    line: String,
+) -> anyhow::Result<(), Error> {
+    ctx.data().celebration_usecase.add(ctx, line).await
+}
+
+/// 登録したお祝いのセリフを表示する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Show the registered celebration lines")
+)]
+async fn list(ctx: Context<'_>) -> anyhow::Result<(), Error> {
+    ctx.data().celebration_usecase.list(ctx).await
+}
+
+/// お祝いのセリフを削除する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Remove a celebration line")
+)]
+async fn remove(
+    ctx: Context<'_>,
+    #[description = "セリフの番号"]
+    #[description_localized("en-US", "Line number")]
+    #[min = 1]
+    number: i32,
+) -> anyhow::Result<(), Error> {
+    ctx.data().celebration_usecase.remove(ctx, number).await
+}
@@ -0,0 +1,48 @@
+use crate::commands::command_locale;
+use crate::models::common::{Context, Error};
+use crate::res::messages::Message;
+use chrono::NaiveDate;
+use poise::CreateReply;
+
+// 指定日を「今日」とみなして、実行したギルドで誕生日通知を試すための開発用コマンド
+// デバッグビルドでのみ登録され、ボットのオーナーだけが実行できる
+/// 指定日時点の誕生日通知を実行する(開発用)
+#[poise::command(slash_command, guild_only, owners_only, hide_in_help)]
+pub async fn notify_as_of(
+    ctx: Context<'_>,
+    #[description = "基準日 (YYYY-MM-DD)"] date: String,
+) -> anyhow::Result<(), Error> {
+    let Ok(today) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+        ctx.send(
+            CreateReply::default()
+                .content(Message::NotifyAsOfInvalidDate.text(command_locale(ctx)))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    ctx.defer_ephemeral().await?;
+    tracing::info!(%today, "dev notify_as_of received");
+    let notified_count = ctx
+        .data()
+        .birth_notify_usecase
+        .invoke_as_of(today, i64::from(guild_id))
+        .await?;
+    ctx.send(
+        CreateReply::default()
+            .content(
+                Message::NotifyAsOfCompleted {
+                    date: today,
+                    notified_count,
+                }
+                .text(command_locale(ctx)),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
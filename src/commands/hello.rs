@@ -1,7 +1,18 @@
+use crate::commands::command_locale;
 use crate::models::common::{Context, Error};
+use crate::res::persona::{Line, Pick};
 
-#[poise::command(slash_command)]
+#[poise::command(
+    slash_command,
+    description_localized("ja", "ずんだもんがあいさつするのだ"),
+    description_localized("en-US", "Say hello to Zundamon")
+)]
 pub async fn hello(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.say("こんにちは、なのだ!").await?;
+    let hello = Line::Hello.text(
+        command_locale(ctx),
+        i64::from(ctx.author().id),
+        Pick::Random,
+    );
+    ctx.say(hello).await?;
     Ok(())
 }
@@ -0,0 +1,252 @@
+// すべてのコマンドに共通する前後処理とエラー処理
+//
+// コマンドの所要時間と結果はここでまとめて記録する。
+// 停止処理中のコマンドは command_check で断り、実行中のコマンドは停止時に完了を待つ対象として登録する。
+// ログのスパンは InstrumentedFramework で付与するため、コマンドごとに設定する必要はない。
+// コマンドの失敗や、実行前のチェック (ギルド限定・権限・クールダウン) で弾かれた理由は、
+// on_error で原因ごとのメッセージにして実行したユーザーにだけ伝える。
+
+use crate::commands::command_locale;
+use crate::models::common::{Context, Data, Error};
+use crate::models::error::BotError;
+use crate::res::colors::EMBED_COLOR_ERROR;
+use crate::res::messages::Message;
+use crate::services::logging::command_span;
+use crate::services::metrics::metrics;
+use crate::services::shutdown::InFlightGuard;
+use async_trait::async_trait;
+use poise::serenity_prelude::{self as serenity, CreateEmbed, FullEvent, Interaction};
+use poise::{BoxFuture, Command, CreateReply, FrameworkError};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// 実行中のコマンドの情報 (invocation data として保持し、コマンドの終了とともに破棄される)
+struct CommandStarted {
+    at: Instant,
+    /// 停止処理で完了を待つ対象として登録したガード
+    _in_flight: Option<InFlightGuard>,
+}
+
+/// poise の Framework を包み、コマンドの処理全体 (チェック・エラー処理を含む) をログのスパンの中で実行する
+pub struct InstrumentedFramework<F>(pub F);
+
+#[async_trait]
+impl<F: serenity::Framework> serenity::Framework for InstrumentedFramework<F> {
+    async fn init(&mut self, client: &serenity::Client) {
+        self.0.init(client).await;
+    }
+
+    async fn dispatch(&self, ctx: serenity::Context, event: FullEvent) {
+        match &event {
+            FullEvent::InteractionCreate {
+                interaction: Interaction::Command(command),
+            } => {
+                let span = command_span(command);
+                self.0.dispatch(ctx, event).instrument(span).await
+            }
+            _ => self.0.dispatch(ctx, event).await,
+        }
+    }
+}
+
+/// 停止処理中は新しいコマンドを受け付けない (断った理由は on_error で返信する)
+pub fn command_check(ctx: Context<'_>) -> BoxFuture<'_, Result<bool, Error>> {
+    Box::pin(async move { Ok(!ctx.data().shutdown.is_requested()) })
+}
+
+pub fn pre_command(ctx: Context<'_>) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        tracing::info!(
+            command = %ctx.command().qualified_name,
+            "command received"
+        );
+        // command_check の後に停止が要求された場合は登録できないが、そのまま最後まで実行する
+        let in_flight = ctx.data().shutdown.track();
+        ctx.set_invocation_data(CommandStarted {
+            at: Instant::now(),
+            _in_flight: in_flight,
+        })
+        .await;
+    })
+}
+
+/// コマンドが成功した場合のみ呼ばれる (失敗した場合は on_error で記録する)
+pub fn post_command(ctx: Context<'_>) -> BoxFuture<'_, ()> {
+    Box::pin(record_command(ctx, "success"))
+}
+
+pub fn on_error(error: FrameworkError<'_, Data, Error>) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let Some(ctx) = error.ctx() else {
+            // コマンド以外 (起動処理・イベント) のエラーは poise の既定の処理に任せる
+            if let Err(e) = poise::builtins::on_error(error).await {
+                tracing::error!("Failed to handle framework error: {}", e);
+            }
+            return;
+        };
+        handle_command_error(ctx, error).await
+    })
+}
+
+/// 全コマンド (サブコマンドを含む) に、ユーザーごとのクールダウンを設定する
+pub fn apply_user_cooldown(commands: &mut [Command<Data, Error>], cooldown: Duration) {
+    for command in commands {
+        command.cooldown_config.write().unwrap().user = Some(cooldown);
+        apply_user_cooldown(&mut command.subcommands, cooldown);
+    }
+}
+
+async fn handle_command_error(ctx: Context<'_>, error: FrameworkError<'_, Data, Error>) {
+    let command = &ctx.command().qualified_name;
+    match error {
+        FrameworkError::Command { error, .. } => {
+            record_command(ctx, "error").await;
+            report_command_error(ctx, &error).await;
+        }
+        FrameworkError::CommandPanic { payload, .. } => {
+            record_command(ctx, "error").await;
+            tracing::error!(
+                error_code = "panic",
+                "{} command panicked: {}",
+                command,
+                payload.as_deref().unwrap_or("unknown payload")
+            );
+            reply_error(ctx, Message::CommandFailed).await;
+        }
+        FrameworkError::ArgumentParse { error, input, .. } => {
+            tracing::warn!(
+                error_code = "invalid_argument",
+                "{} command rejected an argument {:?}: {}",
+                command,
+                input,
+                error
+            );
+            reply_error(ctx, Message::InvalidArgument).await;
+        }
+        FrameworkError::CooldownHit {
+            remaining_cooldown, ..
+        } => {
+            tracing::info!(
+                error_code = "cooldown",
+                remaining_ms = remaining_cooldown.as_millis(),
+                "{} command is on cooldown",
+                command
+            );
+            let remaining_secs = remaining_cooldown.as_secs_f64().ceil() as u64;
+            reply_error(ctx, Message::Cooldown { remaining_secs }).await;
+        }
+        FrameworkError::CommandCheckFailed { error: None, .. }
+            if ctx.data().shutdown.is_requested() =>
+        {
+            tracing::info!(
+                error_code = "restarting",
+                "{} command was rejected while shutting down",
+                command
+            );
+            reply_error(ctx, Message::Restarting).await;
+        }
+        FrameworkError::GuildOnly { .. } => {
+            report_command_error(ctx, &BotError::GuildOnly).await;
+        }
+        FrameworkError::MissingBotPermissions {
+            missing_permissions,
+            ..
+        } => {
+            tracing::error!(
+                error_code = "missing_permission",
+                "{} command needs bot permissions: {}",
+                command,
+                missing_permissions
+            );
+            reply_error(ctx, Message::MissingPermission).await;
+        }
+        FrameworkError::MissingUserPermissions { .. } | FrameworkError::NotAnOwner { .. } => {
+            tracing::warn!(
+                error_code = "missing_user_permission",
+                "{} command was used without permission",
+                command
+            );
+            reply_error(ctx, Message::MissingUserPermission).await;
+        }
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                tracing::error!("Failed to handle framework error: {}", e);
+            }
+        }
+    }
+}
+
+/// コマンドの失敗をエラーコードとともにログに記録し、原因に応じたメッセージを実行したユーザーにだけ伝える
+async fn report_command_error(ctx: Context<'_>, e: &Error) {
+    let command = &ctx.command().qualified_name;
+    if e.is_user_error() {
+        tracing::warn!(error_code = e.code(), "{} command rejected: {}", command, e);
+    } else {
+        tracing::error!(error_code = e.code(), "{} command failed: {}", command, e);
+    }
+    reply_error(ctx, e.message()).await;
+}
+
+async fn reply_error(ctx: Context<'_>, message: Message) {
+    let embed = CreateEmbed::new()
+        .title(message.text(command_locale(ctx)))
+        .color(EMBED_COLOR_ERROR); // 異常系の色
+    if let Err(send_err) = ctx
+        .send(CreateReply::default().embed(embed).ephemeral(true))
+        .await
+    {
+        tracing::warn!("failed to send fallback error response: {}", send_err);
+    }
+}
+
+async fn record_command(ctx: Context<'_>, outcome: &'static str) {
+    // pre_command より前に失敗した場合は記録しない
+    let Some(elapsed) = ctx
+        .invocation_data::<CommandStarted>()
+        .await
+        .map(|started| started.at.elapsed())
+    else {
+        return;
+    };
+    let (command, action) = command_labels(ctx);
+    metrics().record_command(command, action, outcome, elapsed);
+    tracing::info!(
+        command = %ctx.command().qualified_name,
+        outcome,
+        elapsed_ms = elapsed.as_millis(),
+        "command finished"
+    );
+}
+
+/// メトリクスのラベルに使う (コマンド名, アクション)
+///
+/// サブコマンドの場合は親コマンドとサブコマンドの名前、そうでない場合はアクションを `none` にする。
+fn command_labels(ctx: Context<'_>) -> (&str, &str) {
+    let qualified_name = &ctx.command().qualified_name;
+    qualified_name
+        .split_once(' ')
+        .unwrap_or((qualified_name, "none"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_user_cooldown;
+    use crate::commands::birth::birth;
+    use crate::commands::celebration::celebration;
+    use std::time::Duration;
+
+    #[test]
+    fn cooldown_applies_to_subcommands() {
+        let mut commands = vec![birth(), celebration()];
+
+        apply_user_cooldown(&mut commands, Duration::from_secs(3));
+
+        let subcommands = &commands[1].subcommands;
+        assert!(!subcommands.is_empty());
+        for command in commands.iter().chain(subcommands) {
+            let config = command.cooldown_config.read().unwrap();
+            assert_eq!(config.user, Some(Duration::from_secs(3)));
+            assert_eq!(config.guild, None);
+        }
+    }
+}
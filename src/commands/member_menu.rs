@@ -0,0 +1,33 @@
+// メンバーを右クリックして開く「アプリ」メニューのコマンド
+//
+// poise はコンテキストメニューの名前の多言語化と default_member_permissions の登録に対応していないため、
+// 名前は日本語のみとし、モデレーター向けのメニューは required_permissions で実行時に権限を確認する。
+
+use crate::models::common::{Context, Error};
+use poise::serenity_prelude as serenity;
+
+/// メンバーの誕生日と、誕生日までの日数を表示する
+#[poise::command(context_menu_command = "誕生日を見る", guild_only)]
+pub async fn show_member_birth(
+    ctx: Context<'_>,
+    member: serenity::User,
+) -> anyhow::Result<(), Error> {
+    ctx.data()
+        .birth_show_usecase
+        .invoke(ctx, Some(member.id))
+        .await
+}
+
+/// モデレーターがメンバーの誕生日を設定する
+#[poise::command(
+    context_menu_command = "誕生日を設定",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn set_member_birth(
+    ctx: Context<'_>,
+    member: serenity::User,
+) -> anyhow::Result<(), Error> {
+    // モーダルはすぐに開く必要があるため defer しない
+    ctx.data().birth_set_usecase.invoke(ctx, member.id).await
+}
@@ -1,2 +1,66 @@
+use crate::models::common::{Context, Data, Error};
+use crate::res::messages::Locale;
+use poise::Command;
+use std::collections::HashMap;
+
 pub mod birth;
+pub mod celebration;
+pub mod dev;
 pub mod hello;
+pub mod hooks;
+pub mod member_menu;
+pub mod settings;
+
+/// DB を参照できない場面で使う言語 (Discord の表示言語のみで決める)
+pub fn command_locale(ctx: Context<'_>) -> Locale {
+    ctx.locale()
+        .and_then(Locale::from_discord)
+        .unwrap_or_default()
+}
+
+/// 英語の説明文は en-US だけに書き、同じ文を en-GB にも設定する
+pub fn share_english_descriptions(commands: &mut [Command<Data, Error>]) {
+    fn copy_en_us(localizations: &mut HashMap<String, String>) {
+        if let Some(description) = localizations.get("en-US").cloned() {
+            localizations.insert("en-GB".to_string(), description);
+        }
+    }
+
+    for command in commands {
+        copy_en_us(&mut command.description_localizations);
+        for parameter in &mut command.parameters {
+            copy_en_us(&mut parameter.description_localizations);
+        }
+        share_english_descriptions(&mut command.subcommands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::share_english_descriptions;
+    use crate::commands::birth::birth;
+
+    #[test]
+    fn en_gb_descriptions_follow_en_us() {
+        let mut commands = vec![birth()];
+        share_english_descriptions(&mut commands);
+
+        let birth = &commands[0];
+        assert_eq!(
+            birth.description_localizations.get("en-GB"),
+            Some(&"Birthday commands".to_string())
+        );
+        let signup = birth
+            .subcommands
+            .iter()
+            .find(|command| command.name == "signup")
+            .unwrap();
+        assert_eq!(
+            signup.parameters[0].description_localizations.get("en-GB"),
+            signup.parameters[0].description_localizations.get("en-US")
+        );
+        assert!(signup.parameters[0]
+            .description_localizations
+            .contains_key("en-GB"));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::models::common::{Context, Error};
+use crate::res::messages::Locale;
+
+/// お知らせの言語の選択肢
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+enum LocaleChoice {
+    #[name = "日本語"]
+    Ja,
+    #[name = "English"]
+    En,
+}
+
+impl From<LocaleChoice> for Locale {
+    fn from(choice: LocaleChoice) -> Self {
+        match choice {
+            LocaleChoice::Ja => Locale::Ja,
+            LocaleChoice::En => Locale::En,
+        }
+    }
+}
+
+/// サーバーの設定
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("locale"),
+    subcommand_required,
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD",
+    description_localized("en-US", "Server settings")
+)]
+pub async fn settings(_ctx: Context<'_>) -> anyhow::Result<(), Error> {
+    Ok(())
+}
+
+/// 誕生日のお知らせの言語を設定する
+#[poise::command(
+    slash_command,
+    description_localized("en-US", "Set the language of birthday announcements")
+)]
+async fn locale(
+    ctx: Context<'_>,
+    #[description = "言語"]
+    #[description_localized("en-US", "Language")]
+    language: LocaleChoice,
+) -> anyhow::Result<(), Error> {
+    ctx.data()
+        .settings_usecase
+        .set_locale(ctx, language.into())
+        .await
+}
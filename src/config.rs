@@ -0,0 +1,311 @@
+// 起動時の設定
+//
+// 環境変数と、CONFIG_FILE で指定した TOML ファイル(任意)から読み込む。両方に指定がある場合は環境変数を優先する。
+// 値の誤りは起動時にまとめて報告し、不正な設定のまま起動しないようにする。
+
+use crate::services::logging::LogFormat;
+use anyhow::Context as _;
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::env;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+const MAX_CONNECTIONS_LIMIT: u32 = 100;
+const DEFAULT_PORT: u16 = 8080;
+const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Tokyo;
+// Cloud Run は SIGTERM から10秒後に強制終了するため、それより短くする
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 8;
+const DEFAULT_COMMAND_COOLDOWN_SECS: u64 = 3;
+
+pub struct Config {
+    /// serve の場合のみ必須
+    discord_token: Option<String>,
+    /// copy-store 以外では必須
+    database_url: Option<String>,
+    pub database_max_connections: u32,
+    /// ヘルスチェックサーバーのポート
+    pub port: u16,
+    /// 「今日」の判定と通知時刻に使うタイムゾーン
+    pub timezone: Tz,
+    /// 毎日の誕生日チェックの時刻
+    pub notify_time: NaiveTime,
+    /// 指定した場合、スラッシュコマンドをこのギルドにだけ登録する (開発用)
+    pub dev_guild_id: Option<u64>,
+    pub log_format: LogFormat,
+    pub allow_migration_failure: bool,
+    /// 終了シグナルを受けてから、実行中の処理の完了を待つ最大時間
+    pub shutdown_timeout: Duration,
+    /// 同じユーザーが同じコマンドを続けて実行できるまでの間隔 (`None` の場合は制限しない)
+    pub command_cooldown: Option<Duration>,
+}
+
+/// 設定ファイルの内容。キーは環境変数名の小文字
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    discord_token: Option<String>,
+    database_url: Option<String>,
+    database_max_connections: Option<u32>,
+    port: Option<u16>,
+    timezone: Option<String>,
+    notify_time: Option<String>,
+    dev_guild_id: Option<u64>,
+    log_format: Option<String>,
+    allow_migration_failure: Option<bool>,
+    shutdown_timeout_secs: Option<u64>,
+    command_cooldown_secs: Option<u64>,
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        let file = match env::var("CONFIG_FILE") {
+            Ok(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read config file {path}"))?;
+                toml::from_str(&text)
+                    .with_context(|| format!("Failed to parse config file {path}"))?
+            }
+            Err(_) => FileConfig::default(),
+        };
+        Self::from_sources(|name| env::var(name).ok(), file)
+    }
+
+    fn from_sources(
+        env: impl Fn(&str) -> Option<String>,
+        file: FileConfig,
+    ) -> anyhow::Result<Self> {
+        let mut loader = Loader {
+            env: &env,
+            errors: Vec::new(),
+        };
+
+        let discord_token = env("DISCORD_TOKEN").or(file.discord_token);
+        let database_url = env("DATABASE_URL").or(file.database_url);
+        let database_max_connections = loader
+            .parsed("DATABASE_MAX_CONNECTIONS", file.database_max_connections)
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS);
+        if !(1..=MAX_CONNECTIONS_LIMIT).contains(&database_max_connections) {
+            loader.errors.push(format!(
+                "DATABASE_MAX_CONNECTIONS must be between 1 and {MAX_CONNECTIONS_LIMIT}, got {database_max_connections}"
+            ));
+        }
+        let port = loader.parsed("PORT", file.port).unwrap_or(DEFAULT_PORT);
+        let timezone = loader
+            .text("TIMEZONE", file.timezone, |value| {
+                value
+                    .parse::<Tz>()
+                    .map_err(|_| "expected an IANA time zone name such as Asia/Tokyo".to_string())
+            })
+            .unwrap_or(DEFAULT_TIMEZONE);
+        let notify_time = loader
+            .text("NOTIFY_TIME", file.notify_time, |value| {
+                NaiveTime::parse_from_str(value, "%H:%M")
+                    .map_err(|_| "expected HH:MM such as 12:00".to_string())
+            })
+            .unwrap_or_else(|| NaiveTime::from_hms_opt(12, 0, 0).expect("Invalid time."));
+        let dev_guild_id = loader.parsed("DEV_GUILD_ID", file.dev_guild_id);
+        if dev_guild_id == Some(0) {
+            loader
+                .errors
+                .push("DEV_GUILD_ID must be a guild ID, got 0".to_string());
+        }
+        let log_format = loader
+            .text("LOG_FORMAT", file.log_format, |value| {
+                value.parse::<LogFormat>().map_err(|e| e.to_string())
+            })
+            // Cloud Run 上 (K_SERVICE が設定される) では Cloud Logging 向けの JSON を既定にする
+            .unwrap_or(if env("K_SERVICE").is_some() {
+                LogFormat::Json
+            } else {
+                LogFormat::Text
+            });
+        let allow_migration_failure = loader
+            .parsed("ALLOW_MIGRATION_FAILURE", file.allow_migration_failure)
+            .unwrap_or(false);
+        let shutdown_timeout = Duration::from_secs(
+            loader
+                .parsed("SHUTDOWN_TIMEOUT_SECS", file.shutdown_timeout_secs)
+                .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+        );
+        // 0 を指定した場合はクールダウンを無効にする
+        let command_cooldown = Some(
+            loader
+                .parsed("COMMAND_COOLDOWN_SECS", file.command_cooldown_secs)
+                .unwrap_or(DEFAULT_COMMAND_COOLDOWN_SECS),
+        )
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs);
+
+        if !loader.errors.is_empty() {
+            anyhow::bail!(
+                "Invalid configuration:\n  - {}",
+                loader.errors.join("\n  - ")
+            );
+        }
+        Ok(Config {
+            discord_token,
+            database_url,
+            database_max_connections,
+            port,
+            timezone,
+            notify_time,
+            dev_guild_id,
+            log_format,
+            allow_migration_failure,
+            shutdown_timeout,
+            command_cooldown,
+        })
+    }
+
+    pub fn discord_token(&self) -> anyhow::Result<&str> {
+        self.discord_token
+            .as_deref()
+            .context("'DISCORD_TOKEN' was not found in the environment or the config file")
+    }
+
+    pub fn database_url(&self) -> anyhow::Result<&str> {
+        self.database_url
+            .as_deref()
+            .context("'DATABASE_URL' was not found in the environment or the config file")
+    }
+}
+
+/// 環境変数を優先して値を取り出し、解析エラーを蓄積する
+struct Loader<'a> {
+    env: &'a dyn Fn(&str) -> Option<String>,
+    errors: Vec<String>,
+}
+
+impl Loader<'_> {
+    /// 設定ファイルでは数値や真偽値として書く設定
+    fn parsed<T>(&mut self, name: &str, file_value: Option<T>) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let Some(value) = (self.env)(name) else {
+            return file_value;
+        };
+        match value.trim().parse() {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                self.errors
+                    .push(format!("{name}: invalid value {value:?} ({e})"));
+                None
+            }
+        }
+    }
+
+    /// 設定ファイルでも文字列として書く設定
+    fn text<T>(
+        &mut self,
+        name: &str,
+        file_value: Option<String>,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        let value = (self.env)(name).or(file_value)?;
+        match parse(value.trim()) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                self.errors
+                    .push(format!("{name}: invalid value {value:?} ({e})"));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, FileConfig};
+    use crate::services::logging::LogFormat;
+    use chrono::NaiveTime;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn load(env: &[(&str, &str)], file: &str) -> anyhow::Result<Config> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let file: FileConfig = toml::from_str(file)?;
+        Config::from_sources(|name| env.get(name).cloned(), file)
+    }
+
+    #[test]
+    fn defaults_apply_when_nothing_is_set() {
+        let config = load(&[], "").unwrap();
+
+        assert_eq!(config.database_max_connections, 5);
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.timezone, chrono_tz::Asia::Tokyo);
+        assert_eq!(
+            config.notify_time,
+            NaiveTime::from_hms_opt(12, 0, 0).unwrap()
+        );
+        assert_eq!(config.dev_guild_id, None);
+        assert_eq!(config.log_format, LogFormat::Text);
+        assert_eq!(config.shutdown_timeout.as_secs(), 8);
+        assert_eq!(config.command_cooldown, Some(Duration::from_secs(3)));
+        assert!(config.discord_token().is_err());
+    }
+
+    #[test]
+    fn environment_overrides_config_file() {
+        let config = load(
+            &[("PORT", "9090"), ("K_SERVICE", "zunda-bot-rs")],
+            r#"
+            port = 8000
+            timezone = "America/New_York"
+            notify_time = "09:30"
+            dev_guild_id = 42
+            database_url = "memory:"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.port, 9090);
+        assert_eq!(config.timezone, chrono_tz::America::New_York);
+        assert_eq!(
+            config.notify_time,
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap()
+        );
+        assert_eq!(config.dev_guild_id, Some(42));
+        assert_eq!(config.database_url().unwrap(), "memory:");
+        assert_eq!(config.log_format, LogFormat::Json);
+
+        let config = load(
+            &[("COMMAND_COOLDOWN_SECS", "0")],
+            "command_cooldown_secs = 10",
+        )
+        .unwrap();
+        assert_eq!(config.command_cooldown, None);
+    }
+
+    #[test]
+    fn all_invalid_values_are_reported_together() {
+        let error = load(
+            &[
+                ("DATABASE_MAX_CONNECTIONS", "0"),
+                ("PORT", "http"),
+                ("TIMEZONE", "Tokyo"),
+                ("NOTIFY_TIME", "25:00"),
+            ],
+            "",
+        )
+        .err()
+        .unwrap()
+        .to_string();
+
+        assert!(error.contains("DATABASE_MAX_CONNECTIONS must be between 1 and 100"));
+        assert!(error.contains("PORT: invalid value \"http\""));
+        assert!(error.contains("TIMEZONE: invalid value \"Tokyo\""));
+        assert!(error.contains("NOTIFY_TIME: invalid value \"25:00\""));
+        // 設定ファイルの未知のキーはタイプミスとして扱う
+        assert!(load(&[], "prot = 8080").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+// 全データを JSON ファイルにバックアップ・リストアする
+// pg_dump や PostgreSQL のバージョンに依存せずに、ホスト間の移行や同期ミスからの復旧を行うためのもの
+//
+// ファイルの形式は StoreSnapshot で、`version` で互換性を確認する。
+// 設定などのテーブルを追加した場合は StoreSnapshot にフィールドを追加し、
+// 古いバックアップも読めるように `#[serde(default)]` をつけること。
+
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::store_factory::{export_snapshot, import_snapshot};
+use crate::models::data::StoreSnapshot;
+use anyhow::Context as _;
+use std::path::Path;
+
+/// ストアの全データを JSON ファイルに書き出す
+pub async fn backup_to_file(
+    store: &dyn BirthdayStore,
+    path: &Path,
+) -> anyhow::Result<StoreSnapshot> {
+    let snapshot = export_snapshot(store).await?;
+    let json = serde_json::to_vec_pretty(&snapshot)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(snapshot)
+}
+
+/// JSON ファイルの内容をストアに取り込む
+///
+/// 既存の行は上書きし、バックアップに含まれない行は残す。同じファイルを何度リストアしても結果は変わらない。
+pub async fn restore_from_file(
+    store: &dyn BirthdayStore,
+    path: &Path,
+) -> anyhow::Result<StoreSnapshot> {
+    let json = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let snapshot: StoreSnapshot = serde_json::from_slice(&json)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    import_snapshot(store, &snapshot).await?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backup_to_file, restore_from_file};
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::memory_store::MemoryStore;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("zunda-backup-{}-{name}.json", std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn backup_can_be_restored_repeatedly() {
+        let path = temp_path("restore");
+        let birth = NaiveDate::from_ymd_opt(2000, 2, 29).unwrap();
+        let source = MemoryStore::new();
+        source.insert_guild(1, Some("guild")).await.unwrap();
+        source
+            .insert_guild_member(1, 10, Some(birth))
+            .await
+            .unwrap();
+        backup_to_file(&source, &path).await.unwrap();
+
+        let target = MemoryStore::new();
+        restore_from_file(&target, &path).await.unwrap();
+        restore_from_file(&target, &path).await.unwrap();
+
+        assert_eq!(
+            target.select_guilds().await.unwrap(),
+            source.select_guilds().await.unwrap()
+        );
+        assert_eq!(
+            target.select_members().await.unwrap(),
+            source.select_members().await.unwrap()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_version_is_rejected() {
+        let path = temp_path("version");
+        std::fs::write(&path, r#"{"version": 999, "guilds": [], "members": []}"#).unwrap();
+
+        assert!(restore_from_file(&MemoryStore::new(), &path).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,264 @@
+// ギルド・メンバー・誕生日の永続化操作を表す抽象
+// PostgreSQL(ZundaBotDatabase)・SQLite・JSONファイル・インメモリ(MemoryStore) の実装を差し替えて利用する
+
+use crate::models::data::{
+    BirthdayNotification, CelebrationLine, Guild, GuildMember, MigrationStatus, PoolStats,
+};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::time::Duration;
+
+#[async_trait]
+pub trait BirthdayStore: Send + Sync {
+    async fn begin(&self) -> anyhow::Result<Box<dyn BirthdayStoreTransaction>>;
+
+    /// 未適用のマイグレーションを適用する
+    ///
+    /// スキーマを持たないストア(インメモリ・ファイル)では何もしない。
+    async fn migrate_up(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 適用済みのマイグレーションを新しいものから`steps`件取り消し、取り消した件数を返す
+    async fn migrate_down(&self, _steps: usize) -> anyhow::Result<usize> {
+        anyhow::bail!("This store does not support migrations")
+    }
+
+    async fn migration_status(&self) -> anyhow::Result<Vec<MigrationStatus>> {
+        Ok(Vec::new())
+    }
+
+    /// ストアに接続できるかを確認する (レディネスチェック用)
+    async fn ping(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 終了時にコネクションを閉じる (実行中のクエリは完了を待つ)
+    async fn close(&self) {}
+
+    /// コネクションプールの使用状況 (プールを持たないストアでは `None`)
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+
+    /// `name` のリースを `holder` として取得・延長する。他の holder が期限内のリースを持っている場合は false
+    ///
+    /// 複数のプロセスから共有されないストア (インメモリ・ファイル) では常に取得できる。
+    async fn try_acquire_lease(
+        &self,
+        _name: &str,
+        _holder: &str,
+        _ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+
+    /// `holder` が持っているリースを手放し、他のインスタンスがすぐに取得できるようにする
+    async fn release_lease(&self, _name: &str, _holder: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// `scope` に最後に登録したコマンド定義のハッシュ
+    ///
+    /// 再起動をまたいで共有されないストア (インメモリ・ファイル) では記録せず、起動のたびに登録する。
+    async fn select_command_hash(&self, _scope: &str) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    async fn upsert_command_hash(&self, _scope: &str, _hash: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn select_guild_ids(&self) -> anyhow::Result<Vec<i64>>;
+
+    async fn select_guilds(&self) -> anyhow::Result<Vec<Guild>>;
+
+    async fn select_members(&self) -> anyhow::Result<Vec<GuildMember>>;
+
+    async fn select_members_by_guild_id(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>>;
+
+    async fn select_member_by_id(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<Option<GuildMember>>;
+
+    async fn select_guild_by_id(&self, guild_id: i64) -> anyhow::Result<Option<Guild>>;
+
+    async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()>;
+
+    /// ギルドの言語を更新し、ギルドが存在したかどうかを返す
+    async fn update_guild_locale(
+        &self,
+        guild_id: i64,
+        locale: Option<&str>,
+    ) -> anyhow::Result<bool>;
+
+    async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()>;
+
+    async fn delete_guild_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()>;
+
+    async fn insert_guild(&self, guild_id: i64, guild_name: Option<&str>) -> anyhow::Result<()>;
+
+    async fn insert_guild_member(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()>;
+
+    async fn select_notifications(&self) -> anyhow::Result<Vec<BirthdayNotification>>;
+
+    async fn select_notifications_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<BirthdayNotification>>;
+
+    async fn update_notification_reaction_count(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+        reaction_count: i32,
+    ) -> anyhow::Result<()>;
+
+    /// 通知を送信待ちとして登録し、登録できたかどうかを返す
+    ///
+    /// 同じメンバーの同じ年・同じ日付の通知がすでにある場合は何もせず false を返す。
+    /// 解除して別の日付で登録し直した場合は、送信済みの記録を残したまま同じ年にもう一度通知できる。
+    async fn insert_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+    ) -> anyhow::Result<bool>;
+
+    /// 送信待ちの通知のうち、他のインスタンスが確保していないものを `ttl` の間確保して返す
+    ///
+    /// `guild_id` を指定した場合は、そのギルドの通知だけを対象にする。
+    async fn claim_notifications(
+        &self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> anyhow::Result<Vec<BirthdayNotification>>;
+
+    /// `year` 年の送信に失敗した・見送った通知を送信待ちに戻し (試行回数は 0 に戻す)、戻せたかどうかを返す
+    ///
+    /// 送信待ち・送信済みの通知は変更しない。お知らせの日付は変えないため、
+    /// お知らせを送る前の通知は誕生日当日でなければ再び見送られる。
+    async fn rearm_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<bool>;
+
+    async fn select_celebration_lines(&self) -> anyhow::Result<Vec<CelebrationLine>>;
+
+    /// ギルドのお祝いのセリフを番号順に返す
+    async fn select_celebration_lines_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<CelebrationLine>>;
+
+    /// セリフをギルド内の次の番号で追加し、追加した行を返す
+    async fn insert_celebration_line(
+        &self,
+        guild_id: i64,
+        line: &str,
+    ) -> anyhow::Result<CelebrationLine>;
+
+    /// セリフを削除し、削除できたかどうかを返す
+    async fn delete_celebration_line(&self, guild_id: i64, line_no: i32) -> anyhow::Result<bool>;
+}
+
+/// 複数の更新を1つの単位として扱うトランザクション
+///
+/// `commit` せずに破棄した場合はロールバックされる。
+#[async_trait]
+pub trait BirthdayStoreTransaction: Send {
+    async fn commit(self: Box<Self>) -> anyhow::Result<()>;
+
+    async fn insert_guild(&mut self, guild_id: i64, guild_name: Option<&str>)
+        -> anyhow::Result<()>;
+
+    async fn insert_guild_member(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()>;
+
+    /// 誕生日が未登録(NULL)の場合のみ更新し、更新できたかどうかを返す
+    async fn update_member_birth_if_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> anyhow::Result<bool>;
+
+    /// 誕生日の列だけを上書きし (`None` の場合は解除)、値が変わったかどうかを返す
+    ///
+    /// 最終通知日は変更しない。同じ値の場合は更新せず false を返す。
+    async fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<bool>;
+
+    /// 誕生日が登録済みの場合のみ誕生日と最終通知日をNULLに更新し、更新できたかどうかを返す
+    async fn update_member_birth_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<bool>;
+
+    /// ギルドを追加し、すでに存在する場合はギルド名と言語を上書きする
+    async fn upsert_guild(&mut self, guild: &Guild) -> anyhow::Result<()>;
+
+    /// メンバーを追加し、すでに存在する場合は誕生日と最終通知日を上書きする
+    async fn upsert_guild_member(&mut self, member: &GuildMember) -> anyhow::Result<()>;
+
+    async fn update_guild_member_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        last_notified: NaiveDate,
+    ) -> anyhow::Result<()>;
+
+    /// 最終通知日だけをNULLに更新する (誕生日は変更しない)
+    async fn update_member_last_notified_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()>;
+
+    /// 通知を追加し、すでに存在する場合は送信状況を上書きする (確保の期限は変更しない)
+    async fn upsert_notification(
+        &mut self,
+        notification: &BirthdayNotification,
+    ) -> anyhow::Result<()>;
+
+    /// `year` 年の通知を送信状況を問わず削除する
+    async fn delete_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<()>;
+
+    /// メンバーの送信済みでない通知 (送信待ち・見送り・失敗) を、年を問わずすべて削除する
+    ///
+    /// 誕生日が変わった後に、古い日付の通知が残って新しい日付の通知を妨げないようにする。
+    async fn delete_unsent_notifications(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()>;
+
+    /// セリフを追加し、すでに存在する場合は内容を上書きする
+    async fn upsert_celebration_line(&mut self, line: &CelebrationLine) -> anyhow::Result<()>;
+}
@@ -0,0 +1,138 @@
+// ボットが利用する Discord API 操作の抽象
+// 本番では serenity の Http、テストでは記録用のフェイクを差し替えて利用する
+
+use crate::models::domain::{MyChannel, MyMemberProfile, OutgoingMessage};
+use async_trait::async_trait;
+use poise::serenity_prelude::{
+    ChannelId, ChannelType, CreateEmbed, CreateMessage, GuildId, Http, MessageId, Nonce,
+    ReactionType, UserId,
+};
+
+#[async_trait]
+pub trait DiscordGateway: Send + Sync {
+    /// ボットが参加しているギルドIDのリストを取得
+    async fn get_guild_ids(&self) -> anyhow::Result<Vec<GuildId>>;
+
+    async fn get_guild_name(&self, guild_id: GuildId) -> anyhow::Result<String>;
+
+    async fn get_guild_member_ids(&self, guild_id: GuildId) -> anyhow::Result<Vec<UserId>>;
+
+    async fn get_guild_channels(&self, guild_id: GuildId) -> anyhow::Result<Vec<MyChannel>>;
+
+    async fn get_member_profile(
+        &self,
+        guild_id: GuildId,
+        member_id: UserId,
+    ) -> anyhow::Result<MyMemberProfile>;
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<MessageId>;
+
+    async fn react(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: &str,
+    ) -> anyhow::Result<()>;
+
+    /// メッセージについたリアクションの数 (ボット自身のものを除く)
+    async fn get_reaction_count(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> anyhow::Result<u64>;
+}
+
+#[async_trait]
+impl DiscordGateway for Http {
+    async fn get_guild_ids(&self) -> anyhow::Result<Vec<GuildId>> {
+        let guilds = self.get_guilds(None, None).await?;
+        Ok(guilds.into_iter().map(|g| g.id).collect())
+    }
+
+    async fn get_guild_name(&self, guild_id: GuildId) -> anyhow::Result<String> {
+        let partial_guild = self.get_guild(guild_id).await?;
+        Ok(partial_guild.name)
+    }
+
+    async fn get_guild_member_ids(&self, guild_id: GuildId) -> anyhow::Result<Vec<UserId>> {
+        let members = guild_id.members(self, None, None).await?;
+        Ok(members.into_iter().map(|member| member.user.id).collect())
+    }
+
+    async fn get_guild_channels(&self, guild_id: GuildId) -> anyhow::Result<Vec<MyChannel>> {
+        let channels = guild_id.channels(self).await?;
+        Ok(channels
+            .into_values()
+            .map(|channel| MyChannel {
+                id: channel.id,
+                name: channel.name,
+                is_text: channel.kind == ChannelType::Text,
+            })
+            .collect())
+    }
+
+    async fn get_member_profile(
+        &self,
+        guild_id: GuildId,
+        member_id: UserId,
+    ) -> anyhow::Result<MyMemberProfile> {
+        let member = guild_id.member(self, member_id).await?;
+        Ok(MyMemberProfile {
+            display_name: member.display_name().to_string(),
+            avatar_url: member.user.avatar_url(),
+        })
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<MessageId> {
+        let mut builder = CreateMessage::new().content(message.content);
+        if let Some(embed) = message.embed {
+            builder = builder.embed(
+                CreateEmbed::new()
+                    .title(embed.title)
+                    .thumbnail(embed.thumbnail.unwrap_or_default())
+                    .description(embed.description),
+            );
+        }
+        if let Some(reply_to) = message.reply_to {
+            builder = builder.reference_message((channel_id, reply_to));
+        }
+        if let Some(nonce) = message.nonce {
+            builder = builder.nonce(Nonce::String(nonce)).enforce_nonce(true);
+        }
+        let msg = channel_id.send_message(self, builder).await?;
+        Ok(msg.id)
+    }
+
+    async fn react(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: &str,
+    ) -> anyhow::Result<()> {
+        channel_id
+            .create_reaction(self, message_id, ReactionType::Unicode(emoji.to_string()))
+            .await?;
+        Ok(())
+    }
+
+    async fn get_reaction_count(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> anyhow::Result<u64> {
+        let message = channel_id.message(self, message_id).await?;
+        Ok(message
+            .reactions
+            .iter()
+            .map(|reaction| reaction.count - u64::from(reaction.me))
+            .sum())
+    }
+}
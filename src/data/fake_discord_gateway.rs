@@ -0,0 +1,242 @@
+// テスト用の DiscordGateway 実装
+// ネットワークに接続せず、ギルドの状態を手元で組み立てて送信内容を記録する
+
+use crate::data::discord_gateway::DiscordGateway;
+use crate::models::domain::{MyChannel, MyMemberProfile, OutgoingMessage};
+use async_trait::async_trait;
+use poise::serenity_prelude::{ChannelId, GuildId, MessageId, UserId};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// フェイクに対して行われた書き込み操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscordCall {
+    SendMessage {
+        channel_id: ChannelId,
+        message_id: MessageId,
+        message: OutgoingMessage,
+    },
+    React {
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: String,
+    },
+}
+
+#[derive(Default)]
+struct FakeGuild {
+    name: String,
+    members: BTreeMap<UserId, MyMemberProfile>,
+    channels: Vec<MyChannel>,
+    is_unavailable: bool,
+}
+
+#[derive(Default)]
+struct FakeState {
+    guilds: BTreeMap<GuildId, FakeGuild>,
+    is_send_failing: bool,
+    is_react_failing: bool,
+    calls: Vec<DiscordCall>,
+    last_message_id: u64,
+    /// メッセージごとの、ボット以外がつけたリアクションの数
+    reaction_counts: BTreeMap<MessageId, u64>,
+}
+
+#[derive(Default)]
+pub struct FakeDiscordGateway {
+    state: Mutex<FakeState>,
+}
+
+impl FakeDiscordGateway {
+    pub fn new() -> Self {
+        FakeDiscordGateway::default()
+    }
+
+    pub fn add_guild(&self, guild_id: u64, name: &str) {
+        self.state.lock().unwrap().guilds.insert(
+            GuildId::new(guild_id),
+            FakeGuild {
+                name: name.to_string(),
+                ..FakeGuild::default()
+            },
+        );
+    }
+
+    pub fn add_member(&self, guild_id: u64, member_id: u64, display_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let guild = state.guilds.entry(GuildId::new(guild_id)).or_default();
+        guild.members.insert(
+            UserId::new(member_id),
+            MyMemberProfile {
+                display_name: display_name.to_string(),
+                avatar_url: None,
+            },
+        );
+    }
+
+    pub fn add_text_channel(&self, guild_id: u64, channel_id: u64, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let guild = state.guilds.entry(GuildId::new(guild_id)).or_default();
+        guild.channels.push(MyChannel {
+            id: ChannelId::new(channel_id),
+            name: name.to_string(),
+            is_text: true,
+        });
+    }
+
+    /// 指定したギルドに関する取得操作をすべて失敗させる
+    pub fn make_guild_unavailable(&self, guild_id: u64) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .guilds
+            .entry(GuildId::new(guild_id))
+            .or_default()
+            .is_unavailable = true;
+    }
+
+    /// メッセージ送信とリアクションをすべて失敗させる
+    pub fn make_send_failing(&self) {
+        self.state.lock().unwrap().is_send_failing = true;
+    }
+
+    /// メッセージにボット以外のメンバーのリアクションがついた状態にする
+    pub fn set_reaction_count(&self, message_id: u64, count: u64) {
+        self.state
+            .lock()
+            .unwrap()
+            .reaction_counts
+            .insert(MessageId::new(message_id), count);
+    }
+
+    /// リアクションだけを失敗させる (メッセージの送信は成功する)
+    pub fn make_react_failing(&self) {
+        self.state.lock().unwrap().is_react_failing = true;
+    }
+
+    pub fn calls(&self) -> Vec<DiscordCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    pub fn sent_messages(&self) -> Vec<OutgoingMessage> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                DiscordCall::SendMessage { message, .. } => Some(message),
+                DiscordCall::React { .. } => None,
+            })
+            .collect()
+    }
+
+    fn with_guild<T>(
+        &self,
+        guild_id: GuildId,
+        f: impl FnOnce(&FakeGuild) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let state = self.state.lock().unwrap();
+        match state.guilds.get(&guild_id) {
+            Some(guild) if !guild.is_unavailable => f(guild),
+            Some(_) => anyhow::bail!("guild {guild_id} is unavailable"),
+            None => anyhow::bail!("Unknown Guild: {guild_id}"),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscordGateway for FakeDiscordGateway {
+    async fn get_guild_ids(&self) -> anyhow::Result<Vec<GuildId>> {
+        Ok(self.state.lock().unwrap().guilds.keys().copied().collect())
+    }
+
+    async fn get_guild_name(&self, guild_id: GuildId) -> anyhow::Result<String> {
+        self.with_guild(guild_id, |guild| Ok(guild.name.clone()))
+    }
+
+    async fn get_guild_member_ids(&self, guild_id: GuildId) -> anyhow::Result<Vec<UserId>> {
+        self.with_guild(guild_id, |guild| {
+            Ok(guild.members.keys().copied().collect())
+        })
+    }
+
+    async fn get_guild_channels(&self, guild_id: GuildId) -> anyhow::Result<Vec<MyChannel>> {
+        self.with_guild(guild_id, |guild| Ok(guild.channels.clone()))
+    }
+
+    async fn get_member_profile(
+        &self,
+        guild_id: GuildId,
+        member_id: UserId,
+    ) -> anyhow::Result<MyMemberProfile> {
+        self.with_guild(guild_id, |guild| {
+            guild
+                .members
+                .get(&member_id)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Unknown Member: {member_id}"))
+        })
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<MessageId> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_send_failing {
+            anyhow::bail!("Missing Permissions");
+        }
+        // 本物の Discord と同様に、同じ nonce のメッセージは新しく投稿せずに既存のものを返す
+        if let Some(nonce) = &message.nonce {
+            let sent = state.calls.iter().find_map(|call| match call {
+                DiscordCall::SendMessage {
+                    message_id,
+                    message: sent,
+                    ..
+                } if sent.nonce.as_ref() == Some(nonce) => Some(*message_id),
+                _ => None,
+            });
+            if let Some(message_id) = sent {
+                return Ok(message_id);
+            }
+        }
+        state.last_message_id += 1;
+        let message_id = MessageId::new(state.last_message_id);
+        state.calls.push(DiscordCall::SendMessage {
+            channel_id,
+            message_id,
+            message,
+        });
+        Ok(message_id)
+    }
+
+    async fn react(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        emoji: &str,
+    ) -> anyhow::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_send_failing || state.is_react_failing {
+            anyhow::bail!("Missing Permissions");
+        }
+        state.calls.push(DiscordCall::React {
+            channel_id,
+            message_id,
+            emoji: emoji.to_string(),
+        });
+        Ok(())
+    }
+
+    async fn get_reaction_count(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> anyhow::Result<u64> {
+        self.state
+            .lock()
+            .unwrap()
+            .reaction_counts
+            .get(&message_id)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Unknown Message: {message_id}"))
+    }
+}
@@ -0,0 +1,504 @@
+// JSONファイルにデータを保存する BirthdayStore の実装
+// 小規模なセルフホスト環境で、DBサーバーを用意せずに運用するためのもの
+//
+// データはメモリ上の MemoryState で管理し、変更のたびにファイル全体を書き出す。
+// 書き出しは一時ファイルへの書き込みとリネームで行い、途中で落ちても元のファイルが壊れないようにしている。
+// 同じファイルを複数のプロセスから開かないように、ロックファイルで排他制御する。
+
+use crate::data::birthday_store::{BirthdayStore, BirthdayStoreTransaction};
+use crate::data::memory_store::MemoryState;
+use crate::models::data::{
+    BirthdayNotification, CelebrationLine, Guild, GuildMember, StoreSnapshot,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+pub struct FileStore {
+    path: Arc<PathBuf>,
+    state: Arc<Mutex<MemoryState>>,
+    _lock: LockFile,
+}
+
+/// トランザクション中はストア全体をロックし、コミット時にまとめてファイルへ書き出す
+struct FileTransaction {
+    path: Arc<PathBuf>,
+    guard: OwnedMutexGuard<MemoryState>,
+    working: MemoryState,
+}
+
+/// `<データファイル>.lock` を作成して保持し、ドロップ時に削除する
+struct LockFile {
+    path: PathBuf,
+}
+
+impl FileStore {
+    /// データファイルを開く。ファイルが存在しない場合は空のストアとして扱う
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let lock = LockFile::acquire(&path)?;
+        let state = match fs::read(&path) {
+            Ok(bytes) => {
+                let snapshot: StoreSnapshot = serde_json::from_slice(&bytes)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                MemoryState::from_snapshot(snapshot)
+                    .with_context(|| format!("Invalid data in {}", path.display()))?
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => MemoryState::default(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        Ok(FileStore {
+            path: Arc::new(path),
+            state: Arc::new(Mutex::new(state)),
+            _lock: lock,
+        })
+    }
+
+    /// 作業用のコピーに変更を加え、ファイルへの書き出しに成功した場合だけ反映する
+    async fn modify<T>(
+        &self,
+        f: impl FnOnce(&mut MemoryState) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut state = self.state.lock().await;
+        let mut working = state.clone();
+        let result = f(&mut working)?;
+        write_snapshot(&self.path, &working)?;
+        *state = working;
+        Ok(result)
+    }
+}
+
+/// 一時ファイルに書き込んでからリネームし、データファイルを置き換える
+fn write_snapshot(path: &Path, state: &MemoryState) -> anyhow::Result<()> {
+    let json = serde_json::to_vec_pretty(&state.to_snapshot())?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = File::create(&tmp_path)
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    file.write_all(&json)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+impl LockFile {
+    fn acquire(data_path: &Path) -> anyhow::Result<Self> {
+        let mut path = data_path.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => anyhow::bail!(
+                "{} is locked by another process. If no other process is running, remove {}",
+                data_path.display(),
+                path.display()
+            ),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {}", path.display()));
+            }
+        };
+        // どのプロセスがロックしているか確認できるように PID を書いておく
+        writeln!(file, "{}", std::process::id())?;
+        Ok(LockFile { path })
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            tracing::warn!("Failed to remove lock file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl BirthdayStore for FileStore {
+    async fn begin(&self) -> anyhow::Result<Box<dyn BirthdayStoreTransaction>> {
+        let guard = self.state.clone().lock_owned().await;
+        let working = guard.clone();
+        Ok(Box::new(FileTransaction {
+            path: self.path.clone(),
+            guard,
+            working,
+        }))
+    }
+
+    async fn select_guild_ids(&self) -> anyhow::Result<Vec<i64>> {
+        Ok(self.state.lock().await.select_guild_ids())
+    }
+
+    async fn select_guilds(&self) -> anyhow::Result<Vec<Guild>> {
+        Ok(self.state.lock().await.select_guilds())
+    }
+
+    async fn select_members(&self) -> anyhow::Result<Vec<GuildMember>> {
+        Ok(self.state.lock().await.select_members())
+    }
+
+    async fn select_members_by_guild_id(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>> {
+        Ok(self.state.lock().await.select_members_by_guild_id(guild_id))
+    }
+
+    async fn select_member_by_id(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<Option<GuildMember>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .select_member_by_id(guild_id, member_id))
+    }
+
+    async fn select_guild_by_id(&self, guild_id: i64) -> anyhow::Result<Option<Guild>> {
+        Ok(self.state.lock().await.select_guild_by_id(guild_id))
+    }
+
+    async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()> {
+        self.modify(|state| {
+            state.update_guild(guild_id, guild_name);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_guild_locale(
+        &self,
+        guild_id: i64,
+        locale: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        self.modify(|state| Ok(state.update_guild_locale(guild_id, locale)))
+            .await
+    }
+
+    async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()> {
+        self.modify(|state| {
+            state.delete_guild(guild_id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_guild_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
+        self.modify(|state| {
+            state.delete_guild_member(guild_id, member_id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn insert_guild(&self, guild_id: i64, guild_name: Option<&str>) -> anyhow::Result<()> {
+        self.modify(|state| state.insert_guild(guild_id, guild_name))
+            .await
+    }
+
+    async fn insert_guild_member(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.modify(|state| state.insert_guild_member(guild_id, member_id, birth))
+            .await
+    }
+
+    async fn select_notifications(&self) -> anyhow::Result<Vec<BirthdayNotification>> {
+        Ok(self.state.lock().await.select_notifications())
+    }
+
+    async fn select_notifications_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .select_notifications_by_guild_id(guild_id))
+    }
+
+    async fn update_notification_reaction_count(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+        reaction_count: i32,
+    ) -> anyhow::Result<()> {
+        self.modify(|state| {
+            state.update_notification_reaction_count(
+                guild_id,
+                member_id,
+                year,
+                notify_date,
+                reaction_count,
+            );
+            Ok(())
+        })
+        .await
+    }
+
+    async fn insert_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        self.modify(|state| Ok(state.insert_notification(guild_id, member_id, year, notify_date)))
+            .await
+    }
+
+    async fn claim_notifications(
+        &self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        // 確保の期限はファイルに保存しないため、書き出さずにメモリ上の状態だけを更新する
+        Ok(self.state.lock().await.claim_notifications(guild_id, ttl))
+    }
+
+    async fn rearm_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<bool> {
+        self.modify(|state| Ok(state.rearm_notification(guild_id, member_id, year)))
+            .await
+    }
+
+    async fn select_celebration_lines(&self) -> anyhow::Result<Vec<CelebrationLine>> {
+        Ok(self.state.lock().await.select_celebration_lines())
+    }
+
+    async fn select_celebration_lines_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<CelebrationLine>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .select_celebration_lines_by_guild_id(guild_id))
+    }
+
+    async fn insert_celebration_line(
+        &self,
+        guild_id: i64,
+        line: &str,
+    ) -> anyhow::Result<CelebrationLine> {
+        self.modify(|state| Ok(state.insert_celebration_line(guild_id, line)))
+            .await
+    }
+
+    async fn delete_celebration_line(&self, guild_id: i64, line_no: i32) -> anyhow::Result<bool> {
+        self.modify(|state| Ok(state.delete_celebration_line(guild_id, line_no)))
+            .await
+    }
+}
+
+#[async_trait]
+impl BirthdayStoreTransaction for FileTransaction {
+    async fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        let FileTransaction {
+            path,
+            mut guard,
+            working,
+        } = *self;
+        write_snapshot(&path, &working)?;
+        *guard = working;
+        Ok(())
+    }
+
+    async fn insert_guild(
+        &mut self,
+        guild_id: i64,
+        guild_name: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.working.insert_guild(guild_id, guild_name)
+    }
+
+    async fn insert_guild_member(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.working.insert_guild_member(guild_id, member_id, birth)
+    }
+
+    async fn update_member_birth_if_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        Ok(self
+            .working
+            .update_member_birth_if_none(guild_id, member_id, birth))
+    }
+
+    async fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<bool> {
+        Ok(self.working.update_member_birth(guild_id, member_id, birth))
+    }
+
+    async fn update_member_birth_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<bool> {
+        Ok(self.working.update_member_birth_none(guild_id, member_id))
+    }
+
+    async fn upsert_guild(&mut self, guild: &Guild) -> anyhow::Result<()> {
+        self.working.upsert_guild(guild);
+        Ok(())
+    }
+
+    async fn upsert_guild_member(&mut self, member: &GuildMember) -> anyhow::Result<()> {
+        self.working.upsert_guild_member(member)
+    }
+
+    async fn update_guild_member_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        last_notified: NaiveDate,
+    ) -> anyhow::Result<()> {
+        self.working
+            .update_guild_member_last_notified(guild_id, member_id, last_notified);
+        Ok(())
+    }
+
+    async fn update_member_last_notified_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        self.working
+            .update_member_last_notified_none(guild_id, member_id);
+        Ok(())
+    }
+
+    async fn upsert_notification(
+        &mut self,
+        notification: &BirthdayNotification,
+    ) -> anyhow::Result<()> {
+        self.working.upsert_notification(notification);
+        Ok(())
+    }
+
+    async fn delete_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<()> {
+        self.working.delete_notification(guild_id, member_id, year);
+        Ok(())
+    }
+
+    async fn delete_unsent_notifications(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        self.working
+            .delete_unsent_notifications(guild_id, member_id);
+        Ok(())
+    }
+
+    async fn upsert_celebration_line(&mut self, line: &CelebrationLine) -> anyhow::Result<()> {
+        self.working.upsert_celebration_line(line);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileStore;
+    use crate::data::birthday_store::BirthdayStore;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    /// テストごとに別のデータファイルのパスを返す
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zunda-file-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.json"));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2000, month, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn data_is_reloaded_after_reopen() {
+        let path = temp_path("reload");
+        {
+            let store = FileStore::open(&path).unwrap();
+            store.insert_guild(1, Some("guild")).await.unwrap();
+            store.insert_guild_member(1, 10, None).await.unwrap();
+            let mut tx = store.begin().await.unwrap();
+            tx.update_member_birth_if_none(1, 10, date(2, 29))
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+        }
+
+        let store = FileStore::open(&path).unwrap();
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, Some(date(2, 29)));
+        assert_eq!(store.select_guilds().await.unwrap()[0].name, "guild");
+    }
+
+    #[tokio::test]
+    async fn file_cannot_be_opened_twice() {
+        let path = temp_path("lock");
+        let store = FileStore::open(&path).unwrap();
+
+        assert!(FileStore::open(&path).is_err());
+
+        drop(store);
+        assert!(FileStore::open(&path).is_ok());
+    }
+
+    #[tokio::test]
+    async fn failed_or_uncommitted_changes_are_not_written() {
+        let path = temp_path("rollback");
+        {
+            let store = FileStore::open(&path).unwrap();
+            store.insert_guild(1, Some("guild")).await.unwrap();
+            // 存在しないギルドへのメンバー追加は失敗し、ファイルにも残らない
+            assert!(store.insert_guild_member(2, 10, None).await.is_err());
+
+            let mut tx = store.begin().await.unwrap();
+            tx.insert_guild(3, Some("other")).await.unwrap();
+            drop(tx);
+        }
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.select_guild_ids().await.unwrap(), vec![1]);
+        assert!(store.select_members().await.unwrap().is_empty());
+    }
+}
@@ -1,34 +1,51 @@
-use crate::data::zunda_bot_database::ZundaBotDatabase;
-use crate::models::common::Context;
-use crate::models::data::GuildMember;
+use crate::data::birthday_store::{BirthdayStore, BirthdayStoreTransaction};
+use crate::data::discord_gateway::DiscordGateway;
+use crate::models::common::{Context, Error};
+use crate::models::data::{BirthdayNotification, CelebrationLine, Guild, GuildMember};
 use crate::models::domain::{MyGuild, MyGuildMember};
-use chrono::NaiveDate;
-use poise::serenity_prelude::{GuildId, Http};
-use sqlx::PgPool;
+use crate::models::error::BotError;
+use crate::res::messages::Locale;
+use chrono::{Datelike, NaiveDate};
+use poise::serenity_prelude::GuildId;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct GuildRepository {
-    db: ZundaBotDatabase,
-    http: Arc<Http>,
+    db: Arc<dyn BirthdayStore>,
+    discord: Arc<dyn DiscordGateway>,
+}
+
+/// 複数の読み書きをまとめて確定させる作業単位
+///
+/// `commit` せずに破棄した場合、途中の変更はすべて取り消される。
+pub struct GuildUnitOfWork {
+    tx: Box<dyn BirthdayStoreTransaction>,
 }
 
 impl GuildRepository {
-    pub fn new(pool: Arc<PgPool>, http: Arc<Http>) -> anyhow::Result<Self> {
-        let db = ZundaBotDatabase::new(pool)?;
-        Ok(GuildRepository { db, http })
+    pub fn new(
+        db: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> Result<Self, Error> {
+        Ok(GuildRepository { db, discord })
     }
 
-    pub async fn get_all_members(&self) -> anyhow::Result<Vec<GuildMember>> {
+    pub async fn begin(&self) -> Result<GuildUnitOfWork, Error> {
+        let tx = self.db.begin().await?;
+        Ok(GuildUnitOfWork { tx })
+    }
+
+    pub async fn get_all_members(&self) -> Result<Vec<GuildMember>, Error> {
         let all_members = self.db.select_members().await?;
         Ok(all_members)
     }
 
-    pub async fn get_members_by_guild_id(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>> {
+    pub async fn get_members_by_guild_id(&self, guild_id: i64) -> Result<Vec<GuildMember>, Error> {
         let members_by_guid_id = self.db.select_members_by_guild_id(guild_id).await?;
         Ok(members_by_guid_id)
     }
 
-    pub async fn add_guild(&self, guild_id: i64, guild_name: Option<&str>) -> anyhow::Result<()> {
+    pub async fn add_guild(&self, guild_id: i64, guild_name: Option<&str>) -> Result<(), Error> {
         self.db.insert_guild(guild_id, guild_name).await?;
         Ok(())
     }
@@ -38,91 +55,195 @@ impl GuildRepository {
         guild_id: i64,
         member_id: i64,
         birth: Option<NaiveDate>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<(), Error> {
         self.db
             .insert_guild_member(guild_id, member_id, birth)
             .await?;
         Ok(())
     }
 
-    pub async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()> {
+    pub async fn delete_guild(&self, guild_id: i64) -> Result<(), Error> {
         self.db.delete_guild(guild_id).await?;
         Ok(())
     }
 
-    pub async fn delete_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
+    pub async fn delete_member(&self, guild_id: i64, member_id: i64) -> Result<(), Error> {
         self.db.delete_guild_member(guild_id, member_id).await?;
         Ok(())
     }
 
-    pub async fn get_guild_ids(&self) -> anyhow::Result<Vec<i64>> {
+    pub async fn get_guild_ids(&self) -> Result<Vec<i64>, Error> {
         let guild_ids = self.db.select_guild_ids().await?;
         Ok(guild_ids)
     }
 
+    pub async fn get_guilds(&self) -> Result<Vec<Guild>, Error> {
+        let guilds = self.db.select_guilds().await?;
+        Ok(guilds)
+    }
+
+    pub async fn get_member(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> Result<Option<GuildMember>, Error> {
+        let member = self.db.select_member_by_id(guild_id, member_id).await?;
+        Ok(member)
+    }
+
     pub async fn get_member_birth(
         &self,
         guild_id: i64,
         member_id: i64,
-    ) -> anyhow::Result<Option<NaiveDate>, anyhow::Error> {
+    ) -> Result<Option<NaiveDate>, Error> {
         let member = self.db.select_member_by_id(guild_id, member_id).await?;
         Ok(member.and_then(|m| m.birth))
     }
 
-    pub async fn update_member_birth(
+    /// メンバーの誕生日を上書きし (`None` の場合は解除)、変更があったかどうかを返す
+    ///
+    /// 管理用 CLI とモデレーター向けのメニューで共通して使う。メンバーが未登録の場合は追加する。
+    /// 誕生日の列だけを1つの文で更新し、最終通知日など他の列は読み書きしない。
+    /// `guild_name` を指定した場合は、ギルドが未登録なら追加する。
+    pub async fn set_member_birth(
         &self,
         guild_id: i64,
+        guild_name: Option<&str>,
         member_id: i64,
-        birth: NaiveDate,
-    ) -> anyhow::Result<()> {
-        self.db
-            .update_member_birth(guild_id, member_id, birth)
-            .await?;
+        birth: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        let mut uow = self.begin().await?;
+        if guild_name.is_some() {
+            uow.add_guild(guild_id, guild_name).await?;
+        }
+        uow.add_member(guild_id, member_id, None).await?;
+        let changed = uow.update_member_birth(guild_id, member_id, birth).await?;
+        if changed {
+            // 古い日付のまま失敗・見送りになった通知が、新しい日付の通知を妨げないようにする
+            uow.delete_unsent_notifications(guild_id, member_id).await?;
+        }
+        uow.commit().await?;
+        Ok(changed)
+    }
 
+    pub async fn update_guild(&self, guild_id: i64, guild_name: &str) -> Result<(), Error> {
+        self.db.update_guild(guild_id, guild_name).await?;
         Ok(())
     }
 
-    pub async fn reset_member_birth(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
-        self.db
-            .update_member_birth_none(guild_id, member_id)
+    /// その年の誕生日通知を送信待ちとして登録する。すでに登録済みの場合は false を返す
+    pub async fn enqueue_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        notify_date: NaiveDate,
+    ) -> Result<bool, Error> {
+        let inserted = self
+            .db
+            .insert_notification(guild_id, member_id, notify_date.year(), notify_date)
             .await?;
+        Ok(inserted)
+    }
 
-        Ok(())
+    pub async fn claim_notifications(
+        &self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> Result<Vec<BirthdayNotification>, Error> {
+        let notifications = self.db.claim_notifications(guild_id, ttl).await?;
+        Ok(notifications)
     }
 
-    pub async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()> {
-        self.db.update_guild(guild_id, guild_name).await?;
+    /// 失敗・見送りになった通知を送信待ちに戻す。戻せなかった (送信待ち・送信済み・存在しない) 場合は false を返す
+    ///
+    /// 戻した通知は、稼働中のボットが次に送信待ちの通知を再開する際に送信される。
+    pub async fn rearm_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> Result<bool, Error> {
+        let rearmed = self
+            .db
+            .rearm_notification(guild_id, member_id, year)
+            .await?;
+        Ok(rearmed)
+    }
+
+    /// 通知の送信状況を記録する
+    pub async fn save_notification(
+        &self,
+        notification: &BirthdayNotification,
+    ) -> Result<(), Error> {
+        let mut tx = self.db.begin().await?;
+        tx.upsert_notification(notification).await?;
+        tx.commit().await?;
         Ok(())
     }
 
-    pub async fn update_last_notified(
+    pub async fn get_all_notifications(&self) -> Result<Vec<BirthdayNotification>, Error> {
+        let notifications = self.db.select_notifications().await?;
+        Ok(notifications)
+    }
+
+    pub async fn get_notifications_by_guild_id(
         &self,
         guild_id: i64,
-        member_id: i64,
-        last_notified: NaiveDate,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Vec<BirthdayNotification>, Error> {
+        let notifications = self.db.select_notifications_by_guild_id(guild_id).await?;
+        Ok(notifications)
+    }
+
+    pub async fn update_reaction_count(
+        &self,
+        notification: &BirthdayNotification,
+    ) -> Result<(), Error> {
         self.db
-            .update_guild_member_last_notified(guild_id, member_id, last_notified)
+            .update_notification_reaction_count(
+                notification.guild_id,
+                notification.member_id,
+                notification.year,
+                notification.notify_date,
+                notification.reaction_count,
+            )
             .await?;
         Ok(())
     }
 
-    pub async fn fetch_my_guild(&self, guild_id: &GuildId) -> anyhow::Result<MyGuild> {
-        let partial_guild = self.http.get_guild(*guild_id).await?;
-        let members = partial_guild
-            .members(&*self.http, None, None)
+    /// 送信を終えた通知と、メンバーの最終通知日を1つのトランザクションで記録する
+    pub async fn complete_notification(
+        &self,
+        notification: &BirthdayNotification,
+    ) -> Result<(), Error> {
+        let mut tx = self.db.begin().await?;
+        tx.upsert_notification(notification).await?;
+        tx.update_guild_member_last_notified(
+            notification.guild_id,
+            notification.member_id,
+            notification.notify_date,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn fetch_my_guild(&self, guild_id: &GuildId) -> Result<MyGuild, Error> {
+        let name = self.discord.get_guild_name(*guild_id).await?;
+        let members = self
+            .discord
+            .get_guild_member_ids(*guild_id)
             .await?
             .into_iter()
-            .map(|member| MyGuildMember {
-                guild_id: i64::from(member.guild_id),
-                member_id: i64::from(member.user.id),
+            .map(|member_id| MyGuildMember {
+                guild_id: i64::from(*guild_id),
+                member_id: i64::from(member_id),
                 birth: None,
             })
             .collect::<Vec<MyGuildMember>>();
 
         Ok(MyGuild {
-            id: i64::from(partial_guild.id),
-            name: partial_guild.name,
+            id: i64::from(*guild_id),
+            name,
             members,
         })
     }
@@ -130,19 +251,206 @@ impl GuildRepository {
     pub async fn fetch_guild_id_from_command(
         &self,
         poise_ctx: Context<'_>,
-    ) -> anyhow::Result<GuildId> {
-        match poise_ctx.guild_id() {
-            Some(id) => Ok(id),
-            None => {
-                let err_msg = "Could not retrieve the Guild ID.";
-                tracing::error!(err_msg);
-                Err(anyhow::anyhow!(err_msg))
-            }
+    ) -> Result<GuildId, Error> {
+        // DM ではギルドIDがない
+        poise_ctx.guild_id().ok_or(BotError::GuildOnly)
+    }
+
+    /// ギルドに設定した言語 (未設定・未登録のギルドの場合は日本語)
+    pub async fn get_guild_locale(&self, guild_id: i64) -> Result<Locale, Error> {
+        let locale = self
+            .db
+            .select_guild_by_id(guild_id)
+            .await?
+            .and_then(|guild| guild.locale)
+            .map(|locale| locale.parse())
+            .transpose()?;
+        Ok(locale.unwrap_or_default())
+    }
+
+    /// ギルドの言語を設定する (`None` の場合は設定を消して日本語に戻す)
+    pub async fn set_guild_locale(
+        &self,
+        guild_id: i64,
+        locale: Option<Locale>,
+    ) -> Result<(), Error> {
+        let updated = self
+            .db
+            .update_guild_locale(guild_id, locale.as_ref().map(Locale::as_str))
+            .await?;
+        if !updated {
+            return Err(anyhow::anyhow!("Guild {guild_id} was not found").into());
         }
+        Ok(())
+    }
+
+    pub async fn get_celebration_lines(
+        &self,
+        guild_id: i64,
+    ) -> Result<Vec<CelebrationLine>, Error> {
+        let lines = self
+            .db
+            .select_celebration_lines_by_guild_id(guild_id)
+            .await?;
+        Ok(lines)
+    }
+
+    pub async fn add_celebration_line(
+        &self,
+        guild_id: i64,
+        line: &str,
+    ) -> Result<CelebrationLine, Error> {
+        let line = self.db.insert_celebration_line(guild_id, line).await?;
+        Ok(line)
     }
 
-    pub async fn fetch_my_guild_ids(&self) -> anyhow::Result<Vec<GuildId>> {
-        let guilds = self.http.get_guilds(None, None).await?;
-        Ok(guilds.into_iter().map(|g| g.id).collect())
+    pub async fn remove_celebration_line(
+        &self,
+        guild_id: i64,
+        line_no: i32,
+    ) -> Result<bool, Error> {
+        let is_removed = self.db.delete_celebration_line(guild_id, line_no).await?;
+        Ok(is_removed)
+    }
+
+    /// コマンドへの返信に使う言語
+    ///
+    /// 実行したユーザーの Discord の表示言語を優先し、対応していない言語の場合はギルドの設定に従う。
+    pub async fn fetch_locale_from_command(&self, poise_ctx: Context<'_>) -> Locale {
+        if let Some(locale) = poise_ctx.locale().and_then(Locale::from_discord) {
+            return locale;
+        }
+        let Some(guild_id) = poise_ctx.guild_id() else {
+            return Locale::default();
+        };
+        self.get_guild_locale(i64::from(guild_id))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to get guild locale: {}", e);
+                Locale::default()
+            })
+    }
+
+    pub async fn fetch_my_guild_ids(&self) -> Result<Vec<GuildId>, Error> {
+        Ok(self.discord.get_guild_ids().await?)
+    }
+}
+
+impl GuildUnitOfWork {
+    pub async fn commit(self) -> Result<(), Error> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn add_guild(
+        &mut self,
+        guild_id: i64,
+        guild_name: Option<&str>,
+    ) -> Result<(), Error> {
+        self.tx.insert_guild(guild_id, guild_name).await?;
+        Ok(())
+    }
+
+    pub async fn add_member(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> Result<(), Error> {
+        self.tx
+            .insert_guild_member(guild_id, member_id, birth)
+            .await?;
+        Ok(())
+    }
+
+    /// 誕生日が未登録の場合のみ登録する
+    ///
+    /// すでに登録済み(または同時に登録された)場合は `false` を返す。
+    pub async fn signup_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> Result<bool, Error> {
+        let updated = self
+            .tx
+            .update_member_birth_if_none(guild_id, member_id, birth)
+            .await?;
+        if updated {
+            self.delete_unsent_notifications(guild_id, member_id)
+                .await?;
+        }
+        Ok(updated)
+    }
+
+    /// 誕生日だけを上書きし、値が変わったかどうかを返す
+    pub async fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        let updated = self
+            .tx
+            .update_member_birth(guild_id, member_id, birth)
+            .await?;
+        Ok(updated)
+    }
+
+    /// 誕生日が登録済みの場合のみ解除する
+    ///
+    /// 最終通知日と送信済みでない通知も消す。送信済みの通知はお祝いの履歴として残す。
+    /// 未登録(または同時に解除された)場合は `false` を返す。
+    pub async fn reset_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> Result<bool, Error> {
+        let updated = self
+            .tx
+            .update_member_birth_none(guild_id, member_id)
+            .await?;
+        if updated {
+            self.delete_unsent_notifications(guild_id, member_id)
+                .await?;
+        }
+        Ok(updated)
+    }
+
+    /// 送信済みでない通知を年を問わず削除する (誕生日が変わった場合に使う)
+    pub async fn delete_unsent_notifications(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> Result<(), Error> {
+        self.tx
+            .delete_unsent_notifications(guild_id, member_id)
+            .await?;
+        Ok(())
+    }
+
+    /// 最終通知日だけを消去する
+    pub async fn clear_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> Result<(), Error> {
+        self.tx
+            .update_member_last_notified_none(guild_id, member_id)
+            .await?;
+        Ok(())
+    }
+
+    /// その年の誕生日通知の記録を削除し、同じ年にもう一度通知できるようにする
+    pub async fn delete_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> Result<(), Error> {
+        self.tx
+            .delete_notification(guild_id, member_id, year)
+            .await?;
+        Ok(())
     }
 }
@@ -0,0 +1,824 @@
+// プロセス内のメモリだけで完結する BirthdayStore の実装
+// DBなしでユースケースを動かすテストなどで利用する
+// file-store feature のファイルストアも、この状態をファイルに書き出して利用する
+
+use crate::data::birthday_store::{BirthdayStore, BirthdayStoreTransaction};
+#[cfg(feature = "file-store")]
+use crate::models::data::StoreSnapshot;
+use crate::models::data::{
+    BirthdayNotification, CelebrationLine, Guild, GuildMember, NotificationStatus,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// guild / guild_member / birthday_notification / guild_celebration_line テーブル相当のデータ
+///
+/// 制約(ギルド名の NOT NULL、メンバーからギルドへの外部キー)は PostgreSQL のスキーマに合わせている。
+#[derive(Debug, Default, Clone)]
+pub struct MemoryState {
+    guilds: BTreeMap<i64, Guild>,
+    members: BTreeMap<(i64, i64), GuildMember>,
+    notifications: BTreeMap<NotificationKey, BirthdayNotification>,
+    celebration_lines: BTreeMap<(i64, i32), CelebrationLine>,
+    /// 通知を確保している期限 (プロセス内でのみ意味を持つため、スナップショットには含めない)
+    claims: BTreeMap<NotificationKey, DateTime<Utc>>,
+}
+
+/// birthday_notification の主キー (ギルド・メンバー・年・通知日)
+type NotificationKey = (i64, i64, i32, NaiveDate);
+
+fn notification_key(notification: &BirthdayNotification) -> NotificationKey {
+    (
+        notification.guild_id,
+        notification.member_id,
+        notification.year,
+        notification.notify_date,
+    )
+}
+
+impl MemoryState {
+    /// スナップショットから状態を復元する
+    ///
+    /// 各行は upsert で取り込むため、制約に違反するデータが含まれる場合はエラーになる。
+    #[cfg(feature = "file-store")]
+    pub(crate) fn from_snapshot(snapshot: StoreSnapshot) -> anyhow::Result<Self> {
+        if snapshot.version != StoreSnapshot::CURRENT_VERSION {
+            anyhow::bail!(
+                "unsupported snapshot version: {} (expected {})",
+                snapshot.version,
+                StoreSnapshot::CURRENT_VERSION
+            );
+        }
+        let mut state = MemoryState::default();
+        for guild in &snapshot.guilds {
+            state.upsert_guild(guild);
+        }
+        for member in &snapshot.members {
+            state.upsert_guild_member(member)?;
+        }
+        for notification in &snapshot.notifications {
+            state.upsert_notification(notification);
+        }
+        for line in &snapshot.celebration_lines {
+            state.upsert_celebration_line(line);
+        }
+        Ok(state)
+    }
+
+    #[cfg(feature = "file-store")]
+    pub(crate) fn to_snapshot(&self) -> StoreSnapshot {
+        StoreSnapshot {
+            version: StoreSnapshot::CURRENT_VERSION,
+            guilds: self.select_guilds(),
+            members: self.select_members(),
+            notifications: self.select_notifications(),
+            celebration_lines: self.select_celebration_lines(),
+        }
+    }
+
+    pub(crate) fn select_guild_ids(&self) -> Vec<i64> {
+        self.guilds.keys().copied().collect()
+    }
+
+    pub(crate) fn select_guilds(&self) -> Vec<Guild> {
+        self.guilds.values().cloned().collect()
+    }
+
+    pub(crate) fn select_guild_by_id(&self, guild_id: i64) -> Option<Guild> {
+        self.guilds.get(&guild_id).cloned()
+    }
+
+    pub(crate) fn select_members(&self) -> Vec<GuildMember> {
+        self.members.values().cloned().collect()
+    }
+
+    pub(crate) fn select_members_by_guild_id(&self, guild_id: i64) -> Vec<GuildMember> {
+        self.members
+            .values()
+            .filter(|member| member.guild_id == guild_id)
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn select_member_by_id(&self, guild_id: i64, member_id: i64) -> Option<GuildMember> {
+        self.members.get(&(guild_id, member_id)).cloned()
+    }
+
+    pub(crate) fn update_guild(&mut self, guild_id: i64, guild_name: &str) {
+        if let Some(guild) = self.guilds.get_mut(&guild_id) {
+            guild.name = guild_name.to_string();
+        }
+    }
+
+    pub(crate) fn update_guild_locale(&mut self, guild_id: i64, locale: Option<&str>) -> bool {
+        match self.guilds.get_mut(&guild_id) {
+            Some(guild) => {
+                guild.locale = locale.map(str::to_string);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn update_guild_member_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        last_notified: NaiveDate,
+    ) {
+        if let Some(member) = self.members.get_mut(&(guild_id, member_id)) {
+            member.last_notified = Some(last_notified);
+        }
+    }
+
+    pub(crate) fn delete_guild(&mut self, guild_id: i64) {
+        self.members.retain(|&(id, _), _| id != guild_id);
+        self.celebration_lines.retain(|&(id, _), _| id != guild_id);
+        self.guilds.remove(&guild_id);
+    }
+
+    pub(crate) fn delete_guild_member(&mut self, guild_id: i64, member_id: i64) {
+        self.members.remove(&(guild_id, member_id));
+    }
+
+    pub(crate) fn insert_guild(
+        &mut self,
+        guild_id: i64,
+        guild_name: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if self.guilds.contains_key(&guild_id) {
+            return Ok(());
+        }
+        let guild_name = guild_name
+            .ok_or_else(|| anyhow::anyhow!("guild name must not be null (guild_id={guild_id})"))?;
+        self.guilds.insert(
+            guild_id,
+            Guild {
+                guild_id,
+                name: guild_name.to_string(),
+                locale: None,
+            },
+        );
+        Ok(())
+    }
+
+    pub(crate) fn insert_guild_member(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        if !self.guilds.contains_key(&guild_id) {
+            anyhow::bail!("guild {guild_id} does not exist (member_id={member_id})");
+        }
+        self.members
+            .entry((guild_id, member_id))
+            .or_insert(GuildMember {
+                guild_id,
+                member_id,
+                birth,
+                last_notified: None,
+            });
+        Ok(())
+    }
+
+    pub(crate) fn update_member_birth_if_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> bool {
+        match self.members.get_mut(&(guild_id, member_id)) {
+            Some(member) if member.birth.is_none() => {
+                member.birth = Some(birth);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> bool {
+        match self.members.get_mut(&(guild_id, member_id)) {
+            Some(member) if member.birth != birth => {
+                member.birth = birth;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn update_member_birth_none(&mut self, guild_id: i64, member_id: i64) -> bool {
+        match self.members.get_mut(&(guild_id, member_id)) {
+            Some(member) if member.birth.is_some() => {
+                member.birth = None;
+                member.last_notified = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn update_member_last_notified_none(&mut self, guild_id: i64, member_id: i64) {
+        if let Some(member) = self.members.get_mut(&(guild_id, member_id)) {
+            member.last_notified = None;
+        }
+    }
+
+    pub(crate) fn upsert_guild(&mut self, guild: &Guild) {
+        self.guilds.insert(guild.guild_id, guild.clone());
+    }
+
+    pub(crate) fn upsert_guild_member(&mut self, member: &GuildMember) -> anyhow::Result<()> {
+        if !self.guilds.contains_key(&member.guild_id) {
+            anyhow::bail!(
+                "guild {} does not exist (member_id={})",
+                member.guild_id,
+                member.member_id
+            );
+        }
+        self.members
+            .insert((member.guild_id, member.member_id), member.clone());
+        Ok(())
+    }
+
+    pub(crate) fn select_notifications(&self) -> Vec<BirthdayNotification> {
+        let mut notifications: Vec<_> = self.notifications.values().cloned().collect();
+        notifications.sort_by_key(|n| (n.notify_date, n.guild_id, n.member_id));
+        notifications
+    }
+
+    pub(crate) fn select_notifications_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> Vec<BirthdayNotification> {
+        self.select_notifications()
+            .into_iter()
+            .filter(|notification| notification.guild_id == guild_id)
+            .collect()
+    }
+
+    pub(crate) fn update_notification_reaction_count(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+        reaction_count: i32,
+    ) {
+        let key = (guild_id, member_id, year, notify_date);
+        if let Some(notification) = self.notifications.get_mut(&key) {
+            notification.reaction_count = reaction_count;
+        }
+    }
+
+    pub(crate) fn insert_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+    ) -> bool {
+        let key = (guild_id, member_id, year, notify_date);
+        if self.notifications.contains_key(&key) {
+            return false;
+        }
+        self.notifications.insert(
+            key,
+            BirthdayNotification::new(guild_id, member_id, year, notify_date),
+        );
+        true
+    }
+
+    pub(crate) fn claim_notifications(
+        &mut self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> Vec<BirthdayNotification> {
+        let now = Utc::now();
+        let claimed_until = now + ttl;
+        let mut claimed = Vec::new();
+        for (key, notification) in &self.notifications {
+            let is_claimed = self.claims.get(key).is_some_and(|until| *until >= now);
+            if notification.status == NotificationStatus::Pending
+                && !is_claimed
+                && guild_id.is_none_or(|id| notification.guild_id == id)
+            {
+                claimed.push(notification.clone());
+            }
+        }
+        for notification in &claimed {
+            self.claims
+                .insert(notification_key(notification), claimed_until);
+        }
+        claimed
+    }
+
+    /// 条件に合う通知を削除する
+    fn delete_notifications_where(&mut self, predicate: impl Fn(&BirthdayNotification) -> bool) {
+        let keys = self
+            .notifications
+            .iter()
+            .filter(|(_, notification)| predicate(notification))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        for key in keys {
+            self.notifications.remove(&key);
+            self.claims.remove(&key);
+        }
+    }
+
+    pub(crate) fn delete_notification(&mut self, guild_id: i64, member_id: i64, year: i32) {
+        self.delete_notifications_where(|notification| {
+            notification.guild_id == guild_id
+                && notification.member_id == member_id
+                && notification.year == year
+        });
+    }
+
+    pub(crate) fn delete_unsent_notifications(&mut self, guild_id: i64, member_id: i64) {
+        self.delete_notifications_where(|notification| {
+            notification.guild_id == guild_id
+                && notification.member_id == member_id
+                && notification.status != NotificationStatus::Sent
+        });
+    }
+
+    pub(crate) fn rearm_notification(&mut self, guild_id: i64, member_id: i64, year: i32) -> bool {
+        let mut rearmed = false;
+        for (key, notification) in &mut self.notifications {
+            if notification.guild_id == guild_id
+                && notification.member_id == member_id
+                && notification.year == year
+                && matches!(
+                    notification.status,
+                    NotificationStatus::Failed | NotificationStatus::Skipped
+                )
+            {
+                notification.status = NotificationStatus::Pending;
+                notification.attempts = 0;
+                notification.last_error = None;
+                notification.completed_at = None;
+                self.claims.remove(key);
+                rearmed = true;
+            }
+        }
+        rearmed
+    }
+
+    pub(crate) fn upsert_notification(&mut self, notification: &BirthdayNotification) {
+        self.notifications
+            .insert(notification_key(notification), notification.clone());
+    }
+
+    pub(crate) fn select_celebration_lines(&self) -> Vec<CelebrationLine> {
+        self.celebration_lines.values().cloned().collect()
+    }
+
+    pub(crate) fn select_celebration_lines_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> Vec<CelebrationLine> {
+        self.celebration_lines
+            .range((guild_id, i32::MIN)..=(guild_id, i32::MAX))
+            .map(|(_, line)| line.clone())
+            .collect()
+    }
+
+    pub(crate) fn insert_celebration_line(&mut self, guild_id: i64, line: &str) -> CelebrationLine {
+        let line_no = self
+            .select_celebration_lines_by_guild_id(guild_id)
+            .last()
+            .map_or(1, |last| last.line_no + 1);
+        let line = CelebrationLine {
+            guild_id,
+            line_no,
+            line: line.to_string(),
+        };
+        self.upsert_celebration_line(&line);
+        line
+    }
+
+    pub(crate) fn delete_celebration_line(&mut self, guild_id: i64, line_no: i32) -> bool {
+        self.celebration_lines
+            .remove(&(guild_id, line_no))
+            .is_some()
+    }
+
+    pub(crate) fn upsert_celebration_line(&mut self, line: &CelebrationLine) {
+        self.celebration_lines
+            .insert((line.guild_id, line.line_no), line.clone());
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+/// トランザクション中はストア全体をロックし、作業用のコピーに対して変更を行う
+struct MemoryTransaction {
+    guard: OwnedMutexGuard<MemoryState>,
+    working: MemoryState,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+#[async_trait]
+impl BirthdayStore for MemoryStore {
+    async fn begin(&self) -> anyhow::Result<Box<dyn BirthdayStoreTransaction>> {
+        let guard = self.state.clone().lock_owned().await;
+        let working = guard.clone();
+        Ok(Box::new(MemoryTransaction { guard, working }))
+    }
+
+    async fn select_guild_ids(&self) -> anyhow::Result<Vec<i64>> {
+        Ok(self.state.lock().await.select_guild_ids())
+    }
+
+    async fn select_guilds(&self) -> anyhow::Result<Vec<Guild>> {
+        Ok(self.state.lock().await.select_guilds())
+    }
+
+    async fn select_members(&self) -> anyhow::Result<Vec<GuildMember>> {
+        Ok(self.state.lock().await.select_members())
+    }
+
+    async fn select_members_by_guild_id(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>> {
+        Ok(self.state.lock().await.select_members_by_guild_id(guild_id))
+    }
+
+    async fn select_member_by_id(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<Option<GuildMember>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .select_member_by_id(guild_id, member_id))
+    }
+
+    async fn select_guild_by_id(&self, guild_id: i64) -> anyhow::Result<Option<Guild>> {
+        Ok(self.state.lock().await.select_guild_by_id(guild_id))
+    }
+
+    async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()> {
+        self.state.lock().await.update_guild(guild_id, guild_name);
+        Ok(())
+    }
+
+    async fn update_guild_locale(
+        &self,
+        guild_id: i64,
+        locale: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .update_guild_locale(guild_id, locale))
+    }
+
+    async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()> {
+        self.state.lock().await.delete_guild(guild_id);
+        Ok(())
+    }
+
+    async fn delete_guild_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
+        self.state
+            .lock()
+            .await
+            .delete_guild_member(guild_id, member_id);
+        Ok(())
+    }
+
+    async fn insert_guild(&self, guild_id: i64, guild_name: Option<&str>) -> anyhow::Result<()> {
+        self.state.lock().await.insert_guild(guild_id, guild_name)
+    }
+
+    async fn insert_guild_member(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.state
+            .lock()
+            .await
+            .insert_guild_member(guild_id, member_id, birth)
+    }
+
+    async fn select_notifications(&self) -> anyhow::Result<Vec<BirthdayNotification>> {
+        Ok(self.state.lock().await.select_notifications())
+    }
+
+    async fn select_notifications_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .select_notifications_by_guild_id(guild_id))
+    }
+
+    async fn update_notification_reaction_count(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+        reaction_count: i32,
+    ) -> anyhow::Result<()> {
+        self.state.lock().await.update_notification_reaction_count(
+            guild_id,
+            member_id,
+            year,
+            notify_date,
+            reaction_count,
+        );
+        Ok(())
+    }
+
+    async fn insert_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .insert_notification(guild_id, member_id, year, notify_date))
+    }
+
+    async fn claim_notifications(
+        &self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        Ok(self.state.lock().await.claim_notifications(guild_id, ttl))
+    }
+
+    async fn rearm_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<bool> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .rearm_notification(guild_id, member_id, year))
+    }
+
+    async fn select_celebration_lines(&self) -> anyhow::Result<Vec<CelebrationLine>> {
+        Ok(self.state.lock().await.select_celebration_lines())
+    }
+
+    async fn select_celebration_lines_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<CelebrationLine>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .select_celebration_lines_by_guild_id(guild_id))
+    }
+
+    async fn insert_celebration_line(
+        &self,
+        guild_id: i64,
+        line: &str,
+    ) -> anyhow::Result<CelebrationLine> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .insert_celebration_line(guild_id, line))
+    }
+
+    async fn delete_celebration_line(&self, guild_id: i64, line_no: i32) -> anyhow::Result<bool> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .delete_celebration_line(guild_id, line_no))
+    }
+}
+
+#[async_trait]
+impl BirthdayStoreTransaction for MemoryTransaction {
+    async fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        let MemoryTransaction { mut guard, working } = *self;
+        *guard = working;
+        Ok(())
+    }
+
+    async fn insert_guild(
+        &mut self,
+        guild_id: i64,
+        guild_name: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.working.insert_guild(guild_id, guild_name)
+    }
+
+    async fn insert_guild_member(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.working.insert_guild_member(guild_id, member_id, birth)
+    }
+
+    async fn update_member_birth_if_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        Ok(self
+            .working
+            .update_member_birth_if_none(guild_id, member_id, birth))
+    }
+
+    async fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<bool> {
+        Ok(self.working.update_member_birth(guild_id, member_id, birth))
+    }
+
+    async fn update_member_birth_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<bool> {
+        Ok(self.working.update_member_birth_none(guild_id, member_id))
+    }
+
+    async fn upsert_guild(&mut self, guild: &Guild) -> anyhow::Result<()> {
+        self.working.upsert_guild(guild);
+        Ok(())
+    }
+
+    async fn upsert_guild_member(&mut self, member: &GuildMember) -> anyhow::Result<()> {
+        self.working.upsert_guild_member(member)
+    }
+
+    async fn update_guild_member_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        last_notified: NaiveDate,
+    ) -> anyhow::Result<()> {
+        self.working
+            .update_guild_member_last_notified(guild_id, member_id, last_notified);
+        Ok(())
+    }
+
+    async fn update_member_last_notified_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        self.working
+            .update_member_last_notified_none(guild_id, member_id);
+        Ok(())
+    }
+
+    async fn upsert_notification(
+        &mut self,
+        notification: &BirthdayNotification,
+    ) -> anyhow::Result<()> {
+        self.working.upsert_notification(notification);
+        Ok(())
+    }
+
+    async fn delete_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<()> {
+        self.working.delete_notification(guild_id, member_id, year);
+        Ok(())
+    }
+
+    async fn delete_unsent_notifications(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        self.working
+            .delete_unsent_notifications(guild_id, member_id);
+        Ok(())
+    }
+
+    async fn upsert_celebration_line(&mut self, line: &CelebrationLine) -> anyhow::Result<()> {
+        self.working.upsert_celebration_line(line);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryStore;
+    use crate::data::birthday_store::BirthdayStore;
+    use chrono::NaiveDate;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(1970, month, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn insert_guild_member_requires_existing_guild() {
+        let store = MemoryStore::new();
+
+        assert!(store.insert_guild_member(1, 10, None).await.is_err());
+
+        store.insert_guild(1, Some("guild")).await.unwrap();
+        store.insert_guild_member(1, 10, None).await.unwrap();
+        assert_eq!(store.select_members_by_guild_id(1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn delete_guild_removes_its_members() {
+        let store = MemoryStore::new();
+        store.insert_guild(1, Some("guild")).await.unwrap();
+        store.insert_guild(2, Some("other")).await.unwrap();
+        store.insert_guild_member(1, 10, None).await.unwrap();
+        store.insert_guild_member(2, 10, None).await.unwrap();
+
+        store.delete_guild(1).await.unwrap();
+
+        assert_eq!(store.select_guild_ids().await.unwrap(), vec![2]);
+        let members = store.select_members().await.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].guild_id, 2);
+    }
+
+    #[tokio::test]
+    async fn transaction_is_discarded_without_commit() {
+        let store = MemoryStore::new();
+
+        let mut tx = store.begin().await.unwrap();
+        tx.insert_guild(1, Some("guild")).await.unwrap();
+        drop(tx);
+        assert!(store.select_guild_ids().await.unwrap().is_empty());
+
+        let mut tx = store.begin().await.unwrap();
+        tx.insert_guild(1, Some("guild")).await.unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(store.select_guild_ids().await.unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn birth_is_updated_only_when_state_matches() {
+        let store = MemoryStore::new();
+        store.insert_guild(1, Some("guild")).await.unwrap();
+        store.insert_guild_member(1, 10, None).await.unwrap();
+
+        let mut tx = store.begin().await.unwrap();
+        tx.update_guild_member_last_notified(1, 10, date(2, 1))
+            .await
+            .unwrap();
+        assert!(tx
+            .update_member_birth_if_none(1, 10, date(2, 1))
+            .await
+            .unwrap());
+        assert!(!tx
+            .update_member_birth_if_none(1, 10, date(3, 1))
+            .await
+            .unwrap());
+        assert!(tx.update_member_birth_none(1, 10).await.unwrap());
+        assert!(!tx.update_member_birth_none(1, 10).await.unwrap());
+        tx.commit().await.unwrap();
+
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, None);
+        assert_eq!(member.last_notified, None);
+    }
+}
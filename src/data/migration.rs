@@ -0,0 +1,77 @@
+// sqlx のマイグレーションを、DBの種類によらず同じ手順で実行するためのヘルパー
+// PostgreSQL と SQLite の BirthdayStore 実装から利用する
+
+use crate::models::data::MigrationStatus;
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::{Database, Pool};
+use std::collections::HashSet;
+
+/// 未適用のマイグレーションをすべて適用する
+pub async fn migrate_up<DB>(migrator: &Migrator, pool: &Pool<DB>) -> anyhow::Result<()>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    migrator.run(pool).await?;
+    Ok(())
+}
+
+/// 適用済みのマイグレーションを新しいものから`steps`件取り消し、取り消した件数を返す
+pub async fn migrate_down<DB>(
+    migrator: &Migrator,
+    pool: &Pool<DB>,
+    steps: usize,
+) -> anyhow::Result<usize>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let applied_versions = applied_versions(pool).await?;
+    let steps = steps.min(applied_versions.len());
+    // 取り消した後に最新となるバージョン(すべて取り消す場合は0)
+    let target = applied_versions
+        .len()
+        .checked_sub(steps + 1)
+        .map_or(0, |index| applied_versions[index]);
+    migrator.undo(pool, target).await?;
+    Ok(steps)
+}
+
+/// バイナリに含まれるマイグレーションごとの適用状況を返す
+pub async fn migration_status<DB>(
+    migrator: &Migrator,
+    pool: &Pool<DB>,
+) -> anyhow::Result<Vec<MigrationStatus>>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let applied_versions: HashSet<i64> = applied_versions(pool).await?.into_iter().collect();
+    Ok(migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            is_applied: applied_versions.contains(&migration.version),
+        })
+        .collect())
+}
+
+/// 適用済みのバージョンを古い順に返す
+async fn applied_versions<DB>(pool: &Pool<DB>) -> anyhow::Result<Vec<i64>>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let mut versions: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    versions.sort_unstable();
+    Ok(versions)
+}
@@ -1,2 +1,15 @@
+pub mod backup;
+pub mod birthday_store;
+pub mod discord_gateway;
+#[cfg(test)]
+pub mod fake_discord_gateway;
+#[cfg(feature = "file-store")]
+pub mod file_store;
 pub mod guild_repository;
+pub mod memory_store;
+pub mod migration;
+pub mod offline_discord_gateway;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_database;
+pub mod store_factory;
 pub mod zunda_bot_database;
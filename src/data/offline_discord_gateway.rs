@@ -0,0 +1,64 @@
+// Discord に接続しない DiscordGateway の実装
+// 管理用 CLI から、Discord を操作しないことを保証した上でユースケースを利用するためのもの
+
+use crate::data::discord_gateway::DiscordGateway;
+use crate::models::domain::{MyChannel, MyMemberProfile, OutgoingMessage};
+use async_trait::async_trait;
+use poise::serenity_prelude::{ChannelId, GuildId, MessageId, UserId};
+
+const OFFLINE_ERROR: &str = "Discord is not available in offline mode";
+
+/// すべての操作をエラーにする
+pub struct OfflineDiscordGateway;
+
+#[async_trait]
+impl DiscordGateway for OfflineDiscordGateway {
+    async fn get_guild_ids(&self) -> anyhow::Result<Vec<GuildId>> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn get_guild_name(&self, _guild_id: GuildId) -> anyhow::Result<String> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn get_guild_member_ids(&self, _guild_id: GuildId) -> anyhow::Result<Vec<UserId>> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn get_guild_channels(&self, _guild_id: GuildId) -> anyhow::Result<Vec<MyChannel>> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn get_member_profile(
+        &self,
+        _guild_id: GuildId,
+        _member_id: UserId,
+    ) -> anyhow::Result<MyMemberProfile> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn send_message(
+        &self,
+        _channel_id: ChannelId,
+        _message: OutgoingMessage,
+    ) -> anyhow::Result<MessageId> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn react(
+        &self,
+        _channel_id: ChannelId,
+        _message_id: MessageId,
+        _emoji: &str,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+
+    async fn get_reaction_count(
+        &self,
+        _channel_id: ChannelId,
+        _message_id: MessageId,
+    ) -> anyhow::Result<u64> {
+        anyhow::bail!(OFFLINE_ERROR)
+    }
+}
@@ -0,0 +1,1006 @@
+// SQLite を利用する BirthdayStore の実装
+// PostgreSQL を用意するほどでもない小規模な環境向け
+//
+// sqlx のクエリマクロは1種類のDBに対してしか検証できないため、こちらは実行時にクエリを組み立てる。
+// スキーマは db/migrations_sqlite で管理する。
+
+use crate::data::birthday_store::{BirthdayStore, BirthdayStoreTransaction};
+use crate::data::migration;
+use crate::models::data::{
+    BirthdayNotification, CelebrationLine, Guild, GuildMember, MigrationStatus, PoolStats,
+};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::migrate::Migrator;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::sync::Arc;
+use std::time::Duration;
+
+static MIGRATOR: Migrator = sqlx::migrate!("db/migrations_sqlite");
+
+/// BirthdayNotification に読み込む birthday_notification の列
+const NOTIFICATION_COLUMNS: &str = "guild_id, member_id, year, notify_date, status, channel_id, \
+    announcement_message_id, reacted, reply_message_id, attempts, last_error, completed_at, \
+    reaction_count";
+
+pub struct SqliteDatabase {
+    pool: Arc<SqlitePool>,
+}
+
+struct SqliteTransaction {
+    tx: Transaction<'static, Sqlite>,
+}
+
+impl SqliteDatabase {
+    pub fn new(pool: Arc<SqlitePool>) -> anyhow::Result<Self, sqlx::Error> {
+        Ok(SqliteDatabase { pool })
+    }
+}
+
+#[async_trait]
+impl BirthdayStore for SqliteDatabase {
+    async fn begin(&self) -> anyhow::Result<Box<dyn BirthdayStoreTransaction>> {
+        let tx = self.pool.begin().await?;
+        Ok(Box::new(SqliteTransaction { tx }))
+    }
+
+    async fn migrate_up(&self) -> anyhow::Result<()> {
+        migration::migrate_up(&MIGRATOR, &self.pool).await
+    }
+
+    async fn migrate_down(&self, steps: usize) -> anyhow::Result<usize> {
+        migration::migrate_down(&MIGRATOR, &self.pool, steps).await
+    }
+
+    async fn migration_status(&self) -> anyhow::Result<Vec<MigrationStatus>> {
+        migration::migration_status(&MIGRATOR, &self.pool).await
+    }
+
+    async fn ping(&self) -> anyhow::Result<()> {
+        sqlx::query("SELECT 1").execute(&*self.pool).await?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(PoolStats {
+            size: self.pool.size(),
+            idle: u32::try_from(self.pool.num_idle()).unwrap_or(u32::MAX),
+            max_connections: self.pool.options().get_max_connections(),
+        })
+    }
+
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        let acquired: Option<String> = sqlx::query_scalar(
+            r#"
+        INSERT INTO leader_lease (name, holder, expires_at)
+        VALUES (?, ?, unixepoch() + ?)
+        ON CONFLICT (name) DO UPDATE
+        SET holder = excluded.holder, expires_at = excluded.expires_at
+        WHERE leader_lease.holder = excluded.holder OR leader_lease.expires_at < unixepoch()
+        RETURNING holder
+        "#,
+        )
+        .bind(name)
+        .bind(holder)
+        .bind(i64::try_from(ttl.as_secs())?)
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(acquired.is_some())
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM leader_lease WHERE name = ? AND holder = ?")
+            .bind(name)
+            .bind(holder)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn select_command_hash(&self, scope: &str) -> anyhow::Result<Option<String>> {
+        let hash = sqlx::query_scalar("SELECT hash FROM command_registration WHERE scope = ?")
+            .bind(scope)
+            .fetch_optional(&*self.pool)
+            .await?;
+        Ok(hash)
+    }
+
+    async fn upsert_command_hash(&self, scope: &str, hash: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO command_registration (scope, hash, registered_at)
+        VALUES (?, ?, unixepoch())
+        ON CONFLICT (scope) DO UPDATE
+        SET hash = excluded.hash, registered_at = excluded.registered_at
+        "#,
+        )
+        .bind(scope)
+        .bind(hash)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn select_guild_ids(&self) -> anyhow::Result<Vec<i64>> {
+        let guild_ids = sqlx::query_scalar("SELECT guild_id FROM guild")
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(guild_ids)
+    }
+
+    async fn select_guilds(&self) -> anyhow::Result<Vec<Guild>> {
+        let guilds = sqlx::query_as("SELECT guild_id, name, locale FROM guild")
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(guilds)
+    }
+
+    async fn select_guild_by_id(&self, guild_id: i64) -> anyhow::Result<Option<Guild>> {
+        let guild = sqlx::query_as("SELECT guild_id, name, locale FROM guild WHERE guild_id = ?")
+            .bind(guild_id)
+            .fetch_optional(&*self.pool)
+            .await?;
+        Ok(guild)
+    }
+
+    async fn select_members(&self) -> anyhow::Result<Vec<GuildMember>> {
+        let rows = sqlx::query_as("SELECT * FROM guild_member")
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    async fn select_members_by_guild_id(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>> {
+        let rows = sqlx::query_as("SELECT * FROM guild_member WHERE guild_id = ?")
+            .bind(guild_id)
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    async fn select_member_by_id(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<Option<GuildMember>> {
+        let row = sqlx::query_as("SELECT * FROM guild_member WHERE guild_id = ? AND member_id = ?")
+            .bind(guild_id)
+            .bind(member_id)
+            .fetch_optional(&*self.pool)
+            .await?;
+        Ok(row)
+    }
+
+    async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()> {
+        sqlx::query("UPDATE guild SET name = ? WHERE guild_id = ?")
+            .bind(guild_name)
+            .bind(guild_id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_guild_locale(
+        &self,
+        guild_id: i64,
+        locale: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query("UPDATE guild SET locale = ? WHERE guild_id = ?")
+            .bind(locale)
+            .bind(guild_id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()> {
+        // メンバー削除とギルド削除の間に他の更新が割り込まないよう、1つのトランザクションで実行
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM guild_member WHERE guild_id = ?")
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM guild_celebration_line WHERE guild_id = ?")
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM guild WHERE guild_id = ?")
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_guild_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM guild_member WHERE guild_id = ? AND member_id = ?")
+            .bind(guild_id)
+            .bind(member_id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn insert_guild(&self, guild_id: i64, guild_name: Option<&str>) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild (guild_id, name)
+        VALUES (?, ?)
+        ON CONFLICT (guild_id) DO NOTHING
+        "#,
+        )
+        .bind(guild_id)
+        .bind(guild_name)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_guild_member(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild_member (guild_id, member_id, birth)
+        VALUES (?, ?, ?)
+        ON CONFLICT (member_id, guild_id) DO NOTHING
+        "#,
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .bind(birth)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn select_notifications(&self) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let rows = sqlx::query_as(&format!(
+            "SELECT {NOTIFICATION_COLUMNS} FROM birthday_notification ORDER BY notify_date, guild_id, member_id"
+        ))
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn select_notifications_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let rows = sqlx::query_as(&format!(
+            "SELECT {NOTIFICATION_COLUMNS} FROM birthday_notification WHERE guild_id = ? ORDER BY notify_date, member_id"
+        ))
+        .bind(guild_id)
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn update_notification_reaction_count(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+        reaction_count: i32,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        UPDATE birthday_notification
+        SET reaction_count = ?
+        WHERE guild_id = ? AND member_id = ? AND year = ? AND notify_date = ?
+        "#,
+        )
+        .bind(reaction_count)
+        .bind(guild_id)
+        .bind(member_id)
+        .bind(year)
+        .bind(notify_date)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+        INSERT INTO birthday_notification (guild_id, member_id, year, notify_date)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (guild_id, member_id, year, notify_date) DO NOTHING
+        "#,
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .bind(year)
+        .bind(notify_date)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn claim_notifications(
+        &self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let rows = sqlx::query_as(&format!(
+            r#"
+        UPDATE birthday_notification
+        SET claimed_until = unixepoch() + ?
+        WHERE status = 'pending'
+          AND (claimed_until IS NULL OR claimed_until < unixepoch())
+          AND (? IS NULL OR guild_id = ?)
+        RETURNING {NOTIFICATION_COLUMNS}
+        "#
+        ))
+        .bind(i64::try_from(ttl.as_secs())?)
+        .bind(guild_id)
+        .bind(guild_id)
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn rearm_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+        UPDATE birthday_notification
+        SET status = 'pending', attempts = 0, last_error = NULL, claimed_until = NULL,
+            completed_at = NULL
+        WHERE guild_id = ? AND member_id = ? AND year = ?
+          AND status IN ('failed', 'skipped')
+        "#,
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .bind(year)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn select_celebration_lines(&self) -> anyhow::Result<Vec<CelebrationLine>> {
+        let lines = sqlx::query_as("SELECT guild_id, line_no, line FROM guild_celebration_line")
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(lines)
+    }
+
+    async fn select_celebration_lines_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<CelebrationLine>> {
+        let lines = sqlx::query_as(
+            r#"
+        SELECT guild_id, line_no, line FROM guild_celebration_line
+        WHERE guild_id = ?
+        ORDER BY line_no
+        "#,
+        )
+        .bind(guild_id)
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(lines)
+    }
+
+    async fn insert_celebration_line(
+        &self,
+        guild_id: i64,
+        line: &str,
+    ) -> anyhow::Result<CelebrationLine> {
+        let line = sqlx::query_as(
+            r#"
+        INSERT INTO guild_celebration_line (guild_id, line_no, line)
+        SELECT ?1, COALESCE(MAX(line_no), 0) + 1, ?2
+        FROM guild_celebration_line
+        WHERE guild_id = ?1
+        RETURNING guild_id, line_no, line
+        "#,
+        )
+        .bind(guild_id)
+        .bind(line)
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(line)
+    }
+
+    async fn delete_celebration_line(&self, guild_id: i64, line_no: i32) -> anyhow::Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM guild_celebration_line WHERE guild_id = ? AND line_no = ?")
+                .bind(guild_id)
+                .bind(line_no)
+                .execute(&*self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl BirthdayStoreTransaction for SqliteTransaction {
+    async fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_guild(
+        &mut self,
+        guild_id: i64,
+        guild_name: Option<&str>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild (guild_id, name)
+        VALUES (?, ?)
+        ON CONFLICT (guild_id) DO NOTHING
+        "#,
+        )
+        .bind(guild_id)
+        .bind(guild_name)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_guild_member(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild_member (guild_id, member_id, birth)
+        VALUES (?, ?, ?)
+        ON CONFLICT (member_id, guild_id) DO NOTHING
+        "#,
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .bind(birth)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_member_birth_if_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+        UPDATE guild_member
+        SET birth = ?
+        WHERE guild_id = ? AND member_id = ? AND birth IS NULL
+        "#,
+        )
+        .bind(birth)
+        .bind(guild_id)
+        .bind(member_id)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+        UPDATE guild_member
+        SET birth = ?1
+        WHERE guild_id = ?2 AND member_id = ?3 AND birth IS NOT ?1
+        "#,
+        )
+        .bind(birth)
+        .bind(guild_id)
+        .bind(member_id)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_member_birth_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+        UPDATE guild_member
+        SET birth = NULL, last_notified = NULL
+        WHERE guild_id = ? AND member_id = ? AND birth IS NOT NULL
+        "#,
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn upsert_guild(&mut self, guild: &Guild) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild (guild_id, name, locale)
+        VALUES (?, ?, ?)
+        ON CONFLICT (guild_id) DO UPDATE SET name = excluded.name, locale = excluded.locale
+        "#,
+        )
+        .bind(guild.guild_id)
+        .bind(&guild.name)
+        .bind(&guild.locale)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_guild_member(&mut self, member: &GuildMember) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild_member (guild_id, member_id, birth, last_notified)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (member_id, guild_id)
+        DO UPDATE SET birth = excluded.birth, last_notified = excluded.last_notified
+        "#,
+        )
+        .bind(member.guild_id)
+        .bind(member.member_id)
+        .bind(member.birth)
+        .bind(member.last_notified)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_guild_member_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        last_notified: NaiveDate,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        UPDATE guild_member
+        SET last_notified = ?
+        WHERE guild_id = ? AND member_id = ?
+        "#,
+        )
+        .bind(last_notified)
+        .bind(guild_id)
+        .bind(member_id)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_member_last_notified_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE guild_member SET last_notified = NULL WHERE guild_id = ? AND member_id = ?",
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_notification(
+        &mut self,
+        notification: &BirthdayNotification,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO birthday_notification
+            (guild_id, member_id, year, notify_date, status, channel_id, announcement_message_id,
+             reacted, reply_message_id, attempts, last_error, completed_at, reaction_count)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (guild_id, member_id, year, notify_date)
+        DO UPDATE SET status = excluded.status,
+                      channel_id = excluded.channel_id,
+                      announcement_message_id = excluded.announcement_message_id,
+                      reacted = excluded.reacted,
+                      reply_message_id = excluded.reply_message_id,
+                      attempts = excluded.attempts,
+                      last_error = excluded.last_error,
+                      completed_at = excluded.completed_at,
+                      reaction_count = excluded.reaction_count
+        "#,
+        )
+        .bind(notification.guild_id)
+        .bind(notification.member_id)
+        .bind(notification.year)
+        .bind(notification.notify_date)
+        .bind(notification.status.as_str())
+        .bind(notification.channel_id)
+        .bind(notification.announcement_message_id)
+        .bind(notification.reacted)
+        .bind(notification.reply_message_id)
+        .bind(notification.attempts)
+        .bind(&notification.last_error)
+        .bind(notification.completed_at)
+        .bind(notification.reaction_count)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "DELETE FROM birthday_notification WHERE guild_id = ? AND member_id = ? AND year = ?",
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .bind(year)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_unsent_notifications(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "DELETE FROM birthday_notification WHERE guild_id = ? AND member_id = ? AND status <> 'sent'",
+        )
+        .bind(guild_id)
+        .bind(member_id)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_celebration_line(&mut self, line: &CelebrationLine) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO guild_celebration_line (guild_id, line_no, line)
+        VALUES (?, ?, ?)
+        ON CONFLICT (guild_id, line_no) DO UPDATE SET line = excluded.line
+        "#,
+        )
+        .bind(line.guild_id)
+        .bind(line.line_no)
+        .bind(&line.line)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteDatabase;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::models::data::{BirthdayNotification, NotificationStatus};
+    use chrono::NaiveDate;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// マイグレーション済みのインメモリDBを開く
+    async fn open_database() -> SqliteDatabase {
+        // インメモリDBは接続ごとに別のDBになるため、接続は1つに限定する
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = SqliteDatabase::new(Arc::new(pool)).unwrap();
+        db.migrate_up().await.unwrap();
+        db
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2000, month, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn insert_guild_member_requires_existing_guild() {
+        let db = open_database().await;
+
+        assert!(db.insert_guild_member(1, 10, None).await.is_err());
+
+        db.insert_guild(1, Some("guild")).await.unwrap();
+        db.insert_guild_member(1, 10, Some(date(2, 29)))
+            .await
+            .unwrap();
+        let member = db.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, Some(date(2, 29)));
+    }
+
+    #[tokio::test]
+    async fn delete_guild_removes_its_members() {
+        let db = open_database().await;
+        db.insert_guild(1, Some("guild")).await.unwrap();
+        db.insert_guild(2, Some("other")).await.unwrap();
+        db.insert_guild_member(1, 10, None).await.unwrap();
+        db.insert_guild_member(2, 10, None).await.unwrap();
+
+        db.delete_guild(1).await.unwrap();
+
+        assert_eq!(db.select_guild_ids().await.unwrap(), vec![2]);
+        let members = db.select_members().await.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].guild_id, 2);
+    }
+
+    #[tokio::test]
+    async fn member_birth_is_updated_only_when_it_changes() {
+        let db = open_database().await;
+        db.insert_guild(1, Some("guild")).await.unwrap();
+        db.insert_guild_member(1, 10, None).await.unwrap();
+        let mut tx = db.begin().await.unwrap();
+        tx.update_guild_member_last_notified(1, 10, date(2, 1))
+            .await
+            .unwrap();
+
+        assert!(!tx.update_member_birth(1, 10, None).await.unwrap());
+        assert!(tx
+            .update_member_birth(1, 10, Some(date(3, 1)))
+            .await
+            .unwrap());
+        assert!(!tx
+            .update_member_birth(1, 10, Some(date(3, 1)))
+            .await
+            .unwrap());
+        assert!(tx.update_member_birth(1, 10, None).await.unwrap());
+        tx.commit().await.unwrap();
+
+        // 最終通知日は変更しない
+        let member = db.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, None);
+        assert_eq!(member.last_notified, Some(date(2, 1)));
+    }
+
+    #[tokio::test]
+    async fn unsent_notifications_are_deleted_and_failed_ones_rearmed() {
+        let db = open_database().await;
+        db.insert_guild(1, Some("guild")).await.unwrap();
+        let mut sent =
+            BirthdayNotification::new(1, 10, 2024, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        sent.status = NotificationStatus::Sent;
+        let mut failed =
+            BirthdayNotification::new(1, 10, 2025, NaiveDate::from_ymd_opt(2025, 2, 1).unwrap());
+        failed.status = NotificationStatus::Failed;
+        failed.attempts = 3;
+        failed.last_error = Some("Discord is down".to_string());
+        let mut tx = db.begin().await.unwrap();
+        tx.upsert_notification(&sent).await.unwrap();
+        tx.upsert_notification(&failed).await.unwrap();
+        tx.commit().await.unwrap();
+
+        assert!(db.rearm_notification(1, 10, 2025).await.unwrap());
+        assert!(!db.rearm_notification(1, 10, 2025).await.unwrap());
+        assert!(!db.rearm_notification(1, 10, 2024).await.unwrap());
+        let claimed = db
+            .claim_notifications(None, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!((claimed[0].year, claimed[0].attempts), (2025, 0));
+        assert_eq!(claimed[0].last_error, None);
+
+        let mut tx = db.begin().await.unwrap();
+        tx.delete_unsent_notifications(1, 10).await.unwrap();
+        tx.commit().await.unwrap();
+        let remaining = db.select_notifications().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].status, NotificationStatus::Sent);
+    }
+
+    #[tokio::test]
+    async fn notification_on_another_date_keeps_the_sent_one() {
+        let db = open_database().await;
+        let first = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        let second = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut sent = BirthdayNotification::new(1, 10, 2025, first);
+        sent.status = NotificationStatus::Sent;
+        let mut tx = db.begin().await.unwrap();
+        tx.upsert_notification(&sent).await.unwrap();
+        tx.commit().await.unwrap();
+
+        assert!(!db.insert_notification(1, 10, 2025, first).await.unwrap());
+        assert!(db.insert_notification(1, 10, 2025, second).await.unwrap());
+        db.update_notification_reaction_count(1, 10, 2025, first, 4)
+            .await
+            .unwrap();
+
+        let notifications = db.select_notifications().await.unwrap();
+        assert_eq!(
+            notifications
+                .iter()
+                .map(|n| (n.notify_date, n.status, n.reaction_count))
+                .collect::<Vec<_>>(),
+            vec![
+                (first, NotificationStatus::Sent, 4),
+                (second, NotificationStatus::Pending, 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn celebration_lines_are_numbered_per_guild() {
+        let db = open_database().await;
+        db.insert_guild(1, Some("guild")).await.unwrap();
+        db.insert_guild(2, Some("other")).await.unwrap();
+
+        assert_eq!(db.insert_celebration_line(1, "a").await.unwrap().line_no, 1);
+        assert_eq!(db.insert_celebration_line(1, "b").await.unwrap().line_no, 2);
+        assert_eq!(db.insert_celebration_line(2, "c").await.unwrap().line_no, 1);
+        assert!(db.delete_celebration_line(1, 1).await.unwrap());
+        assert!(!db.delete_celebration_line(1, 1).await.unwrap());
+
+        db.delete_guild(2).await.unwrap();
+        let lines = db.select_celebration_lines().await.unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].guild_id, lines[0].line_no), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn command_hash_is_replaced_per_scope() {
+        let db = open_database().await;
+        assert_eq!(db.select_command_hash("global").await.unwrap(), None);
+
+        db.upsert_command_hash("global", "a").await.unwrap();
+        db.upsert_command_hash("global", "b").await.unwrap();
+
+        assert_eq!(
+            db.select_command_hash("global").await.unwrap().as_deref(),
+            Some("b")
+        );
+    }
+
+    #[tokio::test]
+    async fn birth_is_updated_only_when_state_matches() {
+        let db = open_database().await;
+        db.insert_guild(1, Some("guild")).await.unwrap();
+        db.insert_guild_member(1, 10, None).await.unwrap();
+
+        let mut tx = db.begin().await.unwrap();
+        tx.update_guild_member_last_notified(1, 10, date(2, 1))
+            .await
+            .unwrap();
+        assert!(tx
+            .update_member_birth_if_none(1, 10, date(2, 1))
+            .await
+            .unwrap());
+        assert!(!tx
+            .update_member_birth_if_none(1, 10, date(3, 1))
+            .await
+            .unwrap());
+        assert!(tx.update_member_birth_none(1, 10).await.unwrap());
+        assert!(!tx.update_member_birth_none(1, 10).await.unwrap());
+        tx.commit().await.unwrap();
+
+        let member = db.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, None);
+        assert_eq!(member.last_notified, None);
+    }
+
+    #[tokio::test]
+    async fn migrations_can_be_reverted_and_reapplied() {
+        let db = open_database().await;
+        let statuses = db.migration_status().await.unwrap();
+        assert!(statuses.iter().all(|status| status.is_applied));
+
+        // 適用済みの件数より多く指定しても、すべて取り消した時点で止まる
+        assert_eq!(
+            db.migrate_down(statuses.len() + 1).await.unwrap(),
+            statuses.len()
+        );
+        assert!(db
+            .migration_status()
+            .await
+            .unwrap()
+            .iter()
+            .all(|status| !status.is_applied));
+        assert!(db.select_guild_ids().await.is_err());
+
+        db.migrate_up().await.unwrap();
+        assert!(db.select_guild_ids().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn notification_is_claimed_once_until_it_expires() {
+        let db = open_database().await;
+        let birthday = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        let ttl = Duration::from_secs(30);
+
+        assert!(db.insert_notification(1, 10, 2025, birthday).await.unwrap());
+        assert!(!db.insert_notification(1, 10, 2025, birthday).await.unwrap());
+        db.insert_notification(2, 20, 2025, birthday).await.unwrap();
+
+        let claimed = db.claim_notifications(Some(1), ttl).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].status, NotificationStatus::Pending);
+        assert!(db
+            .claim_notifications(Some(1), ttl)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // 送信を終えた通知は、確保の期限が切れても再び確保されない
+        let mut sent = claimed[0].clone();
+        sent.status = NotificationStatus::Sent;
+        sent.announcement_message_id = Some(7);
+        sent.reacted = true;
+        let mut tx = db.begin().await.unwrap();
+        tx.upsert_notification(&sent).await.unwrap();
+        tx.commit().await.unwrap();
+        let claimed = db.claim_notifications(None, Duration::ZERO).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].guild_id, 2);
+        assert_eq!(db.select_notifications().await.unwrap()[0], sent);
+    }
+
+    #[tokio::test]
+    async fn lease_is_held_by_one_holder_until_released() {
+        let db = open_database().await;
+        let ttl = Duration::from_secs(30);
+
+        assert!(db.try_acquire_lease("notifier", "a", ttl).await.unwrap());
+        assert!(!db.try_acquire_lease("notifier", "b", ttl).await.unwrap());
+        // 保持しているインスタンスは延長できる
+        assert!(db.try_acquire_lease("notifier", "a", ttl).await.unwrap());
+
+        // 他のインスタンスは手放せない
+        db.release_lease("notifier", "b").await.unwrap();
+        assert!(!db.try_acquire_lease("notifier", "b", ttl).await.unwrap());
+        db.release_lease("notifier", "a").await.unwrap();
+        assert!(db.try_acquire_lease("notifier", "b", ttl).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn expired_lease_can_be_taken_over() {
+        let db = open_database().await;
+        db.try_acquire_lease("notifier", "a", Duration::ZERO)
+            .await
+            .unwrap();
+
+        // 期限は秒単位で判定するため、1秒以上待つ
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        assert!(db
+            .try_acquire_lease("notifier", "b", Duration::from_secs(30))
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,190 @@
+// DATABASE_URL に応じて BirthdayStore の実装を選択する
+//
+// - postgres://... : PostgreSQL (postgresql:// も可) (ZundaBotDatabase)
+// - sqlite:<パス>  : SQLite (SqliteDatabase, sqlite feature が必要)
+// - file:<パス>    : JSONファイル (FileStore, file-store feature が必要)
+// - memory:        : インメモリ (再起動でデータは消える)
+//
+// マイグレーションはここでは実行しない。起動時や migrate サブコマンドで BirthdayStore::migrate_up を呼ぶこと。
+
+use crate::data::birthday_store::BirthdayStore;
+#[cfg(feature = "file-store")]
+use crate::data::file_store::FileStore;
+use crate::data::memory_store::MemoryStore;
+#[cfg(feature = "sqlite")]
+use crate::data::sqlite_database::SqliteDatabase;
+use crate::data::zunda_bot_database::ZundaBotDatabase;
+use crate::models::data::StoreSnapshot;
+use anyhow::Context as _;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+/// `max_connections` はコネクションプールを使うストア (PostgreSQL, SQLite) の最大接続数
+pub async fn open_store(
+    database_url: &str,
+    max_connections: u32,
+) -> anyhow::Result<Arc<dyn BirthdayStore>> {
+    if database_url.starts_with("memory:") {
+        // DBを用意せずに動作確認するためのインメモリストア(再起動でデータは消える)
+        tracing::warn!("Using in-memory store. All data will be lost on restart.");
+        return Ok(Arc::new(MemoryStore::new()));
+    }
+
+    if let Some(path) = database_url.strip_prefix("file:") {
+        return open_file_store(path.trim_start_matches("//"));
+    }
+
+    if database_url.starts_with("sqlite:") {
+        return open_sqlite_store(database_url, max_connections).await;
+    }
+
+    if !database_url.starts_with("postgres://") && !database_url.starts_with("postgresql://") {
+        anyhow::bail!(
+            "Unsupported DATABASE_URL scheme. Use postgres://, sqlite:, file: or memory:"
+        );
+    }
+
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(database_url)
+        .await
+        .context("Failed to connect to PostgreSQL")?;
+    Ok(Arc::new(ZundaBotDatabase::new(Arc::new(pool))?))
+}
+
+#[cfg(feature = "file-store")]
+fn open_file_store(path: &str) -> anyhow::Result<Arc<dyn BirthdayStore>> {
+    tracing::info!("Using file store: {}", path);
+    Ok(Arc::new(FileStore::open(path)?))
+}
+
+#[cfg(not(feature = "file-store"))]
+fn open_file_store(_path: &str) -> anyhow::Result<Arc<dyn BirthdayStore>> {
+    anyhow::bail!("File store is not enabled. Rebuild with `--features file-store`.")
+}
+
+#[cfg(feature = "sqlite")]
+async fn open_sqlite_store(
+    database_url: &str,
+    max_connections: u32,
+) -> anyhow::Result<Arc<dyn BirthdayStore>> {
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use std::str::FromStr;
+
+    let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .connect_with(options)
+        .await
+        .context("Failed to open SQLite database")?;
+    Ok(Arc::new(SqliteDatabase::new(Arc::new(pool))?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn open_sqlite_store(
+    _database_url: &str,
+    _max_connections: u32,
+) -> anyhow::Result<Arc<dyn BirthdayStore>> {
+    anyhow::bail!("SQLite store is not enabled. Rebuild with `--features sqlite`.")
+}
+
+/// ストアの全データをスナップショットとして取り出す
+pub async fn export_snapshot(store: &dyn BirthdayStore) -> anyhow::Result<StoreSnapshot> {
+    Ok(StoreSnapshot {
+        version: StoreSnapshot::CURRENT_VERSION,
+        guilds: store.select_guilds().await?,
+        members: store.select_members().await?,
+        notifications: store.select_notifications().await?,
+        celebration_lines: store.select_celebration_lines().await?,
+    })
+}
+
+/// スナップショットの内容を1つのトランザクションでストアに取り込む
+///
+/// 既存の行は上書きされるため、同じスナップショットを何度取り込んでも結果は変わらない。
+pub async fn import_snapshot(
+    store: &dyn BirthdayStore,
+    snapshot: &StoreSnapshot,
+) -> anyhow::Result<()> {
+    if snapshot.version != StoreSnapshot::CURRENT_VERSION {
+        anyhow::bail!(
+            "unsupported snapshot version: {} (expected {})",
+            snapshot.version,
+            StoreSnapshot::CURRENT_VERSION
+        );
+    }
+    let mut tx = store.begin().await?;
+    for guild in &snapshot.guilds {
+        tx.upsert_guild(guild).await?;
+    }
+    for member in &snapshot.members {
+        tx.upsert_guild_member(member).await?;
+    }
+    for notification in &snapshot.notifications {
+        tx.upsert_notification(notification).await?;
+    }
+    for line in &snapshot.celebration_lines {
+        tx.upsert_celebration_line(line).await?;
+    }
+    tx.commit().await
+}
+
+/// `from` のデータを `to` へコピーする (PostgreSQL とファイルストア間の移行用)
+pub async fn copy_store(
+    from: &dyn BirthdayStore,
+    to: &dyn BirthdayStore,
+) -> anyhow::Result<StoreSnapshot> {
+    let snapshot = export_snapshot(from).await?;
+    import_snapshot(to, &snapshot).await?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::copy_store;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::memory_store::MemoryStore;
+    use chrono::NaiveDate;
+
+    #[tokio::test]
+    async fn copy_store_overwrites_existing_rows_and_keeps_others() {
+        let birth = NaiveDate::from_ymd_opt(2000, 2, 1).unwrap();
+        let from = MemoryStore::new();
+        from.insert_guild(1, Some("renamed")).await.unwrap();
+        from.insert_guild_member(1, 10, Some(birth)).await.unwrap();
+        let mut tx = from.begin().await.unwrap();
+        tx.update_guild_member_last_notified(1, 10, birth)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let to = MemoryStore::new();
+        to.insert_guild(1, Some("guild")).await.unwrap();
+        to.insert_guild(2, Some("other")).await.unwrap();
+        to.insert_guild_member(1, 10, None).await.unwrap();
+
+        copy_store(&from, &to).await.unwrap();
+        // 2回目のコピーでも結果は変わらない
+        let snapshot = copy_store(&from, &to).await.unwrap();
+
+        assert_eq!(snapshot.members.len(), 1);
+        assert_eq!(to.select_guild_ids().await.unwrap(), vec![1, 2]);
+        assert_eq!(to.select_guilds().await.unwrap()[0].name, "renamed");
+        assert_eq!(
+            to.select_member_by_id(1, 10).await.unwrap().unwrap(),
+            from.select_member_by_id(1, 10).await.unwrap().unwrap()
+        );
+    }
+
+    #[cfg(not(feature = "file-store"))]
+    #[tokio::test]
+    async fn file_url_requires_file_store_feature() {
+        assert!(super::open_store("file:zunda.json", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unknown_scheme_is_rejected() {
+        assert!(super::open_store("mysql://localhost/zunda", 1)
+            .await
+            .is_err());
+    }
+}
@@ -1,20 +1,139 @@
 // DB接続や初期化など、DB全体の管理を担当
 
-use crate::models::data::GuildMember;
+use crate::data::birthday_store::{BirthdayStore, BirthdayStoreTransaction};
+use crate::data::migration;
+use crate::models::data::{
+    BirthdayNotification, CelebrationLine, Guild, GuildMember, MigrationStatus, NotificationStatus,
+    PoolStats,
+};
+use async_trait::async_trait;
 use chrono::NaiveDate;
-use sqlx::PgPool;
+use sqlx::migrate::Migrator;
+use sqlx::{PgPool, Postgres, Transaction};
 use std::sync::Arc;
+use std::time::Duration;
+
+static MIGRATOR: Migrator = sqlx::migrate!("db/migrations");
 
 pub struct ZundaBotDatabase {
     pool: Arc<PgPool>,
 }
 
+struct ZundaBotTransaction {
+    tx: Transaction<'static, Postgres>,
+}
+
 impl ZundaBotDatabase {
     pub fn new(pool: Arc<PgPool>) -> anyhow::Result<Self, sqlx::Error> {
         Ok(ZundaBotDatabase { pool })
     }
+}
+
+#[async_trait]
+impl BirthdayStore for ZundaBotDatabase {
+    async fn begin(&self) -> anyhow::Result<Box<dyn BirthdayStoreTransaction>> {
+        let tx = self.pool.begin().await?;
+        Ok(Box::new(ZundaBotTransaction { tx }))
+    }
+
+    async fn migrate_up(&self) -> anyhow::Result<()> {
+        migration::migrate_up(&MIGRATOR, &self.pool).await
+    }
+
+    async fn migrate_down(&self, steps: usize) -> anyhow::Result<usize> {
+        migration::migrate_down(&MIGRATOR, &self.pool, steps).await
+    }
+
+    async fn migration_status(&self) -> anyhow::Result<Vec<MigrationStatus>> {
+        migration::migration_status(&MIGRATOR, &self.pool).await
+    }
+
+    async fn ping(&self) -> anyhow::Result<()> {
+        sqlx::query("SELECT 1").execute(&*self.pool).await?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(PoolStats {
+            size: self.pool.size(),
+            idle: u32::try_from(self.pool.num_idle()).unwrap_or(u32::MAX),
+            max_connections: self.pool.options().get_max_connections(),
+        })
+    }
+
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        // インスタンス間の時計のずれに影響されないよう、期限は DB の時刻で判定する
+        let acquired = sqlx::query_scalar!(
+            r#"
+        INSERT INTO leader_lease (name, holder, expires_at)
+        VALUES ($1, $2, now() + make_interval(secs => $3))
+        ON CONFLICT (name) DO UPDATE
+        SET holder = EXCLUDED.holder, expires_at = EXCLUDED.expires_at
+        WHERE leader_lease.holder = EXCLUDED.holder OR leader_lease.expires_at < now()
+        RETURNING holder
+        "#,
+            name,
+            holder,
+            ttl.as_secs_f64(),
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(acquired.is_some())
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        DELETE FROM leader_lease
+        WHERE name = $1 AND holder = $2
+        "#,
+            name,
+            holder,
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn select_command_hash(&self, scope: &str) -> anyhow::Result<Option<String>> {
+        let hash = sqlx::query_scalar!(
+            r#"
+        SELECT hash FROM command_registration
+        WHERE scope = $1
+        "#,
+            scope,
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(hash)
+    }
+
+    async fn upsert_command_hash(&self, scope: &str, hash: &str) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        INSERT INTO command_registration (scope, hash, registered_at)
+        VALUES ($1, $2, now())
+        ON CONFLICT (scope) DO UPDATE
+        SET hash = EXCLUDED.hash, registered_at = EXCLUDED.registered_at
+        "#,
+            scope,
+            hash,
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
 
-    pub async fn select_guild_ids(&self) -> anyhow::Result<Vec<i64>> {
+    async fn select_guild_ids(&self) -> anyhow::Result<Vec<i64>> {
         let guild_ids = sqlx::query_scalar!(
             r#"
         SELECT guild_id::BIGINT FROM guild
@@ -25,17 +144,32 @@ impl ZundaBotDatabase {
         Ok(guild_ids)
     }
 
-    pub async fn select_members(&self) -> anyhow::Result<Vec<GuildMember>> {
+    async fn select_guilds(&self) -> anyhow::Result<Vec<Guild>> {
+        let guilds = sqlx::query_as!(Guild, r#"SELECT guild_id, name, locale FROM guild"#)
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(guilds)
+    }
+
+    async fn select_guild_by_id(&self, guild_id: i64) -> anyhow::Result<Option<Guild>> {
+        let guild = sqlx::query_as!(
+            Guild,
+            r#"SELECT guild_id, name, locale FROM guild WHERE guild_id = $1"#,
+            guild_id
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(guild)
+    }
+
+    async fn select_members(&self) -> anyhow::Result<Vec<GuildMember>> {
         let rows = sqlx::query_as!(GuildMember, r#"SELECT * FROM guild_member"#)
             .fetch_all(&*self.pool)
             .await?;
         Ok(rows)
     }
 
-    pub async fn select_members_by_guild_id(
-        &self,
-        guild_id: i64,
-    ) -> anyhow::Result<Vec<GuildMember>> {
+    async fn select_members_by_guild_id(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>> {
         let rows = sqlx::query_as!(
             GuildMember,
             r#"SELECT * FROM guild_member WHERE guild_id = $1"#,
@@ -46,7 +180,7 @@ impl ZundaBotDatabase {
         Ok(rows)
     }
 
-    pub async fn select_member_by_id(
+    async fn select_member_by_id(
         &self,
         guild_id: i64,
         member_id: i64,
@@ -62,7 +196,7 @@ impl ZundaBotDatabase {
         Ok(row)
     }
 
-    pub async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()> {
+    async fn update_guild(&self, guild_id: i64, guild_name: &str) -> anyhow::Result<()> {
         sqlx::query!(
             r#"
         UPDATE guild
@@ -78,19 +212,67 @@ impl ZundaBotDatabase {
         Ok(())
     }
 
-    pub async fn update_member_birth(
+    async fn update_guild_locale(
         &self,
         guild_id: i64,
-        member_id: i64,
-        birth: NaiveDate,
-    ) -> anyhow::Result<()> {
+        locale: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query!(
+            r#"
+        UPDATE guild
+        SET locale = $1
+        WHERE guild_id = $2
+        "#,
+            locale,
+            guild_id,
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()> {
+        // メンバー削除とギルド削除の間に他の更新が割り込まないよう、1つのトランザクションで実行
+        let mut tx = self.pool.begin().await?;
         sqlx::query!(
             r#"
-        UPDATE guild_member
-        SET birth = $1
-        WHERE guild_id = $2 AND member_id = $3
+        DELETE FROM guild_member
+        WHERE guild_id = $1
+        "#,
+            guild_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        DELETE FROM guild_celebration_line
+        WHERE guild_id = $1
+        "#,
+            guild_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        DELETE FROM guild
+        WHERE guild_id = $1
+        "#,
+            guild_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_guild_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        DELETE FROM guild_member
+        WHERE guild_id = $1 AND member_id = $2
         "#,
-            birth,
             guild_id,
             member_id,
         )
@@ -99,86 +281,252 @@ impl ZundaBotDatabase {
         Ok(())
     }
 
-    pub async fn update_member_birth_none(
+    async fn insert_guild(&self, guild_id: i64, guild_name: Option<&str>) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        INSERT INTO guild (guild_id, name)
+        VALUES ($1, $2)
+        ON CONFLICT (guild_id) DO NOTHING
+        "#,
+            guild_id,
+            guild_name,
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_guild_member(
         &self,
         guild_id: i64,
         member_id: i64,
+        birth: Option<NaiveDate>,
     ) -> anyhow::Result<()> {
         sqlx::query!(
             r#"
-        UPDATE guild_member
-        SET birth = NULL, last_notified = NULL
-        WHERE guild_id = $1 AND member_id = $2
+        INSERT INTO guild_member (guild_id, member_id, birth)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id, member_id) DO NOTHING
         "#,
             guild_id,
             member_id,
+            birth,
         )
         .execute(&*self.pool)
         .await?;
         Ok(())
     }
 
-    pub async fn update_guild_member_last_notified(
+    async fn select_notifications(&self) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let rows = sqlx::query_as!(
+            BirthdayNotification,
+            r#"
+        SELECT guild_id, member_id, year, notify_date, status AS "status: NotificationStatus",
+               channel_id, announcement_message_id, reacted, reply_message_id, attempts,
+               last_error, completed_at, reaction_count
+        FROM birthday_notification
+        ORDER BY notify_date, guild_id, member_id
+        "#
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn select_notifications_by_guild_id(
+        &self,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let rows = sqlx::query_as!(
+            BirthdayNotification,
+            r#"
+        SELECT guild_id, member_id, year, notify_date, status AS "status: NotificationStatus",
+               channel_id, announcement_message_id, reacted, reply_message_id, attempts,
+               last_error, completed_at, reaction_count
+        FROM birthday_notification
+        WHERE guild_id = $1
+        ORDER BY notify_date, member_id
+        "#,
+            guild_id
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn update_notification_reaction_count(
         &self,
         guild_id: i64,
         member_id: i64,
-        last_notified: NaiveDate,
+        year: i32,
+        notify_date: NaiveDate,
+        reaction_count: i32,
     ) -> anyhow::Result<()> {
         sqlx::query!(
             r#"
-        UPDATE guild_member
-        SET last_notified = $1
-        WHERE guild_id = $2 AND member_id = $3
+        UPDATE birthday_notification
+        SET reaction_count = $5
+        WHERE guild_id = $1 AND member_id = $2 AND year = $3 AND notify_date = $4
         "#,
-            last_notified,
             guild_id,
             member_id,
+            year,
+            notify_date,
+            reaction_count,
         )
         .execute(&*self.pool)
         .await?;
         Ok(())
     }
 
-    pub async fn delete_guild(&self, guild_id: i64) -> anyhow::Result<()> {
-        sqlx::query!(
+    async fn insert_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+        notify_date: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query!(
             r#"
-        DELETE FROM guild_member
-        WHERE guild_id = $1
+        INSERT INTO birthday_notification (guild_id, member_id, year, notify_date)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id, member_id, year, notify_date) DO NOTHING
         "#,
             guild_id,
+            member_id,
+            year,
+            notify_date,
         )
         .execute(&*self.pool)
         .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        sqlx::query!(
+    async fn claim_notifications(
+        &self,
+        guild_id: Option<i64>,
+        ttl: Duration,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        // 確保の判定と更新を1つの文で行い、複数のインスタンスが同じ通知を確保しないようにする
+        let rows = sqlx::query_as!(
+            BirthdayNotification,
             r#"
-        DELETE FROM guild
-        WHERE guild_id = $1
+        UPDATE birthday_notification
+        SET claimed_until = now() + make_interval(secs => $2)
+        WHERE status = 'pending'
+          AND (claimed_until IS NULL OR claimed_until < now())
+          AND ($1::BIGINT IS NULL OR guild_id = $1)
+        RETURNING guild_id, member_id, year, notify_date, status AS "status: NotificationStatus",
+                  channel_id, announcement_message_id, reacted, reply_message_id, attempts,
+                  last_error, completed_at, reaction_count
         "#,
             guild_id,
+            ttl.as_secs_f64(),
         )
-        .execute(&*self.pool)
+        .fetch_all(&*self.pool)
         .await?;
-        Ok(())
+        Ok(rows)
     }
 
-    pub async fn delete_guild_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<()> {
-        sqlx::query!(
+    async fn rearm_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query!(
             r#"
-        DELETE FROM guild_member
-        WHERE guild_id = $1 AND member_id = $2
+        UPDATE birthday_notification
+        SET status = 'pending', attempts = 0, last_error = NULL, claimed_until = NULL,
+            completed_at = NULL
+        WHERE guild_id = $1 AND member_id = $2 AND year = $3
+          AND status IN ('failed', 'skipped')
         "#,
             guild_id,
             member_id,
+            year,
         )
         .execute(&*self.pool)
         .await?;
-        Ok(())
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn select_celebration_lines(&self) -> anyhow::Result<Vec<CelebrationLine>> {
+        let lines = sqlx::query_as!(
+            CelebrationLine,
+            r#"SELECT guild_id, line_no, line FROM guild_celebration_line"#
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(lines)
     }
 
-    pub async fn insert_guild(
+    async fn select_celebration_lines_by_guild_id(
         &self,
         guild_id: i64,
+    ) -> anyhow::Result<Vec<CelebrationLine>> {
+        let lines = sqlx::query_as!(
+            CelebrationLine,
+            r#"
+        SELECT guild_id, line_no, line FROM guild_celebration_line
+        WHERE guild_id = $1
+        ORDER BY line_no
+        "#,
+            guild_id
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(lines)
+    }
+
+    async fn insert_celebration_line(
+        &self,
+        guild_id: i64,
+        line: &str,
+    ) -> anyhow::Result<CelebrationLine> {
+        // 同時に追加された場合は主キーの重複でエラーになる
+        let line = sqlx::query_as!(
+            CelebrationLine,
+            r#"
+        INSERT INTO guild_celebration_line (guild_id, line_no, line)
+        SELECT $1, COALESCE(MAX(line_no), 0) + 1, $2
+        FROM guild_celebration_line
+        WHERE guild_id = $1
+        RETURNING guild_id, line_no, line
+        "#,
+            guild_id,
+            line,
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(line)
+    }
+
+    async fn delete_celebration_line(&self, guild_id: i64, line_no: i32) -> anyhow::Result<bool> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM guild_celebration_line
+        WHERE guild_id = $1 AND line_no = $2
+        "#,
+            guild_id,
+            line_no,
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl BirthdayStoreTransaction for ZundaBotTransaction {
+    async fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_guild(
+        &mut self,
+        guild_id: i64,
         guild_name: Option<&str>,
     ) -> anyhow::Result<()> {
         sqlx::query!(
@@ -190,13 +538,13 @@ impl ZundaBotDatabase {
             guild_id,
             guild_name,
         )
-        .execute(&*self.pool)
+        .execute(&mut *self.tx)
         .await?;
         Ok(())
     }
 
-    pub async fn insert_guild_member(
-        &self,
+    async fn insert_guild_member(
+        &mut self,
         guild_id: i64,
         member_id: i64,
         birth: Option<NaiveDate>,
@@ -211,7 +559,239 @@ impl ZundaBotDatabase {
             member_id,
             birth,
         )
-        .execute(&*self.pool)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_member_birth_if_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        let updated = sqlx::query_scalar!(
+            r#"
+        UPDATE guild_member
+        SET birth = $1
+        WHERE guild_id = $2 AND member_id = $3 AND birth IS NULL
+        RETURNING member_id
+        "#,
+            birth,
+            guild_id,
+            member_id,
+        )
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        Ok(updated.is_some())
+    }
+
+    async fn update_member_birth(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<bool> {
+        let updated = sqlx::query_scalar!(
+            r#"
+        UPDATE guild_member
+        SET birth = $1
+        WHERE guild_id = $2 AND member_id = $3 AND birth IS DISTINCT FROM $1
+        RETURNING member_id
+        "#,
+            birth,
+            guild_id,
+            member_id,
+        )
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        Ok(updated.is_some())
+    }
+
+    async fn update_member_birth_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<bool> {
+        let updated = sqlx::query_scalar!(
+            r#"
+        UPDATE guild_member
+        SET birth = NULL, last_notified = NULL
+        WHERE guild_id = $1 AND member_id = $2 AND birth IS NOT NULL
+        RETURNING member_id
+        "#,
+            guild_id,
+            member_id,
+        )
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        Ok(updated.is_some())
+    }
+
+    async fn upsert_guild(&mut self, guild: &Guild) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        INSERT INTO guild (guild_id, name, locale)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET name = EXCLUDED.name, locale = EXCLUDED.locale
+        "#,
+            guild.guild_id,
+            guild.name,
+            guild.locale,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_guild_member(&mut self, member: &GuildMember) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        INSERT INTO guild_member (guild_id, member_id, birth, last_notified)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id, member_id)
+        DO UPDATE SET birth = EXCLUDED.birth, last_notified = EXCLUDED.last_notified
+        "#,
+            member.guild_id,
+            member.member_id,
+            member.birth,
+            member.last_notified,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_guild_member_last_notified(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        last_notified: NaiveDate,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        UPDATE guild_member
+        SET last_notified = $1
+        WHERE guild_id = $2 AND member_id = $3
+        "#,
+            last_notified,
+            guild_id,
+            member_id,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_member_last_notified_none(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        UPDATE guild_member
+        SET last_notified = NULL
+        WHERE guild_id = $1 AND member_id = $2
+        "#,
+            guild_id,
+            member_id,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_notification(
+        &mut self,
+        notification: &BirthdayNotification,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        INSERT INTO birthday_notification
+            (guild_id, member_id, year, notify_date, status, channel_id, announcement_message_id,
+             reacted, reply_message_id, attempts, last_error, completed_at, reaction_count)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        ON CONFLICT (guild_id, member_id, year, notify_date)
+        DO UPDATE SET status = EXCLUDED.status,
+                      channel_id = EXCLUDED.channel_id,
+                      announcement_message_id = EXCLUDED.announcement_message_id,
+                      reacted = EXCLUDED.reacted,
+                      reply_message_id = EXCLUDED.reply_message_id,
+                      attempts = EXCLUDED.attempts,
+                      last_error = EXCLUDED.last_error,
+                      completed_at = EXCLUDED.completed_at,
+                      reaction_count = EXCLUDED.reaction_count
+        "#,
+            notification.guild_id,
+            notification.member_id,
+            notification.year,
+            notification.notify_date,
+            notification.status.as_str(),
+            notification.channel_id,
+            notification.announcement_message_id,
+            notification.reacted,
+            notification.reply_message_id,
+            notification.attempts,
+            notification.last_error,
+            notification.completed_at,
+            notification.reaction_count,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_notification(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        DELETE FROM birthday_notification
+        WHERE guild_id = $1 AND member_id = $2 AND year = $3
+        "#,
+            guild_id,
+            member_id,
+            year,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_unsent_notifications(
+        &mut self,
+        guild_id: i64,
+        member_id: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        DELETE FROM birthday_notification
+        WHERE guild_id = $1 AND member_id = $2 AND status <> 'sent'
+        "#,
+            guild_id,
+            member_id,
+        )
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_celebration_line(&mut self, line: &CelebrationLine) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+        INSERT INTO guild_celebration_line (guild_id, line_no, line)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id, line_no) DO UPDATE SET line = EXCLUDED.line
+        "#,
+            line.guild_id,
+            line.line_no,
+            line.line,
+        )
+        .execute(&mut *self.tx)
         .await?;
         Ok(())
     }
@@ -1,4 +1,6 @@
+mod cli;
 mod commands;
+mod config;
 mod data;
 mod models;
 mod res;
@@ -6,91 +8,410 @@ mod services;
 mod usecase;
 mod worker;
 
+use crate::cli::{AdminCommand, CliCommand, MigrateCommand};
 use crate::commands::birth::birth;
+use crate::commands::celebration::celebration;
+use crate::commands::dev::notify_as_of;
+use crate::commands::hooks::{
+    apply_user_cooldown, command_check, on_error, post_command, pre_command, InstrumentedFramework,
+};
+use crate::commands::member_menu::{set_member_birth, show_member_birth};
+use crate::commands::settings::settings;
+use crate::commands::share_english_descriptions;
+use crate::config::Config;
+use crate::data::backup::{backup_to_file, restore_from_file};
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
+use crate::data::store_factory::{copy_store, open_store};
 use crate::models::common::Data;
-use crate::services::healthcheck::run_healthcheck_server;
+use crate::models::data::NotificationStatus;
+use crate::services::clock::{Clock, SystemClock};
+use crate::services::command_registration::register_commands;
+use crate::services::healthcheck::{run_healthcheck_server, HealthState};
+use crate::services::logging::init_logging;
+use crate::services::shutdown::{wait_for_signal, Shutdown};
+use crate::usecase::admin_usecase::AdminUsecase;
+use crate::usecase::birth_history_usecase::BirthHistoryUsecase;
 use crate::usecase::birth_list_usecase::BirthListUsecase;
 use crate::usecase::birth_notify_usecase::BirthNotifyUsecase;
 use crate::usecase::birth_reset_usecase::BirthResetUsecase;
+use crate::usecase::birth_set_usecase::BirthSetUsecase;
+use crate::usecase::birth_show_usecase::BirthShowUsecase;
 use crate::usecase::birth_signup_usecase::BirthSignupUsecase;
+use crate::usecase::celebration_usecase::CelebrationUsecase;
 use crate::usecase::guild_update_usecase::GuildUpdateUsecase;
-use crate::worker::annual_birthday_notifier::AnnualBirthdayNotifier;
+use crate::usecase::settings_usecase::SettingsUsecase;
+use crate::worker::annual_birthday_notifier::{AnnualBirthdayNotifier, NotifierStatus};
+use crate::worker::leader_election::LeaderElection;
 use anyhow::Context as _;
 use commands::hello::hello;
 use dotenvy::dotenv;
 use poise::serenity_prelude as serenity;
 use serenity::model::gateway::GatewayIntents;
 use serenity::Client;
-use sqlx::postgres::PgPoolOptions;
 use std::env;
 use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
+    let config = Config::load()?;
+    init_logging(config.log_format)?;
 
-    let database_url = env::var("DATABASE_URL").context("'DATABASE_URL' was not found")?;
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .context("Failed to connect to PostgreSQL")?;
+    let args: Vec<String> = env::args().skip(1).collect();
+    match cli::parse_args(&args)? {
+        CliCommand::Serve {
+            allow_migration_failure,
+        } => {
+            // 設定でも指定できるようにする (コンテナの起動コマンドを変えずに済むように)
+            let allow_migration_failure = allow_migration_failure || config.allow_migration_failure;
+            serve(&config, allow_migration_failure).await
+        }
+        CliCommand::Migrate(command) => {
+            let store = open_configured_store(&config).await?;
+            run_migrate_command(store.as_ref(), command).await
+        }
+        CliCommand::CopyStore { from_url, to_url } => {
+            // データ移行用: ストア間のデータをコピーして終了する
+            let from = open_store(&from_url, config.database_max_connections).await?;
+            let to = open_store(&to_url, config.database_max_connections).await?;
+            to.migrate_up()
+                .await
+                .context("Failed to migrate the target store")?;
+            let snapshot = copy_store(from.as_ref(), to.as_ref()).await?;
+            println!(
+                "Copied {} guilds and {} members.",
+                snapshot.guilds.len(),
+                snapshot.members.len()
+            );
+            Ok(())
+        }
+        CliCommand::Backup { path } => {
+            let store = open_configured_store(&config).await?;
+            let snapshot = backup_to_file(store.as_ref(), &path).await?;
+            println!(
+                "Backed up {} guilds and {} members to {}.",
+                snapshot.guilds.len(),
+                snapshot.members.len(),
+                path.display()
+            );
+            Ok(())
+        }
+        CliCommand::Restore { path } => {
+            let store = open_configured_store(&config).await?;
+            // 空のDBにもリストアできるよう、先にスキーマを用意する
+            store
+                .migrate_up()
+                .await
+                .context("Failed to run migrations")?;
+            let snapshot = restore_from_file(store.as_ref(), &path).await?;
+            println!(
+                "Restored {} guilds and {} members from {}.",
+                snapshot.guilds.len(),
+                snapshot.members.len(),
+                path.display()
+            );
+            Ok(())
+        }
+        CliCommand::Admin(command) => {
+            let store = open_configured_store(&config).await?;
+            run_admin_command(&AdminUsecase::new(store, config.timezone)?, command).await
+        }
+    }
+}
+
+async fn open_configured_store(config: &Config) -> anyhow::Result<Arc<dyn BirthdayStore>> {
+    open_store(config.database_url()?, config.database_max_connections).await
+}
+
+async fn run_migrate_command(
+    store: &dyn BirthdayStore,
+    command: MigrateCommand,
+) -> anyhow::Result<()> {
+    match command {
+        MigrateCommand::Up => {
+            store.migrate_up().await?;
+            println!("All migrations are applied.");
+        }
+        MigrateCommand::Down(steps) => {
+            let reverted = store.migrate_down(steps).await?;
+            println!("Reverted {reverted} migration(s).");
+        }
+        MigrateCommand::Status => {
+            for status in store.migration_status().await? {
+                let state = if status.is_applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!("{:<8} {} {}", state, status.version, status.description);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_admin_command(admin: &AdminUsecase, command: AdminCommand) -> anyhow::Result<()> {
+    let format_date = |date: Option<chrono::NaiveDate>, format: &str| {
+        date.map_or("-".to_string(), |date| date.format(format).to_string())
+    };
+    match command {
+        AdminCommand::Guilds => {
+            for guild in admin.get_guilds().await? {
+                println!(
+                    "{}\t{}\t{}",
+                    guild.guild_id,
+                    guild.name,
+                    guild.locale.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        AdminCommand::SetLocale { guild_id, locale } => {
+            admin.set_guild_locale(guild_id, locale).await?;
+            println!(
+                "Updated locale: {}",
+                locale.map_or("-".to_string(), |locale| locale.to_string())
+            );
+        }
+        AdminCommand::Members { guild_id } => {
+            println!("member_id\tbirth\tlast_notified");
+            for member in admin.get_members(guild_id).await? {
+                println!(
+                    "{}\t{}\t{}",
+                    member.member_id,
+                    format_date(member.birth, "%m/%d"),
+                    format_date(member.last_notified, "%Y-%m-%d")
+                );
+            }
+        }
+        AdminCommand::GetBirth {
+            guild_id,
+            member_id,
+        } => {
+            let member = admin.get_member(guild_id, member_id).await?;
+            println!(
+                "birth: {}, last_notified: {}",
+                format_date(member.birth, "%m/%d"),
+                format_date(member.last_notified, "%Y-%m-%d")
+            );
+        }
+        AdminCommand::SetBirth {
+            guild_id,
+            member_id,
+            birth,
+        } => {
+            let member = admin.set_member_birth(guild_id, member_id, birth).await?;
+            println!("Updated birth: {}", format_date(member.birth, "%m/%d"));
+        }
+        AdminCommand::ClearNotified {
+            guild_id,
+            member_id,
+            year,
+        } => {
+            let cleared = admin.clear_last_notified(guild_id, member_id, year).await?;
+            println!("Cleared last_notified of {cleared} member(s).");
+        }
+        AdminCommand::NotifyDryRun { today } => {
+            let previews = admin.preview_notifications(today).await?;
+            println!(
+                "{} notification(s) would be sent on {today}.",
+                previews.len()
+            );
+            for preview in previews {
+                println!(
+                    "\n[guild {} / member {} / birth {}]\n{}\n--- reply ---\n{}",
+                    preview.guild_id,
+                    preview.member_id,
+                    preview.birth.format("%m/%d"),
+                    preview.announcement,
+                    preview.celebration
+                );
+            }
+        }
+        AdminCommand::FailedNotifications { guild_id } => {
+            let notifications = admin.get_failed_notifications(guild_id).await?;
+            println!("guild_id\tmember_id\tyear\tstatus\tattempts\tlast_error");
+            for notification in &notifications {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    notification.guild_id,
+                    notification.member_id,
+                    notification.year,
+                    notification.status,
+                    notification.attempts,
+                    notification.last_error.as_deref().unwrap_or("-")
+                );
+            }
+            if notifications
+                .iter()
+                .any(|notification| notification.status == NotificationStatus::Failed)
+            {
+                println!(
+                    "\nRun `admin retry-notification <GUILD_ID> <MEMBER_ID> <YEAR>` to send a failed notification again."
+                );
+            }
+        }
+        AdminCommand::RetryNotification {
+            guild_id,
+            member_id,
+            year,
+        } => {
+            if admin.retry_notification(guild_id, member_id, year).await? {
+                println!("Re-armed the notification. The running bot sends it on its next resume.");
+            } else {
+                println!("No failed or skipped notification was found.");
+            }
+        }
+    }
+    Ok(())
+}
 
-    if let Err(e) = sqlx::migrate!("db/migrations").run(&pool).await {
-        tracing::error!("Failed to run migrations: {:?}", e);
+async fn serve(config: &Config, allow_migration_failure: bool) -> anyhow::Result<()> {
+    let store = open_configured_store(config).await?;
+    // 壊れたスキーマのまま起動しないよう、マイグレーションに失敗した場合は起動を中止する
+    if let Err(e) = store.migrate_up().await {
+        if !allow_migration_failure {
+            return Err(e.context("Failed to run migrations"));
+        }
+        tracing::error!("Failed to run migrations (continuing by override): {:?}", e);
     }
 
-    let token = env::var("DISCORD_TOKEN").context("'DISCORD_TOKEN' was not found")?;
+    let shutdown = Shutdown::new();
+    let leader = Arc::new(LeaderElection::new(store.clone()));
+    tokio::spawn(leader.clone().run(shutdown.clone()));
+    let notifier_status = Arc::new(NotifierStatus::default());
+    let health_state = Arc::new(HealthState::new(store.clone(), notifier_status.clone()));
+
+    let token = config.discord_token()?;
 
     let intents = GatewayIntents::GUILD_MEMBERS // ギルドメンバー情報取得権限
         | GatewayIntents::GUILD_MESSAGES // ギルド内のメッセージイベント受信権限
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
+    let mut commands = vec![
+        // コマンドはここに追加
+        hello(),
+        birth(),
+        celebration(),
+        settings(),
+        show_member_birth(),
+        set_member_birth(),
+    ];
+    if cfg!(debug_assertions) {
+        // 開発用コマンドはデバッグビルドでのみ登録
+        commands.push(notify_as_of());
+    }
+    share_english_descriptions(&mut commands);
+    if let Some(cooldown) = config.command_cooldown {
+        apply_user_cooldown(&mut commands, cooldown);
+    }
+
+    let (timezone, notify_time, dev_guild_id) =
+        (config.timezone, config.notify_time, config.dev_guild_id);
+    let framework_store = store.clone();
+    let framework_shutdown = shutdown.clone();
+    let framework_leader = leader.clone();
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                // コマンドはここに追加
-                hello(),
-                birth(),
-            ],
+            commands,
+            on_error,
+            command_check: Some(command_check),
+            pre_command,
+            post_command,
             ..Default::default()
         })
         .setup(move |ctx, _ready, framework| {
-            let pool = Arc::new(pool.clone());
+            let store = framework_store;
+            let shutdown = framework_shutdown;
+            let leader = framework_leader;
             Box::pin(async move {
-                let birth_list_usecase = BirthListUsecase::new(pool.clone(), ctx.http.clone())?;
-                let birth_signup_usecase = BirthSignupUsecase::new(pool.clone(), ctx.http.clone())?;
-                let birth_reset_usecase = BirthResetUsecase::new(pool.clone(), ctx.http.clone())?;
-                let birth_notify_usecase = BirthNotifyUsecase::new(pool.clone(), ctx.http.clone())?;
-                let guild_update_usecase = GuildUpdateUsecase::new(pool.clone(), ctx.http.clone())?;
+                let discord: Arc<dyn DiscordGateway> = ctx.http.clone();
+                let birth_history_usecase =
+                    BirthHistoryUsecase::new(store.clone(), discord.clone())?;
+                let birth_list_usecase = BirthListUsecase::new(store.clone(), discord.clone())?;
+                let birth_signup_usecase =
+                    BirthSignupUsecase::new(store.clone(), discord.clone(), notify_time)?;
+                let birth_set_usecase = BirthSetUsecase::new(store.clone(), discord.clone())?;
+                let celebration_usecase = CelebrationUsecase::new(store.clone(), discord.clone())?;
+                let clock: Arc<dyn Clock> = Arc::new(SystemClock::new(timezone));
+                let birth_reset_usecase = BirthResetUsecase::new(store.clone(), discord.clone())?;
+                let birth_show_usecase =
+                    BirthShowUsecase::new(store.clone(), discord.clone(), clock.clone())?;
+                let birth_notify_usecase = Arc::new(BirthNotifyUsecase::new(
+                    store.clone(),
+                    discord.clone(),
+                    clock.clone(),
+                )?);
+                let guild_update_usecase = GuildUpdateUsecase::new(store.clone(), discord.clone())?;
+                let settings_usecase = SettingsUsecase::new(store.clone(), discord.clone())?;
                 guild_update_usecase.invoke().await?;
 
-                tokio::spawn(AnnualBirthdayNotifier::run(birth_notify_usecase));
+                tokio::spawn(AnnualBirthdayNotifier::run(
+                    birth_notify_usecase.clone(),
+                    clock,
+                    notifier_status,
+                    notify_time,
+                    shutdown.clone(),
+                    leader,
+                ));
 
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                register_commands(
+                    &ctx.http,
+                    store.as_ref(),
+                    &framework.options().commands,
+                    dev_guild_id,
+                )
+                .await?;
 
                 let data = Data {
+                    birth_history_usecase,
                     birth_list_usecase,
+                    birth_notify_usecase,
                     birth_signup_usecase,
                     birth_reset_usecase,
+                    birth_set_usecase,
+                    birth_show_usecase,
+                    celebration_usecase,
                     guild_update_usecase,
+                    settings_usecase,
+                    shutdown,
                 };
                 Ok(data)
             })
         })
         .build();
 
+    let mut client = Client::builder(token, intents)
+        .framework(InstrumentedFramework(framework))
+        .await?;
+    health_state.set_gateway(client.shard_manager.clone());
+    let shard_manager = client.shard_manager.clone();
     let bot = async {
-        let mut client = Client::builder(&token, intents)
-            .framework(framework)
-            .await?;
         client.start().await?;
         Ok::<(), anyhow::Error>(())
     };
 
-    tokio::select! {
-        result = run_healthcheck_server() => result.context("Healthcheck server stopped"),
+    // ボットやヘルスチェックが異常終了した場合も、下の停止処理を行ってからエラーを返す
+    let result = tokio::select! {
+        result = run_healthcheck_server(config.port, health_state) => result.context("Healthcheck server stopped"),
         result = bot => result.context("Discord bot stopped"),
+        _ = wait_for_signal() => Ok(()),
+    };
+
+    // 新しいコマンドと通知の受付を止め、実行中の処理 (ボタンの応答待ちを含む) の完了を待ってから Gateway から切断する
+    tracing::info!("Shutting down");
+    shutdown.request();
+    if !shutdown.wait_idle(config.shutdown_timeout).await {
+        tracing::warn!(
+            in_flight = shutdown.in_flight(),
+            "Shutdown timed out while waiting for in-flight work"
+        );
     }
+    shard_manager.shutdown_all().await;
+    // 通知が終わってからリースを手放し、他のインスタンスがすぐに引き継げるようにする
+    leader.release().await;
+    store.close().await;
+    tracing::info!("Shutdown complete");
+    result
 }
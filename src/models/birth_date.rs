@@ -0,0 +1,377 @@
+// 誕生日の入力の解析
+//
+// `/birth signup`・登録モーダル・管理用コマンドで共通して使う。
+// 「02/01」「2/1」「0201」「2-1」「2月1日」「Feb 1」「2000/02/01」「2000年2月1日」のような書き方を受け付け、
+// 年が含まれている場合は年も取り出す。月は常に日より先にあるものとして扱う (「1 Feb」のように月名を使う場合を除く)。
+
+use chrono::NaiveDate;
+use std::fmt;
+
+/// 年のない誕生日に使う仮の年 (2月29日も表せるよう、うるう年にする)
+const PLACEHOLDER_YEAR: i32 = 2000;
+/// 誕生日として受け付ける年の範囲
+pub const MIN_YEAR: i32 = 1900;
+pub const MAX_YEAR: i32 = 2100;
+
+const MONTH_NAMES: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BirthDate {
+    pub month: u32,
+    pub day: u32,
+    /// 入力に年が含まれていた場合のみ
+    pub year: Option<i32>,
+}
+
+impl BirthDate {
+    /// 保存する日付 (年がない場合は仮の年を使う)
+    pub fn to_date(self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year.unwrap_or(PLACEHOLDER_YEAR), self.month, self.day)
+            .expect("BirthDate is validated on parse")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BirthDateError {
+    Empty,
+    /// どの書き方にも当てはまらない
+    Unrecognized(String),
+    /// 書き方は正しいが、存在しない日付 (13月、2月30日、うるう年でない年の2月29日など)
+    Nonexistent {
+        month: u32,
+        day: u32,
+    },
+    YearOutOfRange(i32),
+}
+
+impl fmt::Display for BirthDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BirthDateError::Empty => f.write_str("birthday is empty"),
+            BirthDateError::Unrecognized(input) => {
+                write!(f, "unrecognized birthday format: {input:?}")
+            }
+            BirthDateError::Nonexistent { month, day } => {
+                write!(f, "date does not exist: month {month}, day {day}")
+            }
+            BirthDateError::YearOutOfRange(year) => {
+                write!(
+                    f,
+                    "year must be between {MIN_YEAR} and {MAX_YEAR}, got {year}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BirthDateError {}
+
+pub fn parse_birth_date(input: &str) -> Result<BirthDate, BirthDateError> {
+    let normalized = normalize(input);
+    if normalized.is_empty() {
+        return Err(BirthDateError::Empty);
+    }
+    let unrecognized = || BirthDateError::Unrecognized(input.trim().to_string());
+
+    let (year, month, day) = if normalized.contains('月') {
+        parse_japanese(&normalized)
+    } else if normalized.chars().any(|c| c.is_ascii_alphabetic()) {
+        parse_month_name(&normalized)
+    } else {
+        parse_numeric(&normalized)
+    }
+    .ok_or_else(unrecognized)?;
+
+    if let Some(year) = year {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return Err(BirthDateError::YearOutOfRange(year));
+        }
+    }
+    NaiveDate::from_ymd_opt(year.unwrap_or(PLACEHOLDER_YEAR), month, day)
+        .ok_or(BirthDateError::Nonexistent { month, day })?;
+    Ok(BirthDate { month, day, year })
+}
+
+/// 全角の数字・記号を半角にし、英字を小文字にする
+fn normalize(input: &str) -> String {
+    input
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            '／' => '/',
+            '－' | 'ー' => '-',
+            '．' => '.',
+            '，' | '、' => ',',
+            '　' => ' ',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// 「2月1日」「2000年2月1日」
+fn parse_japanese(input: &str) -> Option<(Option<i32>, u32, u32)> {
+    let (year, rest) = match input.split_once('年') {
+        Some((year, rest)) => (Some(parse_year(year.trim())?), rest),
+        None => (None, input),
+    };
+    let (month, day) = rest.split_once('月')?;
+    let day = day.trim();
+    let day = day.strip_suffix('日').unwrap_or(day);
+    Some((
+        year,
+        parse_month_day_part(month.trim())?,
+        parse_month_day_part(day.trim())?,
+    ))
+}
+
+/// 「Feb 1」「February 1st」「1 Feb」「Feb 1, 2000」
+fn parse_month_name(input: &str) -> Option<(Option<i32>, u32, u32)> {
+    let tokens = input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>();
+    if !(2..=3).contains(&tokens.len()) {
+        return None;
+    }
+
+    // 同じ種類の値が2回現れた場合は解釈できない
+    let (mut month, mut day, mut year) = (None, None, None);
+    for token in tokens {
+        let duplicated = if token.chars().all(|c| c.is_ascii_alphabetic()) {
+            // 3文字以上の先頭一致で月名とみなす (Feb・Sept など)
+            let index = MONTH_NAMES
+                .iter()
+                .position(|name| token.len() >= 3 && name.starts_with(token))?;
+            month.replace(index as u32 + 1).is_some()
+        } else if token.len() == 4 && token.chars().all(|c| c.is_ascii_digit()) {
+            year.replace(parse_year(token)?).is_some()
+        } else {
+            let digits = ["st", "nd", "rd", "th"]
+                .iter()
+                .find_map(|suffix| token.strip_suffix(suffix))
+                .unwrap_or(token);
+            day.replace(parse_month_day_part(digits)?).is_some()
+        };
+        if duplicated {
+            return None;
+        }
+    }
+    Some((year, month?, day?))
+}
+
+/// 「2/1」「02-01」「2.1」「0201」「2000/02/01」「20000201」「2/1/2000」
+fn parse_numeric(input: &str) -> Option<(Option<i32>, u32, u32)> {
+    let parts = input
+        .split(['/', '-', '.'])
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    match parts.as_slice() {
+        // 桁数はバイト数で確認するため、切り出す前に ASCII の数字だけであることを確認する
+        [digits] if digits.len() == 4 && is_ascii_digits(digits) => Some((
+            None,
+            parse_month_day_part(&digits[..2])?,
+            parse_month_day_part(&digits[2..])?,
+        )),
+        [digits] if digits.len() == 8 && is_ascii_digits(digits) => Some((
+            Some(parse_year(&digits[..4])?),
+            parse_month_day_part(&digits[4..6])?,
+            parse_month_day_part(&digits[6..])?,
+        )),
+        [month, day] => Some((
+            None,
+            parse_month_day_part(month)?,
+            parse_month_day_part(day)?,
+        )),
+        [year, month, day] if year.len() == 4 => Some((
+            Some(parse_year(year)?),
+            parse_month_day_part(month)?,
+            parse_month_day_part(day)?,
+        )),
+        [month, day, year] if year.len() == 4 => Some((
+            Some(parse_year(year)?),
+            parse_month_day_part(month)?,
+            parse_month_day_part(day)?,
+        )),
+        _ => None,
+    }
+}
+
+fn is_ascii_digits(part: &str) -> bool {
+    part.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// 1〜2桁の数字 (範囲の確認は日付を組み立てる際に行う)
+fn parse_month_day_part(part: &str) -> Option<u32> {
+    if part.is_empty() || part.len() > 2 || !is_ascii_digits(part) {
+        return None;
+    }
+    part.parse().ok()
+}
+
+fn parse_year(part: &str) -> Option<i32> {
+    if part.len() != 4 || !is_ascii_digits(part) {
+        return None;
+    }
+    part.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_birth_date, BirthDate, BirthDateError};
+    use chrono::NaiveDate;
+
+    fn month_day(month: u32, day: u32) -> Result<BirthDate, BirthDateError> {
+        Ok(BirthDate {
+            month,
+            day,
+            year: None,
+        })
+    }
+
+    fn full(year: i32, month: u32, day: u32) -> Result<BirthDate, BirthDateError> {
+        Ok(BirthDate {
+            month,
+            day,
+            year: Some(year),
+        })
+    }
+
+    #[test]
+    fn month_and_day_are_accepted_in_common_forms() {
+        for input in [
+            "02/01",
+            "2/1",
+            "2/01",
+            "0201",
+            "2-1",
+            "02-01",
+            "2.1",
+            " 2 / 1 ",
+            "２／１",
+            "2月1日",
+            "2月1",
+            "２月１日",
+            "Feb 1",
+            "feb 1",
+            "February 1",
+            "February 1st",
+            "1 Feb",
+            "FEB. 1",
+        ] {
+            assert_eq!(parse_birth_date(input), month_day(2, 1), "input: {input:?}");
+        }
+        assert_eq!(parse_birth_date("Sept 22nd"), month_day(9, 22));
+        assert_eq!(parse_birth_date("12/31"), month_day(12, 31));
+    }
+
+    #[test]
+    fn year_is_extracted_when_present() {
+        for input in [
+            "2000/02/01",
+            "2000-2-1",
+            "2000.02.01",
+            "20000201",
+            "2/1/2000",
+            "2000年2月1日",
+            "２０００年２月１日",
+            "Feb 1, 2000",
+            "February 1 2000",
+            "1 February 2000",
+        ] {
+            assert_eq!(
+                parse_birth_date(input),
+                full(2000, 2, 1),
+                "input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn leap_day_needs_a_leap_year_only_when_the_year_is_given() {
+        let leap_day = parse_birth_date("02/29").unwrap();
+        assert_eq!(leap_day, month_day(2, 29).unwrap());
+        assert_eq!(
+            leap_day.to_date(),
+            NaiveDate::from_ymd_opt(2000, 2, 29).unwrap()
+        );
+        assert_eq!(parse_birth_date("2004/02/29"), full(2004, 2, 29));
+        assert_eq!(
+            parse_birth_date("2001/02/29"),
+            Err(BirthDateError::Nonexistent { month: 2, day: 29 })
+        );
+    }
+
+    #[test]
+    fn nonexistent_dates_and_years_out_of_range_are_reported() {
+        assert_eq!(
+            parse_birth_date("13/01"),
+            Err(BirthDateError::Nonexistent { month: 13, day: 1 })
+        );
+        assert_eq!(
+            parse_birth_date("2月30日"),
+            Err(BirthDateError::Nonexistent { month: 2, day: 30 })
+        );
+        assert_eq!(
+            parse_birth_date("00/10"),
+            Err(BirthDateError::Nonexistent { month: 0, day: 10 })
+        );
+        assert_eq!(
+            parse_birth_date("1800/02/01"),
+            Err(BirthDateError::YearOutOfRange(1800))
+        );
+    }
+
+    #[test]
+    fn full_width_digits_are_normalized_before_slicing() {
+        assert_eq!(
+            parse_birth_date("１２３４"),
+            Err(BirthDateError::Nonexistent { month: 12, day: 34 })
+        );
+        assert_eq!(parse_birth_date("０２０１"), month_day(2, 1));
+        assert_eq!(parse_birth_date("２００００２０１"), full(2000, 2, 1));
+    }
+
+    #[test]
+    fn unrecognized_input_is_rejected() {
+        assert_eq!(parse_birth_date("  "), Err(BirthDateError::Empty));
+        for input in [
+            "birthday",
+            "201",
+            "2/1/00",
+            "123/1",
+            "2/1/2000/3",
+            "Feb",
+            "Feb Mar 1",
+            "Fe 1",
+            "Feb 1 2",
+            "2月",
+            "/1",
+            // 4・8バイトの非 ASCII 文字列 (文字の境界で切り出さないこと)
+            "あ1",
+            "é12",
+            "1234あ12",
+            "ああ12",
+            "12/あ1",
+        ] {
+            assert_eq!(
+                parse_birth_date(input),
+                Err(BirthDateError::Unrecognized(input.to_string())),
+                "input: {input:?}"
+            );
+        }
+    }
+}
@@ -1,13 +1,29 @@
+use crate::models::error::BotError;
+use crate::services::shutdown::Shutdown;
+use crate::usecase::birth_history_usecase::BirthHistoryUsecase;
 use crate::usecase::birth_list_usecase::BirthListUsecase;
+use crate::usecase::birth_notify_usecase::BirthNotifyUsecase;
 use crate::usecase::birth_reset_usecase::BirthResetUsecase;
+use crate::usecase::birth_set_usecase::BirthSetUsecase;
+use crate::usecase::birth_show_usecase::BirthShowUsecase;
 use crate::usecase::birth_signup_usecase::BirthSignupUsecase;
+use crate::usecase::celebration_usecase::CelebrationUsecase;
 use crate::usecase::guild_update_usecase::GuildUpdateUsecase;
+use crate::usecase::settings_usecase::SettingsUsecase;
+use std::sync::Arc;
 
 pub struct Data {
+    pub birth_history_usecase: BirthHistoryUsecase,
     pub birth_list_usecase: BirthListUsecase,
+    pub birth_notify_usecase: Arc<BirthNotifyUsecase>,
     pub birth_signup_usecase: BirthSignupUsecase,
     pub birth_reset_usecase: BirthResetUsecase,
+    pub birth_set_usecase: BirthSetUsecase,
+    pub birth_show_usecase: BirthShowUsecase,
+    pub celebration_usecase: CelebrationUsecase,
     pub guild_update_usecase: GuildUpdateUsecase,
+    pub settings_usecase: SettingsUsecase,
+    pub shutdown: Arc<Shutdown>,
 }
-pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Error = BotError;
 pub type Context<'c> = poise::Context<'c, Data, Error>;
@@ -1,9 +1,177 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct Guild {
+    pub guild_id: i64,
+    pub name: String,
+    /// ボットのメッセージの言語 (`ja` / `en`)。`None` の場合は日本語
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
 pub struct GuildMember {
     pub guild_id: i64,
     pub member_id: i64,
     pub birth: Option<NaiveDate>,
     pub last_notified: Option<NaiveDate>,
 }
+
+/// ギルドが登録したお祝いのセリフ
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct CelebrationLine {
+    pub guild_id: i64,
+    /// ギルド内の通し番号
+    pub line_no: i32,
+    pub line: String,
+}
+
+/// 誕生日通知(アウトボックス)の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationStatus {
+    /// 未送信、または送信の途中
+    Pending,
+    Sent,
+    /// 送信先のチャンネルがないなど、送信を見送った
+    Skipped,
+    /// 再試行の上限に達した
+    Failed,
+}
+
+impl NotificationStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationStatus::Pending => "pending",
+            NotificationStatus::Sent => "sent",
+            NotificationStatus::Skipped => "skipped",
+            NotificationStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for NotificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NotificationStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(NotificationStatus::Pending),
+            "sent" => Ok(NotificationStatus::Sent),
+            "skipped" => Ok(NotificationStatus::Skipped),
+            "failed" => Ok(NotificationStatus::Failed),
+            _ => anyhow::bail!("unknown notification status: {s}"),
+        }
+    }
+}
+
+// DB には TEXT として保存する
+impl<DB: sqlx::Database> sqlx::Type<DB> for NotificationStatus
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for NotificationStatus
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(String::decode(value)?.parse()?)
+    }
+}
+
+/// 1人・1年分の誕生日通知 (birthday_notification テーブル)
+///
+/// お知らせ・リアクション・リプライの各操作が終わるたびに進捗を記録し、
+/// 途中で失敗した場合は済んだ操作を飛ばして再開する。
+/// 送信を終えた行は削除せず、過去のお祝いの履歴として残す。
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
+pub struct BirthdayNotification {
+    pub guild_id: i64,
+    pub member_id: i64,
+    pub year: i32,
+    /// 通知する日 (うるう日生まれのメンバーは平年では2/28)
+    pub notify_date: NaiveDate,
+    pub status: NotificationStatus,
+    pub channel_id: Option<i64>,
+    pub announcement_message_id: Option<i64>,
+    pub reacted: bool,
+    pub reply_message_id: Option<i64>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// 送信済み・見送り・あきらめた日時 (送信済みの場合は送信日時)
+    pub completed_at: Option<DateTime<Utc>>,
+    /// お知らせについたリアクションの数 (ボット自身のものを除く、最後に確認した時点の値)
+    #[serde(default)]
+    pub reaction_count: i32,
+}
+
+impl BirthdayNotification {
+    pub fn new(guild_id: i64, member_id: i64, year: i32, notify_date: NaiveDate) -> Self {
+        BirthdayNotification {
+            guild_id,
+            member_id,
+            year,
+            notify_date,
+            status: NotificationStatus::Pending,
+            channel_id: None,
+            announcement_message_id: None,
+            reacted: false,
+            reply_message_id: None,
+            attempts: 0,
+            last_error: None,
+            completed_at: None,
+            reaction_count: 0,
+        }
+    }
+}
+
+/// ストアに保存されている全データ
+///
+/// ファイルへの保存やストア間のデータ移行で利用する。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreSnapshot {
+    pub version: u32,
+    pub guilds: Vec<Guild>,
+    pub members: Vec<GuildMember>,
+    #[serde(default)]
+    pub notifications: Vec<BirthdayNotification>,
+    #[serde(default)]
+    pub celebration_lines: Vec<CelebrationLine>,
+}
+
+impl StoreSnapshot {
+    pub const CURRENT_VERSION: u32 = 1;
+}
+
+/// マイグレーション1件分の適用状況
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub is_applied: bool,
+}
+
+/// DB のコネクションプールの使用状況
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: u32,
+    pub max_connections: u32,
+}
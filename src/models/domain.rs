@@ -1,4 +1,5 @@
 use chrono::NaiveDate;
+use poise::serenity_prelude::{ChannelId, MessageId};
 
 #[derive(Debug)]
 pub struct MyGuild {
@@ -13,3 +14,47 @@ pub struct MyGuildMember {
     pub member_id: i64,
     pub birth: Option<NaiveDate>,
 }
+
+#[derive(Debug, Clone)]
+pub struct MyChannel {
+    pub id: ChannelId,
+    pub name: String,
+    pub is_text: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MyMemberProfile {
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Discordへ送信するメッセージの内容
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OutgoingMessage {
+    pub content: String,
+    pub embed: Option<OutgoingEmbed>,
+    pub reply_to: Option<MessageId>,
+    /// 再送時に同じメッセージを重複して投稿しないための識別子 (25文字以内)
+    ///
+    /// Discord は同じ nonce のメッセージが数分以内に送信済みの場合、新しく投稿せずに既存のメッセージを返す。
+    pub nonce: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OutgoingEmbed {
+    pub title: String,
+    pub thumbnail: Option<String>,
+    pub description: String,
+}
+
+/// 誕生日通知で送信する予定の内容
+///
+/// Discord に接続せずに通知内容を確認する(ドライラン)ために使う。
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationPreview {
+    pub guild_id: i64,
+    pub member_id: i64,
+    pub birth: NaiveDate,
+    pub announcement: String,
+    pub celebration: String,
+}
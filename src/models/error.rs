@@ -0,0 +1,169 @@
+// コマンドの実行中に発生するエラー
+//
+// 原因ごとにユーザーへの返信を変え、ログにはエラーコードを残す。
+// DB や Discord API の呼び出しは anyhow::Error を返すため、中身の型から原因を判別する。
+// serenity::Error は大きいため、Result を小さく保つようボックス化して持つ。
+
+use crate::models::birth_date::{BirthDateError, MAX_YEAR, MIN_YEAR};
+use crate::res::messages::Message;
+use poise::serenity_prelude as serenity;
+use std::fmt;
+
+/// 権限不足を表す Discord API のエラーコード
+const MISSING_PERMISSIONS: isize = 50013;
+
+#[derive(Debug)]
+pub enum BotError {
+    /// ギルド専用のコマンドが DM で実行された
+    GuildOnly,
+    /// 入力された誕生日を読み取れない
+    InvalidDate(BirthDateError),
+    /// ボットに必要な権限がない
+    MissingPermission(Box<serenity::Error>),
+    /// DB に接続できない、またはクエリに失敗した
+    Database(anyhow::Error),
+    /// Discord API の呼び出しに失敗した
+    Discord(Box<serenity::Error>),
+    /// 上記以外の予期しないエラー
+    Unexpected(anyhow::Error),
+}
+
+impl BotError {
+    /// ログに出力するエラーコード
+    pub fn code(&self) -> &'static str {
+        match self {
+            BotError::GuildOnly => "guild_only",
+            BotError::InvalidDate(_) => "invalid_date",
+            BotError::MissingPermission(_) => "missing_permission",
+            BotError::Database(_) => "database_unavailable",
+            BotError::Discord(_) => "discord_api",
+            BotError::Unexpected(_) => "unexpected",
+        }
+    }
+
+    /// ユーザーの操作が原因のエラーかどうか (ボット側の障害ではないもの)
+    pub fn is_user_error(&self) -> bool {
+        matches!(self, BotError::GuildOnly | BotError::InvalidDate(_))
+    }
+
+    /// 実行したユーザーに返すメッセージ
+    pub fn message(&self) -> Message {
+        match self {
+            BotError::GuildOnly => Message::GuildOnly,
+            BotError::InvalidDate(BirthDateError::Nonexistent { month, day }) => {
+                Message::NonexistentBirthDate {
+                    month: *month,
+                    day: *day,
+                }
+            }
+            BotError::InvalidDate(BirthDateError::YearOutOfRange(_)) => {
+                Message::BirthYearOutOfRange {
+                    min: MIN_YEAR,
+                    max: MAX_YEAR,
+                }
+            }
+            BotError::InvalidDate(_) => Message::InvalidBirthFormat,
+            BotError::MissingPermission(_) => Message::MissingPermission,
+            BotError::Database(_) => Message::DatabaseUnavailable,
+            BotError::Discord(_) | BotError::Unexpected(_) => Message::CommandFailed,
+        }
+    }
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::GuildOnly => f.write_str("the command was used outside of a guild"),
+            BotError::InvalidDate(e) => write!(f, "invalid date: {e}"),
+            BotError::MissingPermission(e) => write!(f, "missing permission: {e}"),
+            BotError::Database(e) => write!(f, "database error: {e}"),
+            BotError::Discord(e) => write!(f, "Discord API error: {e}"),
+            BotError::Unexpected(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for BotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BotError::InvalidDate(e) => Some(e),
+            BotError::MissingPermission(e) | BotError::Discord(e) => Some(e.as_ref()),
+            BotError::Database(e) | BotError::Unexpected(e) => Some(e.as_ref()),
+            BotError::GuildOnly => None,
+        }
+    }
+}
+
+impl From<serenity::Error> for BotError {
+    fn from(e: serenity::Error) -> Self {
+        let is_missing_permission = match &e {
+            serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response)) => {
+                response.error.code == MISSING_PERMISSIONS
+            }
+            serenity::Error::Model(serenity::ModelError::InvalidPermissions { .. }) => true,
+            _ => false,
+        };
+        if is_missing_permission {
+            BotError::MissingPermission(Box::new(e))
+        } else {
+            BotError::Discord(Box::new(e))
+        }
+    }
+}
+
+impl From<anyhow::Error> for BotError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<BotError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<serenity::Error>() {
+            Ok(e) => return BotError::from(e),
+            Err(e) => e,
+        };
+        // PostgreSQL・SQLite は sqlx、ファイルストアは入出力のエラーを返す
+        if e.is::<sqlx::Error>() || e.is::<std::io::Error>() {
+            BotError::Database(e)
+        } else {
+            BotError::Unexpected(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BotError;
+    use crate::models::birth_date::BirthDateError;
+    use crate::res::messages::Message;
+    use poise::serenity_prelude as serenity;
+
+    #[test]
+    fn wrapped_errors_are_classified_by_cause() {
+        let database = BotError::from(anyhow::Error::new(sqlx::Error::PoolTimedOut));
+        assert_eq!(database.code(), "database_unavailable");
+        assert_eq!(database.message(), Message::DatabaseUnavailable);
+
+        let permission = BotError::from(anyhow::Error::new(serenity::Error::Model(
+            serenity::ModelError::InvalidPermissions {
+                required: serenity::Permissions::SEND_MESSAGES,
+                present: serenity::Permissions::empty(),
+            },
+        )));
+        assert_eq!(permission.code(), "missing_permission");
+
+        let nested = BotError::from(anyhow::Error::new(BotError::GuildOnly));
+        assert_eq!(nested.code(), "guild_only");
+        assert!(nested.is_user_error());
+
+        let nonexistent = BotError::InvalidDate(BirthDateError::Nonexistent { month: 2, day: 30 });
+        assert_eq!(
+            nonexistent.message(),
+            Message::NonexistentBirthDate { month: 2, day: 30 }
+        );
+
+        let unexpected = BotError::from(anyhow::anyhow!("boom"));
+        assert_eq!(unexpected.code(), "unexpected");
+        assert_eq!(unexpected.message(), Message::CommandFailed);
+        assert!(!unexpected.is_user_error());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod birth_date;
 pub mod common;
 pub mod data;
 pub mod domain;
+pub mod error;
+pub mod hash;
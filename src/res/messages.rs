@@ -0,0 +1,417 @@
+// ユーザーに表示するメッセージのカタログ (日本語・英語)
+//
+// コマンドへの返信は Discord の表示言語、誕生日のお知らせはギルドに設定した言語で表示する。
+// どちらにも対応していない言語の場合は日本語にする。
+// 言い回しを変えながら送るずんだもんのセリフは persona にまとめている。
+
+use chrono::{NaiveDate, NaiveTime};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Locale {
+    /// Discord のロケール (`ja`, `en-US`, `en-GB` など) から対応する言語を返す
+    pub fn from_discord(locale: &str) -> Option<Self> {
+        match locale {
+            "ja" => Some(Locale::Ja),
+            locale if locale.starts_with("en") => Some(Locale::En),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Ja => "ja",
+            Locale::En => "en",
+        }
+    }
+
+    /// 言語の表示名
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::Ja => "日本語",
+            Locale::En => "English",
+        }
+    }
+
+    /// 誕生日の表示 (年なし)
+    pub fn format_month_day(&self, date: NaiveDate) -> String {
+        match self {
+            Locale::Ja => date.format("%-m月%-d日").to_string(),
+            Locale::En => date.format("%B %-d").to_string(),
+        }
+    }
+
+    /// 通知日などの表示 (年あり)
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        match self {
+            Locale::Ja => date.format("%Y年%-m月%-d日").to_string(),
+            Locale::En => date.format("%B %-d, %Y").to_string(),
+        }
+    }
+
+    /// 通知時刻などの表示
+    pub fn format_time(&self, time: NaiveTime) -> String {
+        match self {
+            Locale::Ja => time.format("%-H時%M分").to_string(),
+            Locale::En => time.format("%-I:%M %p").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ja" => Ok(Locale::Ja),
+            "en" => Ok(Locale::En),
+            _ => anyhow::bail!("unknown locale: {s} (expected ja or en)"),
+        }
+    }
+}
+
+/// ユーザーに表示するメッセージ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    /// 停止処理中に新しいコマンドを受け付けなかった
+    Restarting,
+    /// コマンドの実行中に予期しないエラーが発生した
+    CommandFailed,
+    /// ギルド専用のコマンドが DM で実行された
+    GuildOnly,
+    /// DB に接続できない
+    DatabaseUnavailable,
+    /// ボットに必要な権限がない
+    MissingPermission,
+    /// 実行したユーザーにコマンドの権限がない
+    MissingUserPermission,
+    /// コマンドの引数を読み取れない
+    InvalidArgument,
+    /// 同じコマンドを続けて実行しすぎた
+    Cooldown {
+        remaining_secs: u64,
+    },
+    SignupModalTitle,
+    SignupModalField,
+    InvalidBirthFormat,
+    NonexistentBirthDate {
+        month: u32,
+        day: u32,
+    },
+    BirthYearOutOfRange {
+        min: i32,
+        max: i32,
+    },
+    SignupCompleted,
+    /// 登録した日付の何時に通知されるか
+    SignupCompletedNote {
+        notify_time: NaiveTime,
+    },
+    AlreadySignedUp,
+    BirthNotRegistered,
+    ResetConfirm,
+    ResetButton,
+    ResetCompleted,
+    ResetCompletedDescription,
+    BirthListTitle,
+    BirthShowTitle,
+    /// 他のメンバーの誕生日が登録されていない
+    MemberBirthNotRegistered,
+    /// 次の誕生日までの日数
+    DaysUntilBirthday {
+        days: i64,
+    },
+    BirthdayToday,
+    /// モデレーターがメンバーの誕生日を設定するモーダル
+    BirthSetModalTitle,
+    BirthSetModalField,
+    MemberBirthSet,
+    MemberBirthCleared,
+    HistoryEmpty,
+    HistoryTitle,
+    HistoryJumpLink,
+    CelebrationAdded {
+        line_no: i32,
+    },
+    CelebrationTooLong {
+        max_chars: usize,
+    },
+    CelebrationLimitReached {
+        max_lines: usize,
+    },
+    CelebrationListTitle,
+    CelebrationListEmpty,
+    CelebrationRemoved {
+        line_no: i32,
+    },
+    CelebrationNotFound {
+        line_no: i32,
+    },
+    /// ギルドのお知らせの言語を設定した
+    GuildLocaleSet {
+        locale: Locale,
+    },
+    /// 開発用コマンド notify_as_of の基準日を読み取れない
+    NotifyAsOfInvalidDate,
+    NotifyAsOfCompleted {
+        date: NaiveDate,
+        notified_count: usize,
+    },
+}
+
+impl Message {
+    pub fn text(&self, locale: Locale) -> String {
+        match locale {
+            Locale::Ja => self.ja(),
+            Locale::En => self.en(),
+        }
+    }
+
+    fn ja(&self) -> String {
+        let text = match self {
+            Message::Restarting => "ただいま再起動中なのだ。少し待ってから再実行してほしいのだ。",
+            Message::CommandFailed => {
+                "コマンドの実行中にエラーが発生したのだ。時間をおいて再実行してほしいのだ。"
+            }
+            Message::GuildOnly => "🚨 このコマンドはサーバーの中でだけ使えるのだ。",
+            Message::DatabaseUnavailable => {
+                "🚨 いまデータベースにつながらないのだ。少し待ってから再実行してほしいのだ。"
+            }
+            Message::MissingPermission => {
+                "🚨 ずんだもんに必要な権限がないのだ。サーバーの管理者に確認してほしいのだ。"
+            }
+            Message::MissingUserPermission => "🚨 このコマンドを実行する権限がないのだ。",
+            Message::InvalidArgument => {
+                "🚨 入力された値を読み取れなかったのだ。入力を確認してほしいのだ。"
+            }
+            Message::Cooldown { remaining_secs } => {
+                return format!(
+                    "⏳ 続けて実行しすぎなのだ。{remaining_secs} 秒待ってから再実行してほしいのだ。"
+                );
+            }
+            Message::SignupModalTitle => "誕生日の通知登録",
+            Message::SignupModalField => "自身の誕生日を入力するのだ (例: 02/01)",
+            Message::InvalidBirthFormat => {
+                "🚨  誕生日を読み取れなかったのだ。「02/01」「2月1日」「2000/02/01」のように入力してほしいのだ。"
+            }
+            Message::NonexistentBirthDate { month, day } => {
+                return format!("🚨  {month}月{day}日は存在しない日付なのだ。入力を確認してほしいのだ。");
+            }
+            Message::BirthYearOutOfRange { min, max } => {
+                return format!("🚨  年は {min}〜{max} 年の間で入力してほしいのだ。");
+            }
+            Message::SignupCompleted => "✅  誕生日の通知登録が完了したのだ。",
+            Message::SignupCompletedNote { notify_time } => {
+                return format!(
+                    "登録した日付の {} に誕生日が通知されるのだ。",
+                    Locale::Ja.format_time(*notify_time)
+                );
+            }
+            Message::AlreadySignedUp => "⚠️ 誕生日はすでに登録済みなのだ",
+            Message::BirthNotRegistered => "⚠️ 誕生日が登録されていないのだ",
+            Message::ResetConfirm => "誕生日の通知登録を解除するのだ⚠️",
+            Message::ResetButton => "解除",
+            Message::ResetCompleted => "🗑️ 誕生日の通知登録を解除したのだ。",
+            Message::ResetCompletedDescription => "登録した日付はリセットされたのだ。",
+            Message::BirthListTitle => "🎉 誕生日リスト",
+            Message::BirthShowTitle => "🎂 誕生日",
+            Message::MemberBirthNotRegistered => "⚠️ このメンバーの誕生日は登録されていないのだ",
+            Message::DaysUntilBirthday { days } => {
+                return format!("誕生日まであと {days} 日なのだ。");
+            }
+            Message::BirthdayToday => "🎉 今日が誕生日なのだ！",
+            Message::BirthSetModalTitle => "メンバーの誕生日を設定",
+            Message::BirthSetModalField => "誕生日 (例: 02/01、空欄で登録を解除)",
+            Message::MemberBirthSet => "✅ メンバーの誕生日を設定したのだ。",
+            Message::MemberBirthCleared => "🗑️ メンバーの誕生日の登録を解除したのだ。",
+            Message::HistoryEmpty => "⚠️ まだお祝いの記録がないのだ",
+            Message::HistoryTitle => "📜 誕生日のお祝い履歴",
+            Message::HistoryJumpLink => "お知らせを見る",
+            Message::CelebrationAdded { line_no } => {
+                return format!("✅ お祝いのセリフを #{line_no} として登録したのだ。");
+            }
+            Message::CelebrationTooLong { max_chars } => {
+                return format!("🚨 セリフは {max_chars} 文字以内にしてほしいのだ。");
+            }
+            Message::CelebrationLimitReached { max_lines } => {
+                return format!(
+                    "⚠️ 登録できるセリフは {max_lines} 件までなのだ。どれかを削除してほしいのだ。"
+                );
+            }
+            Message::CelebrationListTitle => "🎤 このサーバーのお祝いのセリフ",
+            Message::CelebrationListEmpty => "⚠️ まだお祝いのセリフが登録されていないのだ",
+            Message::CelebrationRemoved { line_no } => {
+                return format!("🗑️ お祝いのセリフ #{line_no} を削除したのだ。");
+            }
+            Message::CelebrationNotFound { line_no } => {
+                return format!("⚠️ お祝いのセリフ #{line_no} は見つからないのだ");
+            }
+            Message::GuildLocaleSet { locale } => {
+                return format!("✅ このサーバーの誕生日のお知らせを{}にしたのだ。", locale.name());
+            }
+            Message::NotifyAsOfInvalidDate => "日付は YYYY-MM-DD の形式で入力してほしいのだ。",
+            Message::NotifyAsOfCompleted {
+                date,
+                notified_count,
+            } => {
+                return format!(
+                    "{date} 時点の誕生日通知を実行したのだ。通知したメンバー: {notified_count}人"
+                );
+            }
+        };
+        text.to_string()
+    }
+
+    fn en(&self) -> String {
+        let text = match self {
+            Message::Restarting => "I'm restarting right now. Please try again in a moment.",
+            Message::CommandFailed => {
+                "Something went wrong while running the command. Please try again later."
+            }
+            Message::GuildOnly => "🚨 This command can only be used in a server.",
+            Message::DatabaseUnavailable => {
+                "🚨 The database is unavailable right now. Please try again in a little while."
+            }
+            Message::MissingPermission => {
+                "🚨 I don't have the permissions needed for this. Please ask a server admin."
+            }
+            Message::MissingUserPermission => "🚨 You don't have permission to run this command.",
+            Message::InvalidArgument => {
+                "🚨 I couldn't read the input. Please check it and try again."
+            }
+            Message::Cooldown { remaining_secs } => {
+                return format!(
+                    "⏳ You're going a bit fast. Please try again in {remaining_secs} seconds."
+                );
+            }
+            Message::SignupModalTitle => "Birthday notification signup",
+            Message::SignupModalField => "Enter your birthday (e.g. 02/01)",
+            Message::InvalidBirthFormat => {
+                "🚨  I couldn't read that birthday. Please enter it like \"02/01\", \"Feb 1\" or \"2000/02/01\"."
+            }
+            Message::NonexistentBirthDate { month, day } => {
+                return format!(
+                    "🚨  Month {month}, day {day} is not a valid date. Please check your input."
+                );
+            }
+            Message::BirthYearOutOfRange { min, max } => {
+                return format!("🚨  The year must be between {min} and {max}.");
+            }
+            Message::SignupCompleted => "✅  Your birthday notification is registered.",
+            Message::SignupCompletedNote { notify_time } => {
+                return format!(
+                    "Your birthday will be announced at {} on the registered date.",
+                    Locale::En.format_time(*notify_time)
+                );
+            }
+            Message::AlreadySignedUp => "⚠️ Your birthday is already registered",
+            Message::BirthNotRegistered => "⚠️ No birthday is registered",
+            Message::ResetConfirm => "This will remove your birthday notification⚠️",
+            Message::ResetButton => "Remove",
+            Message::ResetCompleted => "🗑️ Your birthday notification was removed.",
+            Message::ResetCompletedDescription => "The registered date has been cleared.",
+            Message::BirthListTitle => "🎉 Birthdays",
+            Message::BirthShowTitle => "🎂 Birthday",
+            Message::MemberBirthNotRegistered => "⚠️ This member has not registered a birthday",
+            Message::DaysUntilBirthday { days: 1 } => "1 day until the birthday.",
+            Message::DaysUntilBirthday { days } => {
+                return format!("{days} days until the birthday.");
+            }
+            Message::BirthdayToday => "🎉 The birthday is today!",
+            Message::BirthSetModalTitle => "Set member's birthday",
+            Message::BirthSetModalField => "Birthday (e.g. 02/01, empty to clear)",
+            Message::MemberBirthSet => "✅ The member's birthday was set.",
+            Message::MemberBirthCleared => "🗑️ The member's birthday was cleared.",
+            Message::HistoryEmpty => "⚠️ No birthdays have been celebrated yet",
+            Message::HistoryTitle => "📜 Birthday celebration history",
+            Message::HistoryJumpLink => "View announcement",
+            Message::CelebrationAdded { line_no } => {
+                return format!("✅ Added the celebration line as #{line_no}.");
+            }
+            Message::CelebrationTooLong { max_chars } => {
+                return format!("🚨 Lines must be at most {max_chars} characters.");
+            }
+            Message::CelebrationLimitReached { max_lines } => {
+                return format!(
+                    "⚠️ Up to {max_lines} lines can be registered. Please remove one first."
+                );
+            }
+            Message::CelebrationListTitle => "🎤 Celebration lines of this server",
+            Message::CelebrationListEmpty => "⚠️ No celebration lines are registered yet",
+            Message::CelebrationRemoved { line_no } => {
+                return format!("🗑️ Removed celebration line #{line_no}.");
+            }
+            Message::CelebrationNotFound { line_no } => {
+                return format!("⚠️ Celebration line #{line_no} was not found");
+            }
+            Message::GuildLocaleSet { locale } => {
+                return format!(
+                    "✅ Birthday announcements in this server are now in {}.",
+                    locale.name()
+                );
+            }
+            Message::NotifyAsOfInvalidDate => "Please enter the date as YYYY-MM-DD.",
+            Message::NotifyAsOfCompleted {
+                date,
+                notified_count,
+            } => {
+                return format!(
+                    "Ran the birthday notifications as of {date}. Members notified: {notified_count}"
+                );
+            }
+        };
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locale, Message};
+    use chrono::{NaiveDate, NaiveTime};
+
+    #[test]
+    fn discord_locales_fall_back_to_none_when_unsupported() {
+        assert_eq!(Locale::from_discord("ja"), Some(Locale::Ja));
+        assert_eq!(Locale::from_discord("en-US"), Some(Locale::En));
+        assert_eq!(Locale::from_discord("en-GB"), Some(Locale::En));
+        assert_eq!(Locale::from_discord("fr"), None);
+        assert_eq!("en".parse::<Locale>().unwrap(), Locale::En);
+        assert!("en-US".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn dates_and_messages_follow_the_locale() {
+        let date = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        assert_eq!(Locale::Ja.format_month_day(date), "2月1日");
+        assert_eq!(Locale::En.format_month_day(date), "February 1");
+        assert_eq!(Locale::Ja.format_date(date), "2025年2月1日");
+        assert_eq!(Locale::En.format_date(date), "February 1, 2025");
+        let note = Message::SignupCompletedNote {
+            notify_time: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+        };
+        assert_eq!(
+            note.text(Locale::Ja),
+            "登録した日付の 9時30分 に誕生日が通知されるのだ。"
+        );
+        assert_eq!(
+            note.text(Locale::En),
+            "Your birthday will be announced at 9:30 AM on the registered date."
+        );
+        let removed = Message::CelebrationRemoved { line_no: 3 };
+        assert!(removed.text(Locale::Ja).contains("#3 を削除した"));
+        assert_eq!(removed.text(Locale::En), "🗑️ Removed celebration line #3.");
+    }
+}
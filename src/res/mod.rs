@@ -1 +1,3 @@
 pub mod colors;
+pub mod messages;
+pub mod persona;
@@ -0,0 +1,150 @@
+// ずんだもんのセリフ集
+//
+// 同じ文面が毎回続かないよう、セリフごとに複数の言い回しを用意し、シードまたはランダムに選ぶ。
+// お祝いのリプライには、ギルドが登録したセリフも候補に加える。
+// セリフ中の `{mention}` は、送信時にメンバーのメンションに置き換える。
+
+use crate::res::messages::Locale;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+const MENTION: &str = "{mention}";
+
+/// セリフの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    Hello,
+    /// 誕生日のお知らせ本文
+    Announcement,
+    /// お知らせへのリプライとして送るお祝いメッセージ
+    Celebration,
+}
+
+/// 言い回しの選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    /// 同じシードからは同じ言い回しを選ぶ (再送やドライランで文面を変えないため)
+    Seed(u64),
+    Random,
+}
+
+impl Pick {
+    fn index(&self, len: usize) -> usize {
+        let seed = match self {
+            Pick::Seed(seed) => *seed,
+            Pick::Random => RandomState::new().hash_one(()),
+        };
+        (seed % len as u64) as usize
+    }
+}
+
+impl Line {
+    /// 用意している言い回し (空にはしない)
+    pub fn variants(&self, locale: Locale) -> &'static [&'static str] {
+        match (self, locale) {
+            (Line::Hello, Locale::Ja) => &[
+                "こんにちは、なのだ!",
+                "ずんだもんなのだ。呼んだのだ?",
+                "やっほーなのだ! 今日もいい日になるといいのだ!",
+            ],
+            (Line::Hello, Locale::En) => &[
+                "Hello, nanoda!",
+                "Zundamon is here, nanoda. Did you call?",
+                "Hi there! Hope today is a great day, nanoda!",
+            ],
+            (Line::Announcement, Locale::Ja) => &[
+                "@here\n今日は「🎂 {mention} さんのお誕生日 🎂」！\n\n今年も自分らしい１年を過ごせるとよきなのだ！！！",
+                "@here\n🎂 今日は {mention} さんのお誕生日なのだ 🎂\n\nみんなでお祝いするのだ！！！",
+                "@here\nお知らせなのだ！今日は「🎂 {mention} さんのお誕生日 🎂」なのだ！\n\n素敵な１年になりますように、なのだ！",
+            ],
+            (Line::Announcement, Locale::En) => &[
+                "@here\nToday is 🎂 {mention}'s birthday 🎂!\n\nHope you have a year that's truly yours!!!",
+                "@here\n🎂 It's {mention}'s birthday today, nanoda 🎂\n\nLet's all celebrate!!!",
+                "@here\nBig news, nanoda! Today is 🎂 {mention}'s birthday 🎂!\n\nWishing you a wonderful year ahead!",
+            ],
+            (Line::Celebration, Locale::Ja) => &[
+                "{mention} さん\nお誕生日おめでとうなのだ🎉\nいつもありがとなのだ！",
+                "{mention} さん\nハッピーバースデーなのだ🎂\nずんだもちでお祝いするのだ！",
+                "{mention} さん\nおめでとうなのだ🎉\n今年もいっぱい楽しいことがあるといいのだ！",
+                "{mention} さん\nお誕生日なのだ！🎉\nこれからもよろしくなのだ！",
+            ],
+            (Line::Celebration, Locale::En) => &[
+                "{mention}\nHappy birthday, nanoda🎉\nThank you for always being here!",
+                "{mention}\nHappy birthday🎂\nLet's celebrate with zunda mochi, nanoda!",
+                "{mention}\nCongratulations, nanoda🎉\nHope this year is full of fun!",
+                "{mention}\nIt's your birthday, nanoda!🎉\nLooking forward to another year together!",
+            ],
+        }
+    }
+
+    /// 言い回しを1つ選び、`{mention}` を置き換えて返す
+    pub fn text(&self, locale: Locale, member_id: i64, pick: Pick) -> String {
+        let variants = self.variants(locale);
+        render(variants[pick.index(variants.len())], member_id)
+    }
+}
+
+/// お祝いのリプライを、用意している言い回しとギルドが登録したセリフから選ぶ
+///
+/// ギルドのセリフに `{mention}` がない場合は、先頭にメンションをつける。
+pub fn celebration(locale: Locale, member_id: i64, custom_lines: &[String], pick: Pick) -> String {
+    let variants = Line::Celebration.variants(locale);
+    let index = pick.index(variants.len() + custom_lines.len());
+    match custom_lines.get(index.wrapping_sub(variants.len())) {
+        Some(line) if line.contains(MENTION) => render(line, member_id),
+        Some(line) => format!("<@{member_id}>\n{line}"),
+        None => render(variants[index], member_id),
+    }
+}
+
+fn render(template: &str, member_id: i64) -> String {
+    template.replace(MENTION, &format!("<@{member_id}>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{celebration, Line, Pick};
+    use crate::res::messages::Locale;
+
+    #[test]
+    fn same_seed_picks_the_same_variant() {
+        for line in [Line::Hello, Line::Announcement, Line::Celebration] {
+            for locale in [Locale::Ja, Locale::En] {
+                assert!(line.variants(locale).len() > 1);
+            }
+        }
+        let first = Line::Announcement.text(Locale::Ja, 10, Pick::Seed(7));
+        assert_eq!(
+            first,
+            Line::Announcement.text(Locale::Ja, 10, Pick::Seed(7))
+        );
+        assert!(first.contains("<@10>"));
+        assert!(!first.contains("{mention}"));
+        let all = (0..3)
+            .map(|seed| Line::Announcement.text(Locale::Ja, 10, Pick::Seed(seed)))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn guild_lines_join_the_celebration_candidates() {
+        let builtin = Line::Celebration.variants(Locale::Ja).len() as u64;
+        let custom = vec![
+            "ずんだ餅を用意したのだ🍡".to_string(),
+            "{mention} さん、今年も一緒に遊ぶのだ！".to_string(),
+        ];
+
+        assert_eq!(
+            celebration(Locale::Ja, 10, &custom, Pick::Seed(builtin)),
+            "<@10>\nずんだ餅を用意したのだ🍡"
+        );
+        assert_eq!(
+            celebration(Locale::Ja, 10, &custom, Pick::Seed(builtin + 1)),
+            "<@10> さん、今年も一緒に遊ぶのだ！"
+        );
+        assert_eq!(
+            celebration(Locale::Ja, 10, &custom, Pick::Seed(0)),
+            Line::Celebration.text(Locale::Ja, 10, Pick::Seed(0))
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// 現在日時の取得元
+///
+/// 日付に依存する処理へ注入し、テストでは固定の日時に差し替える。
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Tz>;
+}
+
+/// 設定したタイムゾーンのシステム時刻
+pub struct SystemClock {
+    timezone: Tz,
+}
+
+impl SystemClock {
+    pub fn new(timezone: Tz) -> Self {
+        SystemClock { timezone }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Tz> {
+        self.timezone.from_utc_datetime(&Utc::now().naive_utc())
+    }
+}
+
+/// 常に同じ日時を返すテスト用の時計
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Tz>);
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn at(year: i32, month: u32, day: u32, hour: u32, min: u32) -> Self {
+        FixedClock(
+            chrono_tz::Asia::Tokyo
+                .with_ymd_and_hms(year, month, day, hour, min, 0)
+                .unwrap(),
+        )
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Tz> {
+        self.0
+    }
+}
@@ -0,0 +1,66 @@
+// スラッシュコマンドの Discord への登録
+//
+// 開発用ギルドを指定した場合は、そのギルドにだけ登録する (即座に反映される)。
+// グローバル登録は反映に最大1時間かかり、再起動のたびに登録し直す必要もないため、
+// コマンド定義のハッシュを保存しておき、定義が変わった場合のみ登録する。
+
+use crate::data::birthday_store::BirthdayStore;
+use crate::models::common::{Data, Error};
+use crate::models::hash::fnv1a64;
+use poise::serenity_prelude::{GuildId, Http};
+use poise::Command;
+
+/// グローバル登録したコマンド定義のハッシュを保存するスコープ
+const GLOBAL_SCOPE: &str = "global";
+
+pub async fn register_commands(
+    http: &Http,
+    store: &dyn BirthdayStore,
+    commands: &[Command<Data, Error>],
+    dev_guild_id: Option<u64>,
+) -> anyhow::Result<()> {
+    if let Some(guild_id) = dev_guild_id {
+        poise::builtins::register_in_guild(http, commands, GuildId::new(guild_id)).await?;
+        tracing::info!(guild_id, "Registered commands in the dev guild");
+        return Ok(());
+    }
+
+    let hash = commands_hash(commands)?;
+    if store.select_command_hash(GLOBAL_SCOPE).await?.as_deref() == Some(hash.as_str()) {
+        tracing::info!(%hash, "Command definitions are unchanged; skipping global registration");
+        return Ok(());
+    }
+    poise::builtins::register_globally(http, commands).await?;
+    store.upsert_command_hash(GLOBAL_SCOPE, &hash).await?;
+    tracing::info!(%hash, "Registered commands globally");
+    Ok(())
+}
+
+/// Discord に送るコマンド定義の FNV-1a ハッシュ (16進数)
+///
+/// 多言語の名前・説明は HashMap に入っているため、serde_json::Value を経由してキーの順序を揃えてから計算する。
+fn commands_hash(commands: &[Command<Data, Error>]) -> anyhow::Result<String> {
+    let definitions = serde_json::to_value(poise::builtins::create_application_commands(commands))?;
+    let hash = fnv1a64(definitions.to_string().bytes());
+    Ok(format!("{hash:016x}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::commands_hash;
+    use crate::commands::birth::birth;
+    use crate::commands::celebration::celebration;
+    use crate::commands::hello::hello;
+
+    #[test]
+    fn hash_changes_only_when_definitions_change() {
+        let hash = commands_hash(&[hello(), birth(), celebration()]).unwrap();
+
+        // 多言語の説明を持つコマンドを作り直しても同じハッシュになる
+        assert_eq!(
+            hash,
+            commands_hash(&[hello(), birth(), celebration()]).unwrap()
+        );
+        assert_ne!(hash, commands_hash(&[hello(), birth()]).unwrap());
+    }
+}
@@ -1,40 +1,337 @@
-use std::env;
+use crate::data::birthday_store::BirthdayStore;
+use crate::services::metrics::metrics;
+use crate::worker::annual_birthday_notifier::NotifierStatus;
+use async_trait::async_trait;
+use poise::serenity_prelude::{ConnectionStage, ShardManager};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 
-use tokio::io::AsyncWriteExt;
-use tokio::net::TcpListener;
+/// リクエストヘッダーとして読み込む最大サイズ
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// レディネスチェックで DB の応答を待つ最大時間。プールが枯渇していても /readyz が詰まらないようにする
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
 
-pub async fn run_healthcheck_server() -> anyhow::Result<()> {
-    let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
-    let port: u16 = port.parse()?;
-    run_healthcheck_server_on(port).await
+/// Discord Gateway との接続状態の取得元
+#[async_trait]
+pub trait GatewayProbe: Send + Sync {
+    /// すべてのシャードが接続済みかどうか
+    async fn is_connected(&self) -> bool;
 }
 
-pub async fn run_healthcheck_server_on(port: u16) -> anyhow::Result<()> {
+#[async_trait]
+impl GatewayProbe for ShardManager {
+    async fn is_connected(&self) -> bool {
+        let runners = self.runners.lock().await;
+        !runners.is_empty()
+            && runners
+                .values()
+                .all(|runner| runner.stage == ConnectionStage::Connected)
+    }
+}
+
+/// ヘルスチェックで参照するプロセスの状態
+pub struct HealthState {
+    started_at: Instant,
+    store: Arc<dyn BirthdayStore>,
+    // Discord クライアントの生成後に設定する
+    gateway: OnceLock<Arc<dyn GatewayProbe>>,
+    notifier: Arc<NotifierStatus>,
+}
+
+impl HealthState {
+    pub fn new(store: Arc<dyn BirthdayStore>, notifier: Arc<NotifierStatus>) -> Self {
+        HealthState {
+            started_at: Instant::now(),
+            store,
+            gateway: OnceLock::new(),
+            notifier,
+        }
+    }
+
+    pub fn set_gateway(&self, gateway: Arc<dyn GatewayProbe>) {
+        if self.gateway.set(gateway).is_err() {
+            tracing::warn!("Gateway probe is already set");
+        }
+    }
+}
+
+pub async fn run_healthcheck_server(port: u16, state: Arc<HealthState>) -> anyhow::Result<()> {
     let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await?;
 
     loop {
-        let (mut stream, _) = listener.accept().await?;
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = stream.write_all(response_bytes()).await {
-                tracing::warn!("Failed to write healthcheck response: {}", e);
+            if let Err(e) = handle_connection(stream, &state).await {
+                tracing::warn!("Failed to handle healthcheck request: {}", e);
             }
         });
     }
 }
 
-pub(crate) fn response_bytes() -> &'static [u8] {
-    b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK"
+async fn handle_connection(mut stream: TcpStream, state: &HealthState) -> anyhow::Result<()> {
+    let request = tokio::time::timeout(READ_TIMEOUT, read_request_head(&mut stream)).await??;
+    let response = match parse_request_line(&request) {
+        Some((method, path)) => route(method, path, state).await,
+        None => Response::text(400, "Bad Request", "Bad Request"),
+    };
+    stream
+        .write_all(&response.to_bytes(!request.starts_with("HEAD ")))
+        .await?;
+    Ok(())
+}
+
+/// ヘッダーの終わり(空行)まで読み込む。ボディは使わないので読まない
+async fn read_request_head(stream: &mut TcpStream) -> anyhow::Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// リクエスト行からメソッドとパス(クエリ文字列を除く)を取り出す
+fn parse_request_line(request: &str) -> Option<(&str, &str)> {
+    let line = request.lines().next()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    parts.next()?.strip_prefix("HTTP/")?;
+    let path = target.split('?').next().unwrap_or(target);
+    Some((method, path))
+}
+
+async fn route(method: &str, path: &str, state: &HealthState) -> Response {
+    if method != "GET" && method != "HEAD" {
+        return Response::text(405, "Method Not Allowed", "Method Not Allowed");
+    }
+    match path {
+        // "/" は従来のヘルスチェック(Cloud Run の起動確認)との互換のため
+        "/" | "/healthz" => Response::text(200, "OK", "OK"),
+        "/readyz" => readiness(state).await,
+        "/status" => status(state),
+        "/metrics" => Response {
+            status: 200,
+            reason: "OK",
+            content_type: "text/plain; version=0.0.4",
+            body: metrics().render(state.store.pool_stats()),
+        },
+        _ => Response::text(404, "Not Found", "Not Found"),
+    }
+}
+
+/// DB に接続でき、Discord Gateway に接続済みの場合のみ 200 を返す
+async fn readiness(state: &HealthState) -> Response {
+    let mut problems = Vec::new();
+    if let Some(problem) = check_store(state.store.ping(), PING_TIMEOUT).await {
+        problems.push(problem.to_string());
+    }
+    match state.gateway.get() {
+        None => problems.push("gateway: not started".to_string()),
+        Some(gateway) if !gateway.is_connected().await => {
+            problems.push("gateway: disconnected".to_string())
+        }
+        Some(_) => {}
+    }
+
+    if problems.is_empty() {
+        Response::text(200, "OK", "OK")
+    } else {
+        Response::text(503, "Service Unavailable", &problems.join("\n"))
+    }
+}
+
+/// DB への ping を時間制限付きで待ち、問題があればその内容を返す
+async fn check_store(
+    ping: impl Future<Output = anyhow::Result<()>>,
+    limit: Duration,
+) -> Option<&'static str> {
+    match tokio::time::timeout(limit, ping).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => {
+            tracing::warn!("Readiness check failed to ping the store: {}", e);
+            Some("database: unreachable")
+        }
+        Err(_) => {
+            tracing::warn!(
+                "Readiness check timed out pinging the store after {:?}",
+                limit
+            );
+            Some("database: timed out")
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StatusBody {
+    version: &'static str,
+    uptime_secs: u64,
+    notifier: NotifierBody,
+}
+
+#[derive(Serialize)]
+struct NotifierBody {
+    last_run: Option<String>,
+    last_run_succeeded: Option<bool>,
+    next_run: Option<String>,
+}
+
+fn status(state: &HealthState) -> Response {
+    let notifier = state.notifier.snapshot();
+    let body = StatusBody {
+        version: env!("CARGO_PKG_VERSION"),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        notifier: NotifierBody {
+            last_run: notifier.last_run.map(|time| time.to_rfc3339()),
+            last_run_succeeded: notifier.last_run_succeeded,
+            next_run: notifier.next_run.map(|time| time.to_rfc3339()),
+        },
+    };
+    match serde_json::to_string(&body) {
+        Ok(json) => Response {
+            status: 200,
+            reason: "OK",
+            content_type: "application/json",
+            body: json,
+        },
+        Err(e) => {
+            tracing::error!("Failed to serialize status: {}", e);
+            Response::text(500, "Internal Server Error", "Internal Server Error")
+        }
+    }
+}
+
+struct Response {
+    status: u16,
+    reason: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn text(status: u16, reason: &'static str, body: &str) -> Self {
+        Response {
+            status,
+            reason,
+            content_type: "text/plain; charset=utf-8",
+            body: body.to_string(),
+        }
+    }
+
+    /// HEAD リクエストの場合はボディを含めない
+    fn to_bytes(&self, include_body: bool) -> Vec<u8> {
+        let mut bytes = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason,
+            self.content_type,
+            self.body.len()
+        )
+        .into_bytes();
+        if include_body {
+            bytes.extend_from_slice(self.body.as_bytes());
+        }
+        bytes
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::response_bytes;
+    use super::{check_store, parse_request_line, route, GatewayProbe, HealthState};
+    use crate::data::memory_store::MemoryStore;
+    use crate::worker::annual_birthday_notifier::NotifierStatus;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct StubGateway(bool);
+
+    #[async_trait]
+    impl GatewayProbe for StubGateway {
+        async fn is_connected(&self) -> bool {
+            self.0
+        }
+    }
+
+    fn state() -> HealthState {
+        HealthState::new(
+            Arc::new(MemoryStore::new()),
+            Arc::new(NotifierStatus::default()),
+        )
+    }
 
     #[test]
-    fn response_bytes_returns_http_200_ok() {
-        let response = response_bytes();
+    fn parse_request_line_extracts_method_and_path() {
+        assert_eq!(
+            parse_request_line("GET /readyz?verbose=1 HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            Some(("GET", "/readyz"))
+        );
+        assert_eq!(parse_request_line("garbage"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+
+    #[tokio::test]
+    async fn healthz_is_always_ok_and_unknown_paths_are_not_found() {
+        let state = state();
+
+        let response = route("GET", "/healthz", &state).await;
+        assert_eq!(response.status, 200);
+        assert!(response.to_bytes(true).starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert_eq!(route("GET", "/metrics", &state).await.status, 200);
+        assert_eq!(route("GET", "/nope", &state).await.status, 404);
+        assert_eq!(route("POST", "/healthz", &state).await.status, 405);
+    }
+
+    #[tokio::test]
+    async fn readyz_requires_connected_gateway() {
+        let state = state();
+        let response = route("GET", "/readyz", &state).await;
+        assert_eq!(response.status, 503);
+        assert!(response.body.contains("gateway: not started"));
+
+        state.set_gateway(Arc::new(StubGateway(true)));
+        assert_eq!(route("GET", "/readyz", &state).await.status, 200);
+
+        let state = self::state();
+        state.set_gateway(Arc::new(StubGateway(false)));
+        assert!(route("GET", "/readyz", &state)
+            .await
+            .body
+            .contains("gateway: disconnected"));
+    }
+
+    #[tokio::test]
+    async fn store_ping_that_never_answers_times_out() {
+        let limit = Duration::from_millis(20);
+
+        assert_eq!(check_store(async { Ok(()) }, limit).await, None);
+        assert_eq!(
+            check_store(async { Err(anyhow::anyhow!("refused")) }, limit).await,
+            Some("database: unreachable")
+        );
+        assert_eq!(
+            check_store(std::future::pending(), limit).await,
+            Some("database: timed out")
+        );
+    }
+
+    #[tokio::test]
+    async fn status_returns_version_and_notifier_state_as_json() {
+        let response = route("GET", "/status", &state()).await;
 
-        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
-        assert!(response.ends_with(b"\r\n\r\nOK"));
+        assert_eq!(response.status, 200);
+        let json: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["notifier"]["last_run"].is_null());
     }
 }
@@ -0,0 +1,266 @@
+// ログ出力(tracing subscriber)の初期化
+//
+// 出力レベルは RUST_LOG で指定する (例: `RUST_LOG=info,zunda_bot_rs=debug`)。
+// 出力形式は設定 (Config::log_format) で指定する。
+
+use chrono::Utc;
+use poise::serenity_prelude::{CommandData, CommandDataOptionValue, CommandInteraction};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::io;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Span, Subscriber};
+use tracing_subscriber::fmt::format::{JsonFields, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_FILTER: &str = "info";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// ローカル開発向けの読みやすい形式
+    Text,
+    /// Google Cloud Logging で severity を認識できる1行1JSONの形式
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => anyhow::bail!("Invalid LOG_FORMAT (expected text or json): {value}"),
+        }
+    }
+}
+
+/// グローバルな subscriber を設定する。起動時に一度だけ呼ぶ
+///
+/// CLI の出力 (標準出力) と混ざらないよう、ログは標準エラー出力に書き出す。
+pub fn init_logging(format: LogFormat) -> anyhow::Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let registry = tracing_subscriber::registry().with(filter);
+    match format {
+        LogFormat::Text => registry
+            .with(tracing_subscriber::fmt::layer().with_writer(io::stderr))
+            .try_init()?,
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(io::stderr)
+                    .fmt_fields(JsonFields::new())
+                    .event_format(CloudLoggingFormat),
+            )
+            .try_init()?,
+    }
+    Ok(())
+}
+
+/// コマンド実行中のログに、実行したギルド・ユーザーと操作を付与するスパン
+///
+/// 操作はサブコマンドの名前 (サブコマンドでない場合は `none`) にする。
+pub fn command_span(command: &CommandInteraction) -> Span {
+    let qualified_name = qualified_name(&command.data);
+    let action = qualified_name
+        .split_once(' ')
+        .map_or("none", |(_, action)| action);
+    tracing::info_span!(
+        "command",
+        command = %qualified_name,
+        guild_id = command.guild_id.map(|id| id.get()),
+        user_id = command.user.id.get(),
+        action,
+    )
+}
+
+/// サブコマンド (グループを含む) の名前を空白区切りでつなげた、poise と同じ形式のコマンド名
+fn qualified_name(data: &CommandData) -> String {
+    let mut name = data.name.clone();
+    let mut options = &data.options;
+    while let Some(option) = options.first() {
+        match &option.value {
+            CommandDataOptionValue::SubCommand(sub_options)
+            | CommandDataOptionValue::SubCommandGroup(sub_options) => {
+                name.push(' ');
+                name.push_str(&option.name);
+                options = sub_options;
+            }
+            _ => break,
+        }
+    }
+    name
+}
+
+/// Cloud Logging の構造化ログ形式
+///
+/// レベルを `severity` に、メッセージを `message` に出力し、スパンのフィールドはイベントのフィールドと同じ階層に展開する。
+pub struct CloudLoggingFormat;
+
+impl<S, N> FormatEvent<S, N> for CloudLoggingFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut entry = Map::new();
+        entry.insert("severity".to_string(), severity(metadata.level()).into());
+        entry.insert("time".to_string(), Utc::now().to_rfc3339().into());
+        entry.insert("target".to_string(), metadata.target().into());
+        if let (Some(file), Some(line)) = (metadata.file(), metadata.line()) {
+            entry.insert(
+                "logging.googleapis.com/sourceLocation".to_string(),
+                serde_json::json!({ "file": file, "line": line.to_string() }),
+            );
+        }
+
+        // 外側のスパンから順に展開し、内側のスパンとイベントのフィールドで上書きする
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                let extensions = span.extensions();
+                let Some(fields) = extensions.get::<FormattedFields<N>>() else {
+                    continue;
+                };
+                if let Ok(Value::Object(fields)) = serde_json::from_str(&fields.fields) {
+                    entry.extend(fields);
+                }
+            }
+        }
+        event.record(&mut JsonVisitor(&mut entry));
+
+        writeln!(writer, "{}", Value::Object(entry))
+    }
+}
+
+fn severity(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "ERROR",
+        Level::WARN => "WARNING",
+        Level::INFO => "INFO",
+        Level::DEBUG | Level::TRACE => "DEBUG",
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{qualified_name, CloudLoggingFormat, LogFormat};
+    use poise::serenity_prelude::CommandData;
+    use serde_json::Value;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::format::JsonFields;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn json_format_has_severity_and_span_fields() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .fmt_fields(JsonFields::new())
+            .event_format(CloudLoggingFormat)
+            .with_writer(buffer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("command", guild_id = 1_u64, action = "list");
+            let _entered = span.enter();
+            tracing::warn!(member_id = 10, "failed to send: {}", "timeout");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let entry: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(entry["severity"], "WARNING");
+        assert_eq!(entry["message"], "failed to send: timeout");
+        assert_eq!(entry["guild_id"], 1);
+        assert_eq!(entry["action"], "list");
+        assert_eq!(entry["member_id"], 10);
+    }
+
+    #[test]
+    fn log_format_is_parsed_case_insensitively() {
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn qualified_name_joins_subcommands_like_poise() {
+        let data: CommandData = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "name": "birth",
+            "type": 1,
+            "options": [{
+                "name": "show",
+                "type": 1,
+                "options": [{ "name": "member", "type": 6, "value": "10" }]
+            }]
+        }))
+        .unwrap();
+        assert_eq!(qualified_name(&data), "birth show");
+
+        let data: CommandData =
+            serde_json::from_value(serde_json::json!({ "id": "2", "name": "hello", "type": 1 }))
+                .unwrap();
+        assert_eq!(qualified_name(&data), "hello");
+    }
+}
@@ -0,0 +1,293 @@
+// Prometheus のテキスト形式で公開するメトリクス
+// 依存を増やさないよう、必要なカウンターとヒストグラムだけを手書きで実装している
+
+use crate::models::data::PoolStats;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// 所要時間のヒストグラムのバケット(秒)
+const DURATION_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// プロセス全体で共有するメトリクス
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// ギルド同期で反映した変更の件数
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GuildSyncChanges {
+    pub guilds_added: u64,
+    pub guilds_removed: u64,
+    pub members_added: u64,
+    pub members_removed: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    // (コマンド名, アクション, 結果) ごとの所要時間
+    commands: BTreeMap<(String, String, &'static str), Histogram>,
+    // 結果(sent/skipped/failed) ごとの通知件数
+    notifications: BTreeMap<&'static str, u64>,
+    // 結果ごとのギルド同期の所要時間
+    guild_syncs: BTreeMap<&'static str, Histogram>,
+    guild_sync_changes: GuildSyncChanges,
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    // 各バケットに入った件数 (累積ではない)
+    bucket_counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            bucket_counts: vec![0; DURATION_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(index) = DURATION_BUCKETS.iter().position(|&bound| value <= bound) {
+            self.bucket_counts[index] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(&self.bucket_counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+/// 結果を表すラベルの値
+pub fn outcome_label<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() {
+        "success"
+    } else {
+        "error"
+    }
+}
+
+impl Metrics {
+    pub fn record_command(
+        &self,
+        command: &str,
+        action: &str,
+        outcome: &'static str,
+        elapsed: Duration,
+    ) {
+        self.inner
+            .lock()
+            .unwrap()
+            .commands
+            .entry((command.to_string(), action.to_string(), outcome))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// 誕生日通知の結果 (`sent` / `skipped` / `failed`) を記録する
+    pub fn record_notification(&self, outcome: &'static str) {
+        *self
+            .inner
+            .lock()
+            .unwrap()
+            .notifications
+            .entry(outcome)
+            .or_default() += 1;
+    }
+
+    pub fn record_guild_sync(
+        &self,
+        outcome: &'static str,
+        elapsed: Duration,
+        changes: GuildSyncChanges,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .guild_syncs
+            .entry(outcome)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+        let total = &mut inner.guild_sync_changes;
+        total.guilds_added += changes.guilds_added;
+        total.guilds_removed += changes.guilds_removed;
+        total.members_added += changes.members_added;
+        total.members_removed += changes.members_removed;
+    }
+
+    /// Prometheus のテキスト形式 (version 0.0.4) で出力する
+    pub fn render(&self, pool: Option<PoolStats>) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP zunda_command_invocations_total Number of command invocations.\n");
+        out.push_str("# TYPE zunda_command_invocations_total counter\n");
+        for ((command, action, outcome), histogram) in &inner.commands {
+            let _ = writeln!(
+                out,
+                "zunda_command_invocations_total{{command=\"{}\",action=\"{}\",outcome=\"{outcome}\"}} {}",
+                escape(command),
+                escape(action),
+                histogram.count
+            );
+        }
+
+        out.push_str("# HELP zunda_command_duration_seconds Command latency in seconds.\n");
+        out.push_str("# TYPE zunda_command_duration_seconds histogram\n");
+        for ((command, action, outcome), histogram) in &inner.commands {
+            let labels = format!(
+                "command=\"{}\",action=\"{}\",outcome=\"{outcome}\"",
+                escape(command),
+                escape(action)
+            );
+            histogram.render(&mut out, "zunda_command_duration_seconds", &labels);
+        }
+
+        out.push_str("# HELP zunda_notifications_total Birthday notifications by outcome.\n");
+        out.push_str("# TYPE zunda_notifications_total counter\n");
+        for outcome in ["sent", "skipped", "failed"] {
+            let count = inner.notifications.get(outcome).copied().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "zunda_notifications_total{{outcome=\"{outcome}\"}} {count}"
+            );
+        }
+
+        out.push_str("# HELP zunda_guild_sync_duration_seconds Guild sync duration in seconds.\n");
+        out.push_str("# TYPE zunda_guild_sync_duration_seconds histogram\n");
+        for (outcome, histogram) in &inner.guild_syncs {
+            let labels = format!("outcome=\"{outcome}\"");
+            histogram.render(&mut out, "zunda_guild_sync_duration_seconds", &labels);
+        }
+
+        out.push_str("# HELP zunda_guild_sync_changes_total Rows changed by guild sync.\n");
+        out.push_str("# TYPE zunda_guild_sync_changes_total counter\n");
+        let changes = inner.guild_sync_changes;
+        for (kind, count) in [
+            ("guild_added", changes.guilds_added),
+            ("guild_removed", changes.guilds_removed),
+            ("member_added", changes.members_added),
+            ("member_removed", changes.members_removed),
+        ] {
+            let _ = writeln!(
+                out,
+                "zunda_guild_sync_changes_total{{kind=\"{kind}\"}} {count}"
+            );
+        }
+
+        // インメモリ・ファイルストアにはコネクションプールがないため出力しない
+        if let Some(pool) = pool {
+            out.push_str("# HELP zunda_db_pool_connections DB pool connections by state.\n");
+            out.push_str("# TYPE zunda_db_pool_connections gauge\n");
+            let _ = writeln!(
+                out,
+                "zunda_db_pool_connections{{state=\"idle\"}} {}",
+                pool.idle
+            );
+            let _ = writeln!(
+                out,
+                "zunda_db_pool_connections{{state=\"active\"}} {}",
+                pool.size.saturating_sub(pool.idle)
+            );
+            out.push_str("# HELP zunda_db_pool_max_connections Maximum DB pool size.\n");
+            out.push_str("# TYPE zunda_db_pool_max_connections gauge\n");
+            let _ = writeln!(
+                out,
+                "zunda_db_pool_max_connections {}",
+                pool.max_connections
+            );
+        }
+        out
+    }
+}
+
+/// ラベル値に含められない文字をエスケープする
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GuildSyncChanges, Metrics};
+    use crate::models::data::PoolStats;
+    use std::time::Duration;
+
+    #[test]
+    fn command_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::default();
+        metrics.record_command("birth", "list", "success", Duration::from_millis(80));
+        metrics.record_command("birth", "list", "success", Duration::from_millis(700));
+        metrics.record_command("birth", "list", "success", Duration::from_secs(60));
+
+        let text = metrics.render(None);
+
+        assert!(text.contains(
+            "zunda_command_invocations_total{command=\"birth\",action=\"list\",outcome=\"success\"} 3"
+        ));
+        assert!(text.contains(
+            "zunda_command_duration_seconds_bucket{command=\"birth\",action=\"list\",outcome=\"success\",le=\"0.1\"} 1"
+        ));
+        assert!(text.contains(
+            "zunda_command_duration_seconds_bucket{command=\"birth\",action=\"list\",outcome=\"success\",le=\"1\"} 2"
+        ));
+        assert!(text.contains(
+            "zunda_command_duration_seconds_bucket{command=\"birth\",action=\"list\",outcome=\"success\",le=\"+Inf\"} 3"
+        ));
+        assert!(!text.contains("zunda_db_pool_connections"));
+    }
+
+    #[test]
+    fn notifications_sync_changes_and_pool_are_rendered() {
+        let metrics = Metrics::default();
+        metrics.record_notification("sent");
+        metrics.record_notification("sent");
+        metrics.record_notification("failed");
+        metrics.record_guild_sync(
+            "success",
+            Duration::from_millis(300),
+            GuildSyncChanges {
+                members_added: 2,
+                ..GuildSyncChanges::default()
+            },
+        );
+
+        let text = metrics.render(Some(PoolStats {
+            size: 3,
+            idle: 1,
+            max_connections: 5,
+        }));
+
+        assert!(text.contains("zunda_notifications_total{outcome=\"sent\"} 2"));
+        assert!(text.contains("zunda_notifications_total{outcome=\"skipped\"} 0"));
+        assert!(text.contains("zunda_notifications_total{outcome=\"failed\"} 1"));
+        assert!(text.contains("zunda_guild_sync_duration_seconds_count{outcome=\"success\"} 1"));
+        assert!(text.contains("zunda_guild_sync_changes_total{kind=\"member_added\"} 2"));
+        assert!(text.contains("zunda_db_pool_connections{state=\"active\"} 2"));
+        assert!(text.contains("zunda_db_pool_max_connections 5"));
+    }
+}
@@ -1 +1,6 @@
+pub mod clock;
+pub mod command_registration;
 pub mod healthcheck;
+pub mod logging;
+pub mod metrics;
+pub mod shutdown;
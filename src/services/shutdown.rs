@@ -0,0 +1,152 @@
+// 終了シグナル(SIGTERM / Ctrl+C)を受けたときの段階的な停止
+//
+// Cloud Run は SIGTERM の送信から約10秒後にコンテナを強制終了するため、
+// 新しい処理の受付を止め、実行中のコマンドや誕生日通知が終わるのを期限付きで待ってから終了する。
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+
+/// 停止要求と、実行中の処理の数を管理する
+pub struct Shutdown {
+    requested: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// 実行中の処理を表すガード。ドロップすると処理の完了として扱う
+pub struct InFlightGuard {
+    shutdown: Arc<Shutdown>,
+}
+
+impl Shutdown {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Shutdown {
+            requested: watch::Sender::new(false),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::new(),
+        })
+    }
+
+    pub fn request(&self) {
+        self.requested.send_replace(true);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    /// 停止が要求されるまで待つ
+    pub async fn requested(&self) {
+        let mut receiver = self.requested.subscribe();
+        // 送信側は self が保持しているため、エラーにはならない
+        let _ = receiver.wait_for(|requested| *requested).await;
+    }
+
+    /// 処理の開始を登録する。停止が要求された後は新しい処理を受け付けず `None` を返す
+    pub fn track(self: &Arc<Self>) -> Option<InFlightGuard> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        // 停止要求と同時に開始した処理を取りこぼさないよう、登録後に確認する
+        if self.is_requested() {
+            self.finish();
+            return None;
+        }
+        Some(InFlightGuard {
+            shutdown: self.clone(),
+        })
+    }
+
+    /// 実行中の処理がすべて終わるまで、最大 `timeout` だけ待つ。期限内に終わった場合は true
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let wait = async {
+            loop {
+                // 確認と待機の間に通知を取りこぼさないよう、先に待機を登録しておく
+                let notified = self.idle.notified();
+                tokio::pin!(notified);
+                notified.as_mut().enable();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.is_ok()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    fn finish(&self) {
+        if self.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.shutdown.finish();
+    }
+}
+
+/// SIGTERM または Ctrl+C を受けるまで待つ
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => tracing::info!("Received SIGTERM"),
+                    _ = tokio::signal::ctrl_c() => tracing::info!("Received Ctrl+C"),
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        tracing::error!("Failed to listen for Ctrl+C: {}", e);
+        // シグナルを受け取れない場合は、停止処理を始めずに待ち続ける
+        std::future::pending::<()>().await;
+    }
+    tracing::info!("Received Ctrl+C");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Shutdown;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn no_new_work_is_accepted_after_shutdown_is_requested() {
+        let shutdown = Shutdown::new();
+        let guard = shutdown.track();
+        assert!(guard.is_some());
+
+        shutdown.request();
+
+        assert!(shutdown.track().is_none());
+        assert_eq!(shutdown.in_flight(), 1);
+        shutdown.requested().await;
+    }
+
+    #[tokio::test]
+    async fn wait_idle_waits_for_in_flight_work_until_the_deadline() {
+        let shutdown = Shutdown::new();
+        let guard = shutdown.track().unwrap();
+        assert!(!shutdown.wait_idle(Duration::from_millis(10)).await);
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait_idle(Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(guard);
+
+        assert!(waiter.await.unwrap());
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+}
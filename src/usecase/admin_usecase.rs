@@ -0,0 +1,334 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::guild_repository::GuildRepository;
+use crate::data::offline_discord_gateway::OfflineDiscordGateway;
+use crate::models::data::{BirthdayNotification, Guild, GuildMember, NotificationStatus};
+use crate::models::domain::NotificationPreview;
+use crate::res::messages::Locale;
+use crate::services::clock::{Clock, SystemClock};
+use crate::usecase::birth_notify_usecase::BirthNotifyUsecase;
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 管理用 CLI から、Discord に接続せずにデータを確認・修正するためのユースケース
+pub struct AdminUsecase {
+    guild_repo: GuildRepository,
+    birth_notify_usecase: BirthNotifyUsecase,
+    clock: Arc<dyn Clock>,
+}
+
+impl AdminUsecase {
+    pub fn new(store: Arc<dyn BirthdayStore>, timezone: Tz) -> anyhow::Result<Self> {
+        let discord = Arc::new(OfflineDiscordGateway);
+        let guild_repo = GuildRepository::new(store.clone(), discord.clone())?;
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock::new(timezone));
+        let birth_notify_usecase = BirthNotifyUsecase::new(store, discord, clock.clone())?;
+        Ok(AdminUsecase {
+            guild_repo,
+            birth_notify_usecase,
+            clock,
+        })
+    }
+
+    pub async fn get_guilds(&self) -> anyhow::Result<Vec<Guild>> {
+        Ok(self.guild_repo.get_guilds().await?)
+    }
+
+    pub async fn set_guild_locale(
+        &self,
+        guild_id: i64,
+        locale: Option<Locale>,
+    ) -> anyhow::Result<()> {
+        Ok(self.guild_repo.set_guild_locale(guild_id, locale).await?)
+    }
+
+    pub async fn get_members(&self, guild_id: i64) -> anyhow::Result<Vec<GuildMember>> {
+        Ok(self.guild_repo.get_members_by_guild_id(guild_id).await?)
+    }
+
+    pub async fn get_member(&self, guild_id: i64, member_id: i64) -> anyhow::Result<GuildMember> {
+        self.guild_repo
+            .get_member(guild_id, member_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Member {member_id} was not found in guild {guild_id}"))
+    }
+
+    /// メンバーの誕生日を上書きする (`None` の場合は誕生日を解除する)
+    ///
+    /// メンバーが未登録の場合は追加する。最終通知日はそのまま残す。
+    pub async fn set_member_birth(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        birth: Option<NaiveDate>,
+    ) -> anyhow::Result<GuildMember> {
+        self.guild_repo
+            .set_member_birth(guild_id, None, member_id, birth)
+            .await?;
+        self.get_member(guild_id, member_id).await
+    }
+
+    /// `year` 年の通知の記録と、その年以降の最終通知日を消去し、消去した人数を返す
+    ///
+    /// 通知の記録は最終通知日に関わらず消去するため、失敗・見送りのまま最終通知日が空のメンバーも対象になる。
+    /// 消去したメンバーは、同じ年でも次の誕生日チェックで再び通知される。
+    /// `member_id` を省略した場合はギルドの全メンバー、`year` を省略した場合は今年が対象になる。
+    pub async fn clear_last_notified(
+        &self,
+        guild_id: i64,
+        member_id: Option<i64>,
+        year: Option<i32>,
+    ) -> anyhow::Result<usize> {
+        let year = year.unwrap_or_else(|| self.clock.now().year());
+        let members = match member_id {
+            Some(member_id) => vec![self.get_member(guild_id, member_id).await?],
+            None => self.get_members(guild_id).await?,
+        };
+        let notified_member_ids = self
+            .guild_repo
+            .get_notifications_by_guild_id(guild_id)
+            .await?
+            .into_iter()
+            .filter(|notification| notification.year == year)
+            .map(|notification| notification.member_id)
+            .collect::<HashSet<_>>();
+        let mut uow = self.guild_repo.begin().await?;
+        let mut cleared_count = 0;
+        for member in members {
+            let has_notification = notified_member_ids.contains(&member.member_id);
+            let has_last_notified = member
+                .last_notified
+                .is_some_and(|last_notified| last_notified.year() >= year);
+            if !has_notification && !has_last_notified {
+                continue;
+            }
+            uow.delete_notification(guild_id, member.member_id, year)
+                .await?;
+            if has_last_notified {
+                uow.clear_last_notified(guild_id, member.member_id).await?;
+            }
+            cleared_count += 1;
+        }
+        uow.commit().await?;
+        Ok(cleared_count)
+    }
+
+    /// 送信に失敗した誕生日通知と、失敗後に再試行を待っている誕生日通知を返す
+    pub async fn get_failed_notifications(
+        &self,
+        guild_id: Option<i64>,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let notifications = match guild_id {
+            Some(guild_id) => {
+                self.guild_repo
+                    .get_notifications_by_guild_id(guild_id)
+                    .await?
+            }
+            None => self.guild_repo.get_all_notifications().await?,
+        };
+        Ok(notifications
+            .into_iter()
+            .filter(|notification| match notification.status {
+                NotificationStatus::Failed => true,
+                NotificationStatus::Pending => notification.attempts > 0,
+                NotificationStatus::Sent | NotificationStatus::Skipped => false,
+            })
+            .collect())
+    }
+
+    /// 送信に失敗した・見送った誕生日通知を送信待ちに戻し、戻せたかどうかを返す
+    ///
+    /// 戻した通知は、稼働中のボットが次に送信待ちの通知を再開する際に送信される。
+    pub async fn retry_notification(
+        &self,
+        guild_id: i64,
+        member_id: i64,
+        year: i32,
+    ) -> anyhow::Result<bool> {
+        Ok(self
+            .guild_repo
+            .rearm_notification(guild_id, member_id, year)
+            .await?)
+    }
+
+    /// 指定日に送信される予定の誕生日通知を返す (ドライラン)
+    pub async fn preview_notifications(
+        &self,
+        today: NaiveDate,
+    ) -> anyhow::Result<Vec<NotificationPreview>> {
+        self.birth_notify_usecase.preview_as_of(today).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdminUsecase;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::memory_store::MemoryStore;
+    use crate::models::data::{BirthdayNotification, NotificationStatus};
+    use chrono::NaiveDate;
+    use std::sync::Arc;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    async fn store_with_member() -> Arc<MemoryStore> {
+        let store = Arc::new(MemoryStore::new());
+        store.insert_guild(1, Some("guild")).await.unwrap();
+        store
+            .insert_guild_member(1, 10, Some(date(2000, 2, 1)))
+            .await
+            .unwrap();
+        let mut tx = store.begin().await.unwrap();
+        tx.update_guild_member_last_notified(1, 10, date(2025, 2, 1))
+            .await
+            .unwrap();
+        let mut sent = BirthdayNotification::new(1, 10, 2025, date(2025, 2, 1));
+        sent.status = NotificationStatus::Sent;
+        tx.upsert_notification(&sent).await.unwrap();
+        tx.commit().await.unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn set_member_birth_overwrites_and_keeps_last_notified() {
+        let store = store_with_member().await;
+        let admin = AdminUsecase::new(store.clone(), chrono_tz::Asia::Tokyo).unwrap();
+
+        admin
+            .set_member_birth(1, 10, Some(date(2000, 3, 1)))
+            .await
+            .unwrap();
+        admin
+            .set_member_birth(1, 20, Some(date(2000, 4, 1)))
+            .await
+            .unwrap();
+
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, Some(date(2000, 3, 1)));
+        assert_eq!(member.last_notified, Some(date(2025, 2, 1)));
+        assert!(store.select_member_by_id(1, 20).await.unwrap().is_some());
+        // 存在しないギルドのメンバーは追加できない
+        assert!(admin.set_member_birth(2, 10, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_touch_discord_or_last_notified() {
+        let store = store_with_member().await;
+        let admin = AdminUsecase::new(store.clone(), chrono_tz::Asia::Tokyo).unwrap();
+
+        assert!(admin
+            .preview_notifications(date(2025, 2, 1))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            admin
+                .clear_last_notified(1, None, Some(2025))
+                .await
+                .unwrap(),
+            1
+        );
+
+        let previews = admin.preview_notifications(date(2025, 2, 1)).await.unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].member_id, 10);
+        assert!(previews[0].announcement.contains("<@10>"));
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.last_notified, None);
+        // 通知の記録も消え、同じ年にもう一度通知できる
+        assert!(store.select_notifications().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn clear_removes_the_years_notification_even_without_last_notified() {
+        let store = store_with_member().await;
+        store.insert_guild_member(1, 20, None).await.unwrap();
+        let mut failed = BirthdayNotification::new(1, 20, 2025, date(2025, 3, 1));
+        failed.status = NotificationStatus::Failed;
+        let mut tx = store.begin().await.unwrap();
+        tx.upsert_notification(&failed).await.unwrap();
+        tx.commit().await.unwrap();
+        let admin = AdminUsecase::new(store.clone(), chrono_tz::Asia::Tokyo).unwrap();
+
+        // 別の年を指定した場合は何も消さない
+        assert_eq!(
+            admin
+                .clear_last_notified(1, Some(20), Some(2024))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            admin
+                .clear_last_notified(1, Some(20), Some(2025))
+                .await
+                .unwrap(),
+            1
+        );
+
+        let remaining = store.select_notifications().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].member_id, 10);
+        // 他のメンバーの最終通知日は変更しない
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.last_notified, Some(date(2025, 2, 1)));
+    }
+
+    #[tokio::test]
+    async fn failed_notifications_include_pending_retries_only() {
+        let store = store_with_member().await;
+        let mut tx = store.begin().await.unwrap();
+        let mut failed = BirthdayNotification::new(1, 20, 2025, date(2025, 3, 1));
+        failed.status = NotificationStatus::Failed;
+        failed.attempts = 3;
+        let mut retrying = BirthdayNotification::new(2, 30, 2025, date(2025, 4, 1));
+        retrying.attempts = 1;
+        let untouched = BirthdayNotification::new(2, 40, 2025, date(2025, 5, 1));
+        for notification in [failed, retrying, untouched] {
+            tx.upsert_notification(&notification).await.unwrap();
+        }
+        tx.commit().await.unwrap();
+        let admin = AdminUsecase::new(store, chrono_tz::Asia::Tokyo).unwrap();
+
+        let mut all = admin.get_failed_notifications(None).await.unwrap();
+        all.sort_by_key(|notification| notification.member_id);
+        assert_eq!(
+            all.iter().map(|n| n.member_id).collect::<Vec<_>>(),
+            vec![20, 30]
+        );
+        let in_guild = admin.get_failed_notifications(Some(1)).await.unwrap();
+        assert_eq!(in_guild.len(), 1);
+        assert_eq!(in_guild[0].member_id, 20);
+    }
+
+    #[tokio::test]
+    async fn retry_rearms_failed_notifications_only() {
+        let store = store_with_member().await;
+        let mut tx = store.begin().await.unwrap();
+        let mut failed = BirthdayNotification::new(1, 20, 2025, date(2025, 3, 1));
+        failed.status = NotificationStatus::Failed;
+        failed.attempts = 3;
+        failed.last_error = Some("Discord is down".to_string());
+        tx.upsert_notification(&failed).await.unwrap();
+        tx.commit().await.unwrap();
+        let admin = AdminUsecase::new(store.clone(), chrono_tz::Asia::Tokyo).unwrap();
+
+        assert!(admin.retry_notification(1, 20, 2025).await.unwrap());
+        // 送信待ちに戻した通知・送信済みの通知・存在しない通知は変更しない
+        assert!(!admin.retry_notification(1, 20, 2025).await.unwrap());
+        assert!(!admin.retry_notification(1, 10, 2025).await.unwrap());
+        assert!(!admin.retry_notification(1, 20, 2024).await.unwrap());
+
+        let claimed = store
+            .claim_notifications(Some(1), std::time::Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].member_id, 20);
+        assert_eq!(claimed[0].attempts, 0);
+        assert_eq!(claimed[0].last_error, None);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
+use crate::data::guild_repository::GuildRepository;
+use crate::models::common::{Context, Error};
+use crate::models::data::{BirthdayNotification, NotificationStatus};
+use crate::res::colors::{EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
+use crate::res::messages::{Locale, Message};
+use poise::futures_util::future::join_all;
+use poise::CreateReply;
+use serenity::all::{ChannelId, CreateEmbed, GuildId, MessageId, UserId};
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+/// 履歴として表示する件数の上限
+const HISTORY_LIMIT: usize = 10;
+
+pub struct BirthHistoryUsecase {
+    guild_repo: GuildRepository,
+    discord: Arc<dyn DiscordGateway>,
+}
+
+impl BirthHistoryUsecase {
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord.clone())?;
+        Ok(BirthHistoryUsecase {
+            guild_repo,
+            discord,
+        })
+    }
+
+    pub async fn invoke(
+        &self,
+        poise_ctx: Context<'_>,
+        member_id: Option<UserId>,
+    ) -> anyhow::Result<(), Error> {
+        // コマンドが実行されたギルドのギルドIDを取得
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let history = self
+            .get_history(i64::from(guild_id), member_id.map(i64::from))
+            .await?;
+
+        let reply = if history.is_empty() {
+            // 「まだ誕生日を祝ったことがないこと」をメッセージで通知
+            CreateReply::default()
+                .embed(
+                    CreateEmbed::new()
+                        .title(Message::HistoryEmpty.text(locale))
+                        .color(EMBED_COLOR_WARNING), // 警告系の色
+                )
+                .ephemeral(true)
+        } else {
+            // お祝いした日・メンバー・リアクション数と、お知らせへのリンクの一覧をメッセージで通知
+            let lines = history
+                .iter()
+                .filter_map(|notification| history_line(locale, guild_id, notification))
+                .collect::<Vec<_>>();
+            CreateReply::default()
+                .embed(
+                    CreateEmbed::new()
+                        .title(Message::HistoryTitle.text(locale))
+                        .description(lines.join(""))
+                        .color(EMBED_COLOR_SUCCESS), // 正常系の色
+                )
+                .ephemeral(true)
+        };
+        poise_ctx.send(reply).await?;
+
+        Ok(())
+    }
+
+    /// 送信済みの誕生日通知を新しい順に返す (`member_id` を指定した場合はそのメンバーのみ)
+    ///
+    /// リアクションの数は Discord から取得し直して記録する。
+    /// お知らせが削除されているなどで取得できない場合は、最後に記録した数を使う。
+    pub async fn get_history(
+        &self,
+        guild_id: i64,
+        member_id: Option<i64>,
+    ) -> anyhow::Result<Vec<BirthdayNotification>> {
+        let mut history = self
+            .guild_repo
+            .get_notifications_by_guild_id(guild_id)
+            .await?
+            .into_iter()
+            .filter(|notification| {
+                notification.status == NotificationStatus::Sent
+                    && member_id.is_none_or(|id| notification.member_id == id)
+            })
+            .collect::<Vec<_>>();
+        history.sort_by_key(|notification| {
+            Reverse((notification.notify_date, notification.member_id))
+        });
+        history.truncate(HISTORY_LIMIT);
+
+        join_all(
+            history
+                .iter_mut()
+                .map(|notification| self.refresh_reaction_count(notification)),
+        )
+        .await;
+        Ok(history)
+    }
+
+    async fn refresh_reaction_count(&self, notification: &mut BirthdayNotification) {
+        let (Some(channel_id), Some(message_id)) = (
+            notification.channel_id,
+            notification.announcement_message_id,
+        ) else {
+            return;
+        };
+        let fetched = async {
+            let count = self
+                .discord
+                .get_reaction_count(
+                    ChannelId::new(u64::try_from(channel_id)?),
+                    MessageId::new(u64::try_from(message_id)?),
+                )
+                .await?;
+            anyhow::Ok(i32::try_from(count).unwrap_or(i32::MAX))
+        };
+        match fetched.await {
+            Ok(count) if count != notification.reaction_count => {
+                notification.reaction_count = count;
+                if let Err(e) = self.guild_repo.update_reaction_count(notification).await {
+                    tracing::warn!("Failed to record reaction count: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(
+                guild_id = notification.guild_id,
+                member_id = notification.member_id,
+                year = notification.year,
+                "Failed to fetch reactions of birthday announcement: {}",
+                e
+            ),
+        }
+    }
+}
+
+/// 履歴の1行 (お知らせへのジャンプリンクつき)
+fn history_line(
+    locale: Locale,
+    guild_id: GuildId,
+    notification: &BirthdayNotification,
+) -> Option<String> {
+    let channel_id = ChannelId::new(u64::try_from(notification.channel_id?).ok()?);
+    let message_id = MessageId::new(u64::try_from(notification.announcement_message_id?).ok()?);
+    Some(format!(
+        "・{}: <@{}> 🎉×{} [{}]({})\n",
+        locale.format_date(notification.notify_date),
+        notification.member_id,
+        notification.reaction_count,
+        Message::HistoryJumpLink.text(locale),
+        message_id.link(channel_id, Some(guild_id)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{history_line, BirthHistoryUsecase};
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::fake_discord_gateway::FakeDiscordGateway;
+    use crate::data::memory_store::MemoryStore;
+    use crate::models::data::{BirthdayNotification, NotificationStatus};
+    use crate::res::messages::Locale;
+    use chrono::NaiveDate;
+    use serenity::all::GuildId;
+    use std::sync::Arc;
+
+    fn sent(member_id: i64, year: i32, message_id: i64) -> BirthdayNotification {
+        let mut notification = BirthdayNotification::new(
+            1,
+            member_id,
+            year,
+            NaiveDate::from_ymd_opt(year, 2, 1).unwrap(),
+        );
+        notification.status = NotificationStatus::Sent;
+        notification.channel_id = Some(100);
+        notification.announcement_message_id = Some(message_id);
+        notification.reaction_count = 2;
+        notification
+    }
+
+    #[tokio::test]
+    async fn history_lists_sent_notifications_newest_first_with_fresh_reactions() {
+        let store = Arc::new(MemoryStore::new());
+        let mut skipped =
+            BirthdayNotification::new(1, 11, 2025, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        skipped.status = NotificationStatus::Skipped;
+        let mut tx = store.begin().await.unwrap();
+        for notification in [sent(10, 2024, 7), sent(10, 2025, 8), skipped] {
+            tx.upsert_notification(&notification).await.unwrap();
+        }
+        tx.commit().await.unwrap();
+        let discord = Arc::new(FakeDiscordGateway::new());
+        // 2024年のお知らせは削除されていて取得できない
+        discord.set_reaction_count(8, 5);
+        let usecase = BirthHistoryUsecase::new(store.clone(), discord).unwrap();
+
+        let history = usecase.get_history(1, None).await.unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].year, history[0].reaction_count), (2025, 5));
+        assert_eq!((history[1].year, history[1].reaction_count), (2024, 2));
+        let stored = store.select_notifications_by_guild_id(1).await.unwrap();
+        assert!(stored
+            .iter()
+            .any(|notification| notification.year == 2025 && notification.reaction_count == 5));
+        assert!(usecase.get_history(1, Some(11)).await.unwrap().is_empty());
+        let line = history_line(Locale::En, GuildId::new(1), &history[0]).unwrap();
+        assert!(line.starts_with("・February 1, 2025: <@10>"));
+        assert!(line.contains("https://discord.com/channels/1/100/8"));
+    }
+}
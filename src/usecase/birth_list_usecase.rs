@@ -1,24 +1,29 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
 use crate::data::guild_repository::GuildRepository;
 use crate::models::common::{Context, Error};
 use crate::res::colors::{EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
+use crate::res::messages::Message;
 use chrono::Datelike;
 use poise::futures_util::future::join_all;
 use poise::CreateReply;
-use serenity::all::{CreateEmbed, Http};
-use sqlx::PgPool;
+use serenity::all::{CreateEmbed, UserId};
 use std::sync::Arc;
 
 pub struct BirthListUsecase {
     guild_repo: GuildRepository,
-    http: Arc<Http>,
+    discord: Arc<dyn DiscordGateway>,
 }
 
 impl BirthListUsecase {
-    pub fn new(pool: Arc<PgPool>, http: Arc<Http>) -> anyhow::Result<Self> {
-        let guild_repo = GuildRepository::new(pool, http.clone())?;
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord.clone())?;
         Ok(BirthListUsecase {
             guild_repo,
-            http: http.clone(),
+            discord,
         })
     }
 
@@ -28,6 +33,7 @@ impl BirthListUsecase {
             .guild_repo
             .fetch_guild_id_from_command(poise_ctx)
             .await?;
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
 
         // ギルドIDに一致するメンバー情報リストをguild_memberテーブルから取得
         let mut members = self
@@ -44,7 +50,7 @@ impl BirthListUsecase {
             CreateReply::default()
                 .embed(
                     CreateEmbed::new()
-                        .title("⚠️ 誕生日が登録されていないのだ")
+                        .title(Message::BirthNotRegistered.text(locale))
                         .color(EMBED_COLOR_WARNING), // 警告系の色
                 )
                 .ephemeral(true)
@@ -54,13 +60,17 @@ impl BirthListUsecase {
 
             // メンバーの誕生日とディスプレイ名のリストをメッセージで通知
             let birth_features = members.into_iter().map(move |member| async move {
-                let latest_member_id = u64::try_from(member.member_id).ok()?;
-                let latest_member = guild_id.member(&self.http, latest_member_id).await.ok()?;
+                let latest_member_id = UserId::new(u64::try_from(member.member_id).ok()?);
+                let latest_member = self
+                    .discord
+                    .get_member_profile(guild_id, latest_member_id)
+                    .await
+                    .ok()?;
                 member.birth.map(|birth| {
                     format!(
                         "・{}: {}\n",
-                        birth.format("%m/%d"),
-                        latest_member.display_name(),
+                        locale.format_month_day(birth),
+                        latest_member.display_name,
                     )
                 })
             });
@@ -72,7 +82,7 @@ impl BirthListUsecase {
             CreateReply::default()
                 .embed(
                     CreateEmbed::new()
-                        .title("🎉 誕生日リスト")
+                        .title(Message::BirthListTitle.text(locale))
                         .description(birth_list.join(""))
                         .color(EMBED_COLOR_SUCCESS), // 正常系の色
                 )
@@ -1,100 +1,857 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
 use crate::data::guild_repository::GuildRepository;
 use crate::models::common::Error;
-use crate::models::data::GuildMember;
-use chrono::{Datelike, Local, TimeZone};
-use chrono_tz::Asia::Tokyo;
-use serenity::all::{ChannelType, CreateEmbed, CreateMessage, GuildId, Http, ReactionType};
-use sqlx::PgPool;
+use crate::models::data::{BirthdayNotification, GuildMember, NotificationStatus};
+use crate::models::domain::{NotificationPreview, OutgoingEmbed, OutgoingMessage};
+use crate::models::hash::fnv1a64;
+use crate::res::messages::Locale;
+use crate::res::persona::{self, Line, Pick};
+use crate::services::clock::Clock;
+use crate::services::metrics::metrics;
+use chrono::{Datelike, NaiveDate, Utc};
+use serenity::all::{ChannelId, GuildId, MessageId, UserId};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// 送信を確保した通知を、他のインスタンスが引き継げるようになるまでの時間
+const CLAIM_TTL: Duration = Duration::from_secs(5 * 60);
+/// 送信に失敗した通知を再試行する上限
+const MAX_ATTEMPTS: i32 = 3;
 
 pub struct BirthNotifyUsecase {
     guild_repo: GuildRepository,
-    http: Arc<Http>,
+    discord: Arc<dyn DiscordGateway>,
+    clock: Arc<dyn Clock>,
 }
 
 impl BirthNotifyUsecase {
-    pub fn new(pool: Arc<PgPool>, http: Arc<Http>) -> anyhow::Result<Self> {
-        let guild_repo = GuildRepository::new(pool, http.clone())?;
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+        clock: Arc<dyn Clock>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord.clone())?;
         Ok(BirthNotifyUsecase {
             guild_repo,
-            http: http.clone(),
+            discord,
+            clock,
         })
     }
 
     pub async fn invoke(&self) -> anyhow::Result<(), Error> {
-        let http = &self.http;
-        let members = self.guild_repo.get_all_members().await?;
-        for GuildMember {
-            guild_id,
-            member_id,
-            birth,
-            last_notified,
-        } in members
-        {
-            // メンバーの誕生日を取得
-            let birth = match birth {
-                None => continue, // メンバーの誕生日が存在しない
-                Some(birth) => birth,
-            };
+        // 注入された時計から現在日時を取得
+        let today = self.clock.now().date_naive();
+        self.notify_birthdays(today, None).await?;
+        Ok(())
+    }
 
-            // タイムゾーン"Asia/Tokyo"の現在日時を取得
-            let now = Tokyo.from_utc_datetime(&Local::now().naive_utc());
-            let is_not_sending_enable = last_notified
-                .is_some_and(|last_notified| last_notified.year() >= now.year())
-                || birth.month() != now.date_naive().month()
-                || birth.day() != now.day();
-            if is_not_sending_enable {
+    /// 指定した日を「今日」とみなして、指定したギルドのメンバーにのみ誕生日を通知する
+    ///
+    /// 開発用コマンドから、任意の日付の通知をステージング用ギルドで確認するために使う。
+    /// 通知したメンバーの最終通知日は指定した日で記録される。
+    pub async fn invoke_as_of(&self, today: NaiveDate, guild_id: i64) -> anyhow::Result<usize> {
+        self.notify_birthdays(today, Some(guild_id)).await
+    }
+
+    /// 指定した日に通知される予定のメンバーと送信内容を返す
+    ///
+    /// Discord への送信も最終通知日の記録も行わない。
+    pub async fn preview_as_of(
+        &self,
+        today: NaiveDate,
+    ) -> anyhow::Result<Vec<NotificationPreview>> {
+        let mut previews = Vec::new();
+        for member in self.find_birthday_members(today).await? {
+            let Some(birth) = member.birth else {
                 continue;
             };
-
-            // メンバーのギルドIDからチャンネル情報を取得
-            let guild_id = GuildId::new(u64::try_from(guild_id)?);
-            let channels = guild_id.channels(http).await?;
-            let general_channel = channels.iter().find(|(_, ch)| {
-                ch.kind == ChannelType::Text && (ch.name == "一般" || ch.name == "general")
+            let locale = self.guild_repo.get_guild_locale(member.guild_id).await?;
+            // 実際の送信と同じ言い回しを選ぶ
+            let notification =
+                BirthdayNotification::new(member.guild_id, member.member_id, today.year(), today);
+            previews.push(NotificationPreview {
+                guild_id: member.guild_id,
+                member_id: member.member_id,
+                birth,
+                announcement: announcement_content(&notification, locale),
+                celebration: self.celebration_content(&notification, locale).await?,
             });
-            let channel_id = match general_channel {
-                None => continue, // "一般"または"general"のチャンネル名が存在しない
-                Some((_, channel)) => channel.id,
-            };
+        }
+        Ok(previews)
+    }
 
-            // 誕生日のメッセージをメンバーのメンションをつけて、"一般"または"general"のチャンネルに送信
-            let mention = format!("<@{member_id}>");
-            let main_content = format!("@here\n今日は「🎂 {mention} さんのお誕生日 🎂」！\n\n今年も自分らしい１年を過ごせるとよきなのだ！！！");
-            let member = guild_id.member(http, u64::try_from(member_id)?).await?;
-            let msg = channel_id
-                .send_message(
-                    http,
-                    CreateMessage::new().content(main_content).embed(
-                        CreateEmbed::new()
-                            .title(member.display_name())
-                            .thumbnail(member.user.avatar_url().unwrap_or_default())
-                            .description(birth.format("%m/%d").to_string()),
-                    ),
-                )
+    /// 今日が誕生日のメンバーを送信待ちとして登録してから通知し、通知できた人数を返す
+    async fn notify_birthdays(
+        &self,
+        today: NaiveDate,
+        guild_id: Option<i64>,
+    ) -> anyhow::Result<usize> {
+        let members = self
+            .find_birthday_members(today)
+            .await?
+            .into_iter()
+            .filter(|member| guild_id.is_none_or(|id| member.guild_id == id));
+        for member in members {
+            // 送信前に1年1件の通知を登録しておき、途中で落ちても同じ年に2回通知しないようにする
+            self.guild_repo
+                .enqueue_notification(member.guild_id, member.member_id, today)
                 .await?;
+        }
+        self.dispatch_pending(today, guild_id).await
+    }
 
-            // 誕生日のメッセージにリアクションをつける
-            msg.react(http, ReactionType::Unicode("🎉".to_string()))
-                .await?;
+    /// 送信待ち・送信途中の通知を再開し、通知できた人数を返す
+    ///
+    /// 送信中に落ちたインスタンスの通知は、確保の期限が切れた後に引き継ぐ。
+    pub async fn resume_pending(&self) -> anyhow::Result<usize> {
+        let today = self.clock.now().date_naive();
+        self.dispatch_pending(today, None).await
+    }
 
-            // お祝いメッセージの一例を誕生日のメッセージのリプライとして送信
-            let sub_content =
-                format!("{mention} さん\nお誕生日おめでとうなのだ🎉\nいつもありがとなのだ！");
-            channel_id
-                .send_message(
-                    http,
-                    CreateMessage::new()
-                        .content(sub_content)
-                        .reference_message(&msg),
-                )
-                .await?;
+    async fn dispatch_pending(
+        &self,
+        today: NaiveDate,
+        guild_id: Option<i64>,
+    ) -> anyhow::Result<usize> {
+        let notifications = self
+            .guild_repo
+            .claim_notifications(guild_id, CLAIM_TTL)
+            .await?;
+        let mut notified_count = 0;
+        for mut notification in notifications {
+            // 1人の通知に失敗しても、他のメンバーへの通知は続ける
+            match self.dispatch(&mut notification, today).await {
+                Ok(NotificationStatus::Sent) => {
+                    notified_count += 1;
+                    metrics().record_notification("sent");
+                }
+                Ok(_) => metrics().record_notification("skipped"),
+                Err(e) => {
+                    metrics().record_notification("failed");
+                    tracing::error!(
+                        guild_id = notification.guild_id,
+                        member_id = notification.member_id,
+                        attempts = notification.attempts + 1,
+                        "Failed to send birthday notification: {}",
+                        e
+                    );
+                    self.record_failure(&mut notification, &e).await;
+                }
+            }
+        }
+        Ok(notified_count)
+    }
 
-            // guild_memberテーブルに誕生日を通知したメンバーの最終通知日時を記録
-            self.guild_repo
-                .update_last_notified(i64::from(guild_id), member_id, now.date_naive())
+    /// 通知を送信し、終了時の状態を返す
+    ///
+    /// お知らせ・リアクション・リプライのうち、記録済みの操作は飛ばして続きから送信する。
+    async fn dispatch(
+        &self,
+        notification: &mut BirthdayNotification,
+        today: NaiveDate,
+    ) -> anyhow::Result<NotificationStatus> {
+        // お知らせを送る前に日付が変わった場合は、翌日以降に「今日は誕生日」と送らないよう見送る
+        if notification.announcement_message_id.is_none() && notification.notify_date != today {
+            return self
+                .finish(notification, "the birthday passed before it was announced")
+                .await;
+        }
+        let guild_id = GuildId::new(u64::try_from(notification.guild_id)?);
+        // お知らせとリプライはギルドに設定した言語で送る
+        let locale = self
+            .guild_repo
+            .get_guild_locale(notification.guild_id)
+            .await?;
+
+        let (channel_id, announcement_id) = match (
+            notification.channel_id,
+            notification.announcement_message_id,
+        ) {
+            (Some(channel_id), Some(message_id)) => (
+                ChannelId::new(u64::try_from(channel_id)?),
+                MessageId::new(u64::try_from(message_id)?),
+            ),
+            _ => {
+                // メンバーの誕生日を取得 (登録後に解除された場合は送らない)
+                let member = self
+                    .guild_repo
+                    .get_member(notification.guild_id, notification.member_id)
+                    .await?;
+                let Some(birth) = member.and_then(|member| member.birth) else {
+                    return self.finish(notification, "the birthday was reset").await;
+                };
+
+                // メンバーのギルドIDからチャンネル情報を取得
+                let channels = self.discord.get_guild_channels(guild_id).await?;
+                let general_channel = channels
+                    .iter()
+                    .find(|ch| ch.is_text && (ch.name == "一般" || ch.name == "general"));
+                let Some(channel) = general_channel else {
+                    return self.finish(notification, "no general channel").await;
+                };
+
+                // 誕生日のメッセージをメンバーのメンションをつけて、"一般"または"general"のチャンネルに送信
+                let profile = self
+                    .discord
+                    .get_member_profile(
+                        guild_id,
+                        UserId::new(u64::try_from(notification.member_id)?),
+                    )
+                    .await?;
+                let message_id = self
+                    .discord
+                    .send_message(
+                        channel.id,
+                        OutgoingMessage {
+                            content: announcement_content(notification, locale),
+                            embed: Some(OutgoingEmbed {
+                                title: profile.display_name,
+                                thumbnail: profile.avatar_url,
+                                description: locale.format_month_day(birth),
+                            }),
+                            reply_to: None,
+                            nonce: Some(message_nonce(notification, "announcement")),
+                        },
+                    )
+                    .await?;
+                notification.channel_id = Some(i64::from(channel.id));
+                notification.announcement_message_id = Some(i64::from(message_id));
+                self.guild_repo.save_notification(notification).await?;
+                (channel.id, message_id)
+            }
+        };
+
+        // 誕生日のメッセージにリアクションをつける
+        if !notification.reacted {
+            self.discord
+                .react(channel_id, announcement_id, "🎉")
                 .await?;
+            notification.reacted = true;
+            self.guild_repo.save_notification(notification).await?;
         }
-        Ok(())
+
+        // お祝いメッセージの一例を誕生日のメッセージのリプライとして送信
+        let reply_id = self
+            .discord
+            .send_message(
+                channel_id,
+                OutgoingMessage {
+                    content: self.celebration_content(notification, locale).await?,
+                    embed: None,
+                    reply_to: Some(announcement_id),
+                    nonce: Some(message_nonce(notification, "reply")),
+                },
+            )
+            .await?;
+        notification.reply_message_id = Some(i64::from(reply_id));
+
+        // 通知の完了と、guild_memberテーブルの最終通知日をまとめて記録
+        notification.status = NotificationStatus::Sent;
+        notification.completed_at = Some(self.clock.now().with_timezone(&Utc));
+        self.guild_repo.complete_notification(notification).await?;
+        Ok(NotificationStatus::Sent)
+    }
+
+    /// 通知を見送ったことを記録する
+    async fn finish(
+        &self,
+        notification: &mut BirthdayNotification,
+        reason: &str,
+    ) -> anyhow::Result<NotificationStatus> {
+        notification.status = NotificationStatus::Skipped;
+        notification.last_error = Some(reason.to_string());
+        notification.completed_at = Some(self.clock.now().with_timezone(&Utc));
+        self.guild_repo.save_notification(notification).await?;
+        Ok(NotificationStatus::Skipped)
+    }
+
+    /// 失敗を記録し、再試行の上限に達した通知は送信をあきらめる
+    async fn record_failure(&self, notification: &mut BirthdayNotification, error: &anyhow::Error) {
+        notification.attempts += 1;
+        notification.last_error = Some(error.to_string());
+        if notification.attempts >= MAX_ATTEMPTS {
+            notification.status = NotificationStatus::Failed;
+            notification.completed_at = Some(self.clock.now().with_timezone(&Utc));
+        }
+        if let Err(e) = self.guild_repo.save_notification(notification).await {
+            tracing::error!(
+                guild_id = notification.guild_id,
+                member_id = notification.member_id,
+                "Failed to record birthday notification failure: {}",
+                e
+            );
+        }
+    }
+
+    /// お知らせへのリプライとして送るお祝いメッセージ (ギルドが登録したセリフも候補にする)
+    async fn celebration_content(
+        &self,
+        notification: &BirthdayNotification,
+        locale: Locale,
+    ) -> anyhow::Result<String> {
+        let custom_lines = self
+            .guild_repo
+            .get_celebration_lines(notification.guild_id)
+            .await?
+            .into_iter()
+            .map(|line| line.line)
+            .collect::<Vec<_>>();
+        Ok(persona::celebration(
+            locale,
+            notification.member_id,
+            &custom_lines,
+            Pick::Seed(notification_hash(notification, "celebration")),
+        ))
+    }
+
+    /// 今日が誕生日で、今年まだ通知していないメンバーを取得
+    async fn find_birthday_members(&self, today: NaiveDate) -> anyhow::Result<Vec<GuildMember>> {
+        let members = self
+            .guild_repo
+            .get_all_members()
+            .await?
+            .into_iter()
+            .filter(|member| is_notify_target(member, today))
+            .collect();
+        Ok(members)
+    }
+}
+
+/// 通知・メッセージごとに一意な nonce
+///
+/// 送信済みの記録に失敗して再送した場合でも、Discord 側で重複を防げるようにする。
+/// 上限の25文字に収まるよう、ハッシュの16進数表記にする。
+fn message_nonce(notification: &BirthdayNotification, label: &str) -> String {
+    format!("birthday-{:016x}", notification_hash(notification, label))
+}
+
+/// 通知と用途ごとに決まる FNV-1a ハッシュ
+///
+/// 再送しても同じ値になるため、nonce や言い回しの選択に使う。
+/// 同じ年に別の日付で通知し直した場合は別の値になるよう、通知日も含める。
+fn notification_hash(notification: &BirthdayNotification, label: &str) -> u64 {
+    let bytes = notification
+        .guild_id
+        .to_le_bytes()
+        .into_iter()
+        .chain(notification.member_id.to_le_bytes())
+        .chain(notification.year.to_le_bytes())
+        .chain(notification.notify_date.to_string().into_bytes())
+        .chain(label.bytes());
+    fnv1a64(bytes)
+}
+
+/// 誕生日のお知らせ本文
+fn announcement_content(notification: &BirthdayNotification, locale: Locale) -> String {
+    Line::Announcement.text(
+        locale,
+        notification.member_id,
+        Pick::Seed(notification_hash(notification, "announcement")),
+    )
+}
+
+fn is_notify_target(member: &GuildMember, today: NaiveDate) -> bool {
+    let Some(birth) = member.birth else {
+        return false; // メンバーの誕生日が存在しない
+    };
+    let is_already_notified = member
+        .last_notified
+        .is_some_and(|last_notified| last_notified.year() >= today.year());
+    !is_already_notified && is_birthday(birth, today)
+}
+
+fn is_birthday(birth: NaiveDate, today: NaiveDate) -> bool {
+    if birth.month() == today.month() && birth.day() == today.day() {
+        return true;
+    }
+    // うるう日生まれのメンバーは、うるう年でない年は2/28に通知する
+    let is_leap_year = NaiveDate::from_ymd_opt(today.year(), 2, 29).is_some();
+    birth.month() == 2
+        && birth.day() == 29
+        && !is_leap_year
+        && today.month() == 2
+        && today.day() == 28
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BirthNotifyUsecase;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::fake_discord_gateway::{DiscordCall, FakeDiscordGateway};
+    use crate::data::guild_repository::GuildRepository;
+    use crate::data::memory_store::MemoryStore;
+    use crate::models::data::{BirthdayNotification, NotificationStatus};
+    use crate::res::messages::Locale;
+    use crate::res::persona::Line;
+    use crate::services::clock::FixedClock;
+    use chrono::{Datelike, NaiveDate};
+    use serenity::all::{ChannelId, MessageId};
+    use std::sync::Arc;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    async fn store_with_members(
+        members: &[(i64, Option<NaiveDate>, Option<NaiveDate>)],
+    ) -> Arc<MemoryStore> {
+        let store = Arc::new(MemoryStore::new());
+        store.insert_guild(1, Some("guild")).await.unwrap();
+        for &(member_id, birth, last_notified) in members {
+            store
+                .insert_guild_member(1, member_id, birth)
+                .await
+                .unwrap();
+            if let Some(last_notified) = last_notified {
+                let mut tx = store.begin().await.unwrap();
+                tx.update_guild_member_last_notified(1, member_id, last_notified)
+                    .await
+                    .unwrap();
+                tx.commit().await.unwrap();
+            }
+        }
+        store
+    }
+
+    fn discord_with_general_channel() -> Arc<FakeDiscordGateway> {
+        let discord = Arc::new(FakeDiscordGateway::new());
+        discord.add_guild(1, "guild");
+        discord.add_text_channel(1, 100, "general");
+        discord
+    }
+
+    fn notify_usecase_at(
+        store: Arc<MemoryStore>,
+        discord: Arc<FakeDiscordGateway>,
+        today: NaiveDate,
+    ) -> BirthNotifyUsecase {
+        let clock = FixedClock::at(today.year(), today.month(), today.day(), 12, 0);
+        BirthNotifyUsecase::new(store, discord, Arc::new(clock)).unwrap()
+    }
+
+    async fn last_notified(store: &MemoryStore, member_id: i64) -> Option<NaiveDate> {
+        store
+            .select_member_by_id(1, member_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .last_notified
+    }
+
+    async fn notification(store: &MemoryStore, member_id: i64) -> BirthdayNotification {
+        store
+            .select_notifications()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|notification| notification.member_id == member_id)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn find_birthday_members_returns_members_born_today() {
+        let store = store_with_members(&[
+            (10, Some(date(1970, 2, 1)), None),
+            (11, Some(date(1970, 2, 2)), None),
+            (12, None, None),
+        ])
+        .await;
+        let usecase =
+            notify_usecase_at(store, Arc::new(FakeDiscordGateway::new()), date(2025, 2, 1));
+
+        let members = usecase
+            .find_birthday_members(date(2025, 2, 1))
+            .await
+            .unwrap();
+
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].member_id, 10);
+    }
+
+    #[tokio::test]
+    async fn find_birthday_members_skips_members_already_notified_this_year() {
+        let store = store_with_members(&[
+            (10, Some(date(1970, 2, 1)), Some(date(2025, 2, 1))),
+            (11, Some(date(1970, 2, 1)), Some(date(2024, 2, 1))),
+        ])
+        .await;
+        let usecase =
+            notify_usecase_at(store, Arc::new(FakeDiscordGateway::new()), date(2025, 2, 1));
+
+        let members = usecase
+            .find_birthday_members(date(2025, 2, 1))
+            .await
+            .unwrap();
+
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].member_id, 11);
+    }
+
+    #[tokio::test]
+    async fn invoke_sends_announcement_reaction_and_reply() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        let calls = discord.calls();
+        assert_eq!(calls.len(), 3);
+        let DiscordCall::SendMessage {
+            channel_id,
+            message_id,
+            message,
+        } = &calls[0]
+        else {
+            panic!("first call must be the announcement: {:?}", calls[0]);
+        };
+        assert_eq!(*channel_id, ChannelId::new(100));
+        assert!(message.content.contains("<@10>"));
+        assert_eq!(message.embed.as_ref().unwrap().title, "ずんだもん");
+        assert_eq!(
+            calls[1],
+            DiscordCall::React {
+                channel_id: ChannelId::new(100),
+                message_id: *message_id,
+                emoji: "🎉".to_string(),
+            }
+        );
+        let DiscordCall::SendMessage { message: reply, .. } = &calls[2] else {
+            panic!("third call must be the reply: {:?}", calls[2]);
+        };
+        assert_eq!(reply.reply_to, Some(*message_id));
+        assert_eq!(last_notified(&store, 10).await, Some(date(2025, 2, 1)));
+    }
+
+    #[tokio::test]
+    async fn invoke_announces_in_the_guild_locale() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        store.update_guild_locale(1, Some("en")).await.unwrap();
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        let messages = discord.sent_messages();
+        assert!(messages[0].content.contains("<@10>'s birthday"));
+        assert_eq!(
+            messages[0].embed.as_ref().unwrap().description,
+            "February 1"
+        );
+        assert!(Line::Celebration
+            .variants(Locale::En)
+            .iter()
+            .any(|variant| variant.replace("{mention}", "<@10>") == messages[1].content));
+    }
+
+    #[tokio::test]
+    async fn invoke_skips_guild_without_general_channel() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = Arc::new(FakeDiscordGateway::new());
+        discord.add_guild(1, "guild");
+        discord.add_text_channel(1, 100, "random");
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        assert!(discord.calls().is_empty());
+        assert_eq!(last_notified(&store, 10).await, None);
+    }
+
+    #[tokio::test]
+    async fn invoke_continues_when_a_member_has_left() {
+        let store = store_with_members(&[
+            (10, Some(date(1970, 2, 1)), None),
+            (11, Some(date(1970, 2, 1)), None),
+        ])
+        .await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 11, "めたん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        let sent = discord.sent_messages();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|message| message.content.contains("<@11>")));
+        assert_eq!(last_notified(&store, 10).await, None);
+        assert_eq!(last_notified(&store, 11).await, Some(date(2025, 2, 1)));
+    }
+
+    #[tokio::test]
+    async fn invoke_does_not_record_failed_notification() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        discord.make_send_failing();
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        assert!(discord.calls().is_empty());
+        assert_eq!(last_notified(&store, 10).await, None);
+        let notification = notification(&store, 10).await;
+        assert_eq!(notification.status, NotificationStatus::Pending);
+        assert_eq!(notification.attempts, 1);
+        assert!(notification.last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_reaction_keeps_the_sent_announcement() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        discord.make_react_failing();
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        assert_eq!(discord.sent_messages().len(), 1);
+        let notification = notification(&store, 10).await;
+        assert_eq!(notification.status, NotificationStatus::Pending);
+        assert_eq!(notification.channel_id, Some(100));
+        assert!(notification.announcement_message_id.is_some());
+        assert!(!notification.reacted);
+        assert_eq!(last_notified(&store, 10).await, None);
+    }
+
+    #[tokio::test]
+    async fn invoke_resumes_partially_sent_notification_without_resending() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let mut partial = BirthdayNotification::new(1, 10, 2025, date(2025, 2, 1));
+        partial.channel_id = Some(100);
+        partial.announcement_message_id = Some(7);
+        let mut tx = store.begin().await.unwrap();
+        tx.upsert_notification(&partial).await.unwrap();
+        tx.commit().await.unwrap();
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+
+        usecase.invoke().await.unwrap();
+
+        let calls = discord.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(
+            calls[0],
+            DiscordCall::React {
+                channel_id: ChannelId::new(100),
+                message_id: MessageId::new(7),
+                emoji: "🎉".to_string(),
+            }
+        );
+        let DiscordCall::SendMessage { message: reply, .. } = &calls[1] else {
+            panic!("second call must be the reply: {:?}", calls[1]);
+        };
+        assert_eq!(reply.reply_to, Some(MessageId::new(7)));
+        let notification = notification(&store, 10).await;
+        assert_eq!(notification.status, NotificationStatus::Sent);
+        assert!(notification.reply_message_id.is_some());
+        assert_eq!(last_notified(&store, 10).await, Some(date(2025, 2, 1)));
+    }
+
+    #[tokio::test]
+    async fn resume_pending_does_not_announce_after_the_birthday() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let mut tx = store.begin().await.unwrap();
+        tx.upsert_notification(&BirthdayNotification::new(1, 10, 2025, date(2025, 2, 1)))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 2));
+
+        assert_eq!(usecase.resume_pending().await.unwrap(), 0);
+
+        assert!(discord.calls().is_empty());
+        assert_eq!(
+            notification(&store, 10).await.status,
+            NotificationStatus::Skipped
+        );
+    }
+
+    #[tokio::test]
+    async fn reset_keeps_the_sent_notification_as_history() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+        usecase.invoke().await.unwrap();
+        assert_eq!(discord.sent_messages().len(), 2);
+
+        let guild_repo = GuildRepository::new(store.clone(), discord.clone()).unwrap();
+        let mut uow = guild_repo.begin().await.unwrap();
+        assert!(uow.reset_member_birth(1, 10).await.unwrap());
+        uow.commit().await.unwrap();
+        assert_eq!(
+            notification(&store, 10).await.status,
+            NotificationStatus::Sent
+        );
+
+        // 同じ日付で登録し直しても、送信済みのお祝いを同じ日にもう一度送らない
+        let mut uow = guild_repo.begin().await.unwrap();
+        assert!(uow
+            .signup_member_birth(1, 10, date(1970, 2, 1))
+            .await
+            .unwrap());
+        uow.commit().await.unwrap();
+        usecase.invoke().await.unwrap();
+
+        assert_eq!(discord.sent_messages().len(), 2);
+        assert_eq!(store.select_notifications().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn signup_again_with_another_date_notifies_again_in_the_same_year() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1))
+            .invoke()
+            .await
+            .unwrap();
+
+        let guild_repo = GuildRepository::new(store.clone(), discord.clone()).unwrap();
+        let mut uow = guild_repo.begin().await.unwrap();
+        assert!(uow.reset_member_birth(1, 10).await.unwrap());
+        assert!(uow
+            .signup_member_birth(1, 10, date(1970, 3, 1))
+            .await
+            .unwrap());
+        uow.commit().await.unwrap();
+        notify_usecase_at(store.clone(), discord.clone(), date(2025, 3, 1))
+            .invoke()
+            .await
+            .unwrap();
+
+        // 最初のお祝いは履歴として残ったまま、新しい日付でもう一度通知する
+        assert_eq!(discord.sent_messages().len(), 4);
+        let notify_dates = store
+            .select_notifications()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|notification| (notification.notify_date, notification.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notify_dates,
+            vec![
+                (date(2025, 2, 1), NotificationStatus::Sent),
+                (date(2025, 3, 1), NotificationStatus::Sent),
+            ]
+        );
+        assert_eq!(last_notified(&store, 10).await, Some(date(2025, 3, 1)));
+    }
+
+    #[tokio::test]
+    async fn changed_birth_is_not_blocked_by_a_failed_notification() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let mut failed = BirthdayNotification::new(1, 10, 2025, date(2025, 2, 1));
+        failed.status = NotificationStatus::Failed;
+        failed.attempts = 3;
+        let mut tx = store.begin().await.unwrap();
+        tx.upsert_notification(&failed).await.unwrap();
+        tx.commit().await.unwrap();
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+
+        let guild_repo = GuildRepository::new(store.clone(), discord.clone()).unwrap();
+        assert!(guild_repo
+            .set_member_birth(1, None, 10, Some(date(1970, 3, 1)))
+            .await
+            .unwrap());
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 3, 1));
+        usecase.invoke().await.unwrap();
+
+        assert_eq!(discord.sent_messages().len(), 2);
+        assert_eq!(last_notified(&store, 10).await, Some(date(2025, 3, 1)));
+    }
+
+    #[tokio::test]
+    async fn rearmed_notification_is_sent_on_resume() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let mut failed = BirthdayNotification::new(1, 10, 2025, date(2025, 2, 1));
+        failed.status = NotificationStatus::Failed;
+        failed.attempts = 3;
+        let mut tx = store.begin().await.unwrap();
+        tx.upsert_notification(&failed).await.unwrap();
+        tx.commit().await.unwrap();
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 2, 1));
+        // 失敗した通知は、送信待ちに戻すまで再開されない
+        assert_eq!(usecase.resume_pending().await.unwrap(), 0);
+
+        let guild_repo = GuildRepository::new(store.clone(), discord.clone()).unwrap();
+        assert!(guild_repo.rearm_notification(1, 10, 2025).await.unwrap());
+        assert_eq!(usecase.resume_pending().await.unwrap(), 1);
+
+        assert_eq!(discord.sent_messages().len(), 2);
+        assert_eq!(
+            notification(&store, 10).await.status,
+            NotificationStatus::Sent
+        );
+    }
+
+    #[tokio::test]
+    async fn invoke_notifies_again_after_year_boundary() {
+        let store =
+            store_with_members(&[(10, Some(date(1970, 1, 1)), Some(date(2024, 12, 31)))]).await;
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 1, 1));
+
+        usecase.invoke().await.unwrap();
+
+        assert_eq!(discord.sent_messages().len(), 2);
+        assert_eq!(last_notified(&store, 10).await, Some(date(2025, 1, 1)));
+    }
+
+    #[tokio::test]
+    async fn find_birthday_members_moves_leap_day_to_feb_28_in_common_years() {
+        let store = store_with_members(&[(10, Some(date(2000, 2, 29)), None)]).await;
+        let usecase = notify_usecase_at(store, discord_with_general_channel(), date(2025, 2, 28));
+
+        let common_year = usecase
+            .find_birthday_members(date(2025, 2, 28))
+            .await
+            .unwrap();
+        let leap_year_feb_28 = usecase
+            .find_birthday_members(date(2024, 2, 28))
+            .await
+            .unwrap();
+        let leap_year_feb_29 = usecase
+            .find_birthday_members(date(2024, 2, 29))
+            .await
+            .unwrap();
+
+        assert_eq!(common_year.len(), 1);
+        assert!(leap_year_feb_28.is_empty());
+        assert_eq!(leap_year_feb_29.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn invoke_as_of_notifies_only_the_given_guild() {
+        let store = store_with_members(&[(10, Some(date(1970, 2, 1)), None)]).await;
+        store.insert_guild(2, Some("other")).await.unwrap();
+        store
+            .insert_guild_member(2, 20, Some(date(1970, 2, 1)))
+            .await
+            .unwrap();
+        let discord = discord_with_general_channel();
+        discord.add_member(1, 10, "ずんだもん");
+        let usecase = notify_usecase_at(store.clone(), discord.clone(), date(2025, 6, 1));
+
+        let notified_count = usecase.invoke_as_of(date(2025, 2, 1), 1).await.unwrap();
+
+        assert_eq!(notified_count, 1);
+        assert_eq!(last_notified(&store, 10).await, Some(date(2025, 2, 1)));
+        let other = store.select_member_by_id(2, 20).await.unwrap().unwrap();
+        assert_eq!(other.last_notified, None);
     }
 }
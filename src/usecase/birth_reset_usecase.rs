@@ -1,9 +1,11 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
 use crate::data::guild_repository::GuildRepository;
 use crate::models::common::{Context, Error};
 use crate::res::colors::{EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
+use crate::res::messages::Message;
 use poise::CreateReply;
-use serenity::all::{CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse, Http};
-use sqlx::PgPool;
+use serenity::all::{CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -12,8 +14,11 @@ pub struct BirthResetUsecase {
 }
 
 impl BirthResetUsecase {
-    pub fn new(pool: Arc<PgPool>, http: Arc<Http>) -> anyhow::Result<Self> {
-        let guild_repo = GuildRepository::new(pool, http.clone())?;
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
         Ok(BirthResetUsecase { guild_repo })
     }
 
@@ -29,16 +34,16 @@ impl BirthResetUsecase {
             .map(|guild| guild.name.clone())
             .unwrap_or_else(|| format!("guild-{guild_id}"));
 
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
         // コマンドを実行したメンバーのメンバーIDを取得
         let member_id = i64::from(poise_ctx.author().id);
 
         // 初回参加メンバーでも参照できるよう、対象レコードを事前に作成しておく
-        self.guild_repo
-            .add_guild(guild_id, Some(guild_name.as_str()))
-            .await?;
-        self.guild_repo
-            .add_member(guild_id, member_id, None)
-            .await?;
+        let mut uow = self.guild_repo.begin().await?;
+        uow.add_guild(guild_id, Some(guild_name.as_str())).await?;
+        uow.add_member(guild_id, member_id, None).await?;
+        uow.commit().await?;
 
         // ギルドIDとメンバーIDに一致するメンバーの誕生日をguild_memberテーブルから取得
         let member_birth = self
@@ -53,7 +58,7 @@ impl BirthResetUsecase {
                     CreateReply::default()
                         .embed(
                             CreateEmbed::new()
-                                .title("⚠️ 誕生日が登録されていないのだ")
+                                .title(Message::BirthNotRegistered.text(locale))
                                 .color(EMBED_COLOR_WARNING), // 警告系の色
                         )
                         .ephemeral(true),
@@ -62,13 +67,13 @@ impl BirthResetUsecase {
         } else {
             // 誕生日解除の確認メッセージと「解除」ボタンを表示
             let reset_button = CreateButton::new("reset")
-                .label("解除")
+                .label(Message::ResetButton.text(locale))
                 .style(serenity::all::ButtonStyle::Danger);
             let action_row = CreateActionRow::Buttons(vec![reset_button]);
             let reply_handle = poise_ctx
                 .send(
                     CreateReply::default()
-                        .content("誕生日の通知登録を解除するのだ⚠️")
+                        .content(Message::ResetConfirm.text(locale))
                         .components(vec![action_row])
                         .ephemeral(true),
                 )
@@ -89,10 +94,11 @@ impl BirthResetUsecase {
                         });
 
                     // ユーザーが「解除」ボタンを押下
-                    // guild_memberテーブルの誕生日と最終通知日をNULLに更新
-                    self.guild_repo
-                        .reset_member_birth(guild_id, member_id)
-                        .await?;
+                    // guild_memberテーブルの誕生日と最終通知日をNULLに更新し、送信済みでない通知を削除
+                    // 確認中に別の操作で解除されていた場合は更新しない
+                    let mut uow = self.guild_repo.begin().await?;
+                    let is_reset = uow.reset_member_birth(guild_id, member_id).await?;
+                    uow.commit().await?;
 
                     // 誕生日解除の確認メッセージと「解除」ボタンを削除
                     reply_handle
@@ -100,18 +106,20 @@ impl BirthResetUsecase {
                         .await
                         .unwrap_or_else(|e| tracing::warn!("Failed to delete message: {}", e));
 
-                    // 「誕生日通知が解除されたこと」をメッセージで通知
+                    let embed = if is_reset {
+                        // 「誕生日通知が解除されたこと」をメッセージで通知
+                        CreateEmbed::new()
+                            .title(Message::ResetCompleted.text(locale))
+                            .description(Message::ResetCompletedDescription.text(locale))
+                            .color(EMBED_COLOR_SUCCESS) // 正常系の色
+                    } else {
+                        // 確認中にすでに解除されていたことをメッセージで通知
+                        CreateEmbed::new()
+                            .title(Message::BirthNotRegistered.text(locale))
+                            .color(EMBED_COLOR_WARNING) // 警告系の色
+                    };
                     poise_ctx
-                        .send(
-                            CreateReply::default()
-                                .embed(
-                                    CreateEmbed::new()
-                                        .title("🗑️ 誕生日の通知登録を解除したのだ。")
-                                        .description("登録した日付はリセットされたのだ。")
-                                        .color(EMBED_COLOR_SUCCESS), // 正常系の色
-                                )
-                                .ephemeral(true),
-                        )
+                        .send(CreateReply::default().embed(embed).ephemeral(true))
                         .await
                         .map_err(|e| {
                             tracing::warn!("Failed to send reset response: {}", e);
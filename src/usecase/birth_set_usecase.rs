@@ -0,0 +1,145 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
+use crate::data::guild_repository::GuildRepository;
+use crate::models::birth_date::parse_birth_date;
+use crate::models::common::{Context, Error};
+use crate::models::error::BotError;
+use crate::res::colors::EMBED_COLOR_SUCCESS;
+use crate::res::messages::{Locale, Message};
+use poise::{CreateReply, Modal};
+use serenity::all::{
+    CreateActionRow, CreateEmbed, CreateInputText, CreateInteractionResponse, CreateModal,
+    InputTextStyle, ModalInteractionData, UserId,
+};
+use std::sync::Arc;
+
+/// モデレーターがメンバーの誕生日を設定するユースケース
+pub struct BirthSetUsecase {
+    guild_repo: GuildRepository,
+}
+
+impl BirthSetUsecase {
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
+        Ok(BirthSetUsecase { guild_repo })
+    }
+
+    /// モーダルで入力してもらった誕生日を、メンバーの誕生日として上書きする (空欄の場合は登録を解除する)
+    pub async fn invoke(
+        &self,
+        poise_ctx: Context<'_>,
+        member_id: UserId,
+    ) -> anyhow::Result<(), Error> {
+        let Context::Application(app_ctx) = poise_ctx else {
+            return Ok(());
+        };
+        // 先にモーダルを開いて interaction のタイムアウトを避ける
+        // 登録済みの誕生日を読む時間もないため、入力欄は空のまま開く
+        let locale = poise_ctx
+            .locale()
+            .and_then(Locale::from_discord)
+            .unwrap_or_default();
+        let Some(data) = BirthSetModal::execute_with_defaults(
+            app_ctx,
+            BirthSetModal {
+                birth_input: None,
+                locale,
+            },
+        )
+        .await?
+        else {
+            return Ok(());
+        };
+
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let birth = data
+            .birth_input
+            .map(|input| parse_birth_date(&input).map(|birth| birth.to_date()))
+            .transpose()
+            .map_err(BotError::InvalidDate)?;
+
+        // コマンドが実行されたギルドのギルドIDを取得
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let guild_id = i64::from(guild_id);
+        let guild_name = poise_ctx
+            .guild()
+            .map(|guild| guild.name.clone())
+            .unwrap_or_else(|| format!("guild-{guild_id}"));
+
+        self.guild_repo
+            .set_member_birth(guild_id, Some(&guild_name), i64::from(member_id), birth)
+            .await?;
+        tracing::info!(
+            member_id = member_id.get(),
+            cleared = birth.is_none(),
+            "member birth was set by a moderator"
+        );
+
+        let embed = match birth {
+            Some(birth) => CreateEmbed::new()
+                .title(Message::MemberBirthSet.text(locale))
+                .description(format!(
+                    "<@{member_id}>: {}",
+                    locale.format_month_day(birth)
+                )),
+            None => CreateEmbed::new()
+                .title(Message::MemberBirthCleared.text(locale))
+                .description(format!("<@{member_id}>")),
+        };
+        poise_ctx
+            .send(
+                CreateReply::default()
+                    .embed(embed.color(EMBED_COLOR_SUCCESS)) // 正常系の色
+                    .ephemeral(true),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// メンバーの誕生日を入力するモーダル
+///
+/// 空欄で送信した場合は `birth_input` が `None` になる。`locale` は表示にのみ使う。
+#[derive(Debug)]
+struct BirthSetModal {
+    birth_input: Option<String>,
+    locale: Locale,
+}
+
+impl Modal for BirthSetModal {
+    fn create(defaults: Option<Self>, custom_id: String) -> CreateInteractionResponse {
+        let locale = defaults
+            .as_ref()
+            .map(|defaults| defaults.locale)
+            .unwrap_or_default();
+        let birth_input = CreateInputText::new(
+            InputTextStyle::Short,
+            Message::BirthSetModalField.text(locale),
+            "birth_input",
+        )
+        .placeholder("02/01")
+        .max_length(20)
+        .required(false);
+        CreateInteractionResponse::Modal(
+            CreateModal::new(custom_id, Message::BirthSetModalTitle.text(locale))
+                .components(vec![CreateActionRow::InputText(birth_input)]),
+        )
+    }
+
+    fn parse(mut data: ModalInteractionData) -> Result<Self, &'static str> {
+        let birth_input = poise::modal::find_modal_text(&mut data, "birth_input")
+            .filter(|input| !input.trim().is_empty());
+        Ok(BirthSetModal {
+            birth_input,
+            locale: Locale::default(),
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
+use crate::data::guild_repository::GuildRepository;
+use crate::models::common::{Context, Error};
+use crate::res::colors::{EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
+use crate::res::messages::Message;
+use crate::services::clock::Clock;
+use chrono::{Datelike, NaiveDate};
+use poise::CreateReply;
+use serenity::all::{CreateEmbed, UserId};
+use std::sync::Arc;
+
+pub struct BirthShowUsecase {
+    guild_repo: GuildRepository,
+    clock: Arc<dyn Clock>,
+}
+
+impl BirthShowUsecase {
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+        clock: Arc<dyn Clock>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
+        Ok(BirthShowUsecase { guild_repo, clock })
+    }
+
+    /// メンバーの誕生日と、誕生日までの日数を表示する (`member_id` を省略した場合は実行したメンバー自身)
+    ///
+    /// 年は登録されていても表示せず、返信は実行したメンバーにだけ見えるようにする。
+    pub async fn invoke(
+        &self,
+        poise_ctx: Context<'_>,
+        member_id: Option<UserId>,
+    ) -> anyhow::Result<(), Error> {
+        // コマンドが実行されたギルドのギルドIDを取得
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let is_self = member_id.is_none_or(|member_id| member_id == poise_ctx.author().id);
+        let member_id = member_id.unwrap_or(poise_ctx.author().id);
+        let member_birth = self
+            .guild_repo
+            .get_member_birth(i64::from(guild_id), i64::from(member_id))
+            .await?;
+
+        let embed = match member_birth {
+            Some(birth) => {
+                let days = days_until_birthday(birth, self.clock.now().date_naive());
+                let countdown = if days == 0 {
+                    Message::BirthdayToday
+                } else {
+                    Message::DaysUntilBirthday { days }
+                };
+                CreateEmbed::new()
+                    .title(Message::BirthShowTitle.text(locale))
+                    .description(format!(
+                        "<@{member_id}>: {}\n{}",
+                        locale.format_month_day(birth),
+                        countdown.text(locale)
+                    ))
+                    .color(EMBED_COLOR_SUCCESS) // 正常系の色
+            }
+            None => {
+                let message = if is_self {
+                    Message::BirthNotRegistered
+                } else {
+                    Message::MemberBirthNotRegistered
+                };
+                CreateEmbed::new()
+                    .title(message.text(locale))
+                    .color(EMBED_COLOR_WARNING) // 警告系の色
+            }
+        };
+        poise_ctx
+            .send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// 次の誕生日までの日数 (今日が誕生日の場合は 0)
+///
+/// うるう日生まれのメンバーは、通知と同じくうるう年でない年は2/28を誕生日として数える。
+fn days_until_birthday(birth: NaiveDate, today: NaiveDate) -> i64 {
+    let birthday_in = |year: i32| {
+        NaiveDate::from_ymd_opt(year, birth.month(), birth.day())
+            .or_else(|| NaiveDate::from_ymd_opt(year, 2, 28))
+            .expect("February 28 exists every year")
+    };
+    let this_year = birthday_in(today.year());
+    let next_birthday = if this_year >= today {
+        this_year
+    } else {
+        birthday_in(today.year() + 1)
+    };
+    (next_birthday - today).num_days()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::days_until_birthday;
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn days_are_counted_to_the_next_birthday() {
+        let birth = date(2000, 2, 1);
+        assert_eq!(days_until_birthday(birth, date(2026, 2, 1)), 0);
+        assert_eq!(days_until_birthday(birth, date(2026, 1, 31)), 1);
+        // 今年の誕生日が過ぎている場合は来年の誕生日まで数える
+        assert_eq!(days_until_birthday(birth, date(2026, 2, 2)), 364);
+        assert_eq!(days_until_birthday(birth, date(2028, 2, 2)), 365);
+    }
+
+    #[test]
+    fn leap_day_birthday_falls_on_february_28_in_common_years() {
+        let birth = date(2000, 2, 29);
+        assert_eq!(days_until_birthday(birth, date(2026, 2, 28)), 0);
+        assert_eq!(days_until_birthday(birth, date(2026, 2, 27)), 1);
+        assert_eq!(days_until_birthday(birth, date(2028, 2, 28)), 1);
+        assert_eq!(days_until_birthday(birth, date(2027, 3, 1)), 365);
+    }
+}
@@ -1,50 +1,74 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
 use crate::data::guild_repository::GuildRepository;
+use crate::models::birth_date::parse_birth_date;
 use crate::models::common::{Context, Error};
-use crate::res::colors::{EMBED_COLOR_ERROR, EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
-use chrono::NaiveDate;
+use crate::models::error::BotError;
+use crate::res::colors::{EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
+use crate::res::messages::{Locale, Message};
+use chrono::{NaiveDate, NaiveTime};
 use poise::{CreateReply, Modal};
-use serenity::all::{CreateEmbed, Http};
-use sqlx::PgPool;
+use serenity::all::{
+    CreateActionRow, CreateEmbed, CreateInputText, CreateInteractionResponse, CreateModal,
+    InputTextStyle, ModalInteractionData,
+};
 use std::sync::Arc;
 
 pub struct BirthSignupUsecase {
     guild_repo: GuildRepository,
+    /// 誕生日を通知する時刻 (登録完了のメッセージで案内する)
+    notify_time: NaiveTime,
 }
 
 impl BirthSignupUsecase {
-    pub fn new(pool: Arc<PgPool>, http: Arc<Http>) -> anyhow::Result<Self> {
-        let guild_repo = GuildRepository::new(pool, http.clone())?;
-        Ok(BirthSignupUsecase { guild_repo })
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+        notify_time: NaiveTime,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
+        Ok(BirthSignupUsecase {
+            guild_repo,
+            notify_time,
+        })
     }
 
-    pub async fn invoke(&self, poise_ctx: Context<'_>) -> anyhow::Result<(), Error> {
-        let input_birth = if let Context::Application(app_ctx) = poise_ctx {
-            // 先にモーダルを開いて interaction のタイムアウトを避ける
-            let data = BirthSignupModal::execute(app_ctx).await?;
-            match data {
-                Some(data) => data.birth_input,
-                None => return Ok(()),
+    /// 誕生日を登録する (`input_birth` を省略した場合はモーダルで入力してもらう)
+    pub async fn invoke(
+        &self,
+        poise_ctx: Context<'_>,
+        input_birth: Option<String>,
+    ) -> anyhow::Result<(), Error> {
+        let input_birth = match (input_birth, poise_ctx) {
+            (Some(input_birth), _) => input_birth,
+            (None, Context::Application(app_ctx)) => {
+                // 先にモーダルを開いて interaction のタイムアウトを避ける
+                // ギルドの設定を読む時間もないため、モーダルは Discord の表示言語だけで決める
+                let locale = poise_ctx
+                    .locale()
+                    .and_then(Locale::from_discord)
+                    .unwrap_or_default();
+                let data = BirthSignupModal::execute_with_defaults(
+                    app_ctx,
+                    BirthSignupModal {
+                        birth_input: String::new(),
+                        locale,
+                    },
+                )
+                .await?;
+                match data {
+                    Some(data) => data.birth_input,
+                    None => return Ok(()),
+                }
             }
-        } else {
-            return Ok(());
+            (None, Context::Prefix(_)) => return Ok(()),
         };
 
-        let birth = NaiveDate::parse_from_str(&format!("1970/{input_birth}"), "%Y/%m/%d");
-        if birth.is_err() {
-            // 誕生日の入力フォーマットが無効
-            poise_ctx
-                .send(
-                    CreateReply::default()
-                        .embed(
-                            CreateEmbed::new()
-                                .title("🚨  誕生日が正しいフォーマットで入力されていないのだ。")
-                                .color(EMBED_COLOR_ERROR), // 異常系の色
-                        )
-                        .ephemeral(true),
-                )
-                .await?;
-            return Ok(());
-        }
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let birth = parse_birth_date(&input_birth)
+            .map_err(BotError::InvalidDate)?
+            .to_date();
 
         // コマンドが実行されたギルドのギルドIDを取得
         let guild_id = self
@@ -60,49 +84,36 @@ impl BirthSignupUsecase {
         // コマンドを実行したメンバーのメンバーIDを取得;
         let member_id = i64::from(poise_ctx.author().id);
 
-        // 初回参加メンバーでも登録できるよう、対象レコードを事前に作成しておく
-        self.guild_repo
-            .add_guild(guild_id, Some(guild_name.as_str()))
-            .await?;
-        self.guild_repo
-            .add_member(guild_id, member_id, None)
-            .await?;
-
-        // ギルドIDとメンバーIDに一致するメンバー情報をguild_memberテーブルから取得
-        let member_birth = self
-            .guild_repo
-            .get_member_birth(guild_id, member_id)
-            .await?;
-
-        if member_birth.is_none() {
-            // メンバー情報に誕生日が存在しない
-            // guild_memberテーブルのメンバーIDに一致するにメンバーの誕生日を更新
-            self.guild_repo
-                .update_member_birth(guild_id, member_id, birth?)
-                .await?;
+        let is_signed_up = self.signup(guild_id, &guild_name, member_id, birth).await?;
 
+        if is_signed_up {
             // 「誕生日通知の登録が完了したこと」をメッセージで通知
             poise_ctx
                 .send(
                     CreateReply::default()
                         .embed(
                             CreateEmbed::new()
-                                .title("✅  誕生日の通知登録が完了したのだ。")
+                                .title(Message::SignupCompleted.text(locale))
                                 .color(EMBED_COLOR_SUCCESS), // 正常系の色
                         )
-                        .content("登録した日付の正午（12:00）に誕生日が通知されるのだ。")
+                        .content(
+                            Message::SignupCompletedNote {
+                                notify_time: self.notify_time,
+                            }
+                            .text(locale),
+                        )
                         .ephemeral(true),
                 )
                 .await?;
         } else {
-            // メンバー情報に誕生日が存在する
+            // メンバー情報に誕生日が存在する(同時に登録された場合も含む)
             // 「すでに誕生日通知が登録済であること」をメッセージで通知
             poise_ctx
                 .send(
                     CreateReply::default()
                         .embed(
                             CreateEmbed::new()
-                                .title("⚠️ 誕生日はすでに登録済みなのだ")
+                                .title(Message::AlreadySignedUp.text(locale))
                                 .color(EMBED_COLOR_WARNING), // 警告系の色
                         )
                         .ephemeral(true),
@@ -112,14 +123,110 @@ impl BirthSignupUsecase {
 
         Ok(())
     }
+
+    /// 誕生日が未登録の場合のみ登録し、登録できたかどうかを返す
+    async fn signup(
+        &self,
+        guild_id: i64,
+        guild_name: &str,
+        member_id: i64,
+        birth: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        // 初回参加メンバーでも登録できるよう対象レコードを作成し、未登録の場合のみ誕生日を登録する
+        // 連続送信やギルド同期と処理が交差しないよう、1つのトランザクションで実行
+        let mut uow = self.guild_repo.begin().await?;
+        uow.add_guild(guild_id, Some(guild_name)).await?;
+        uow.add_member(guild_id, member_id, None).await?;
+        let is_signed_up = uow.signup_member_birth(guild_id, member_id, birth).await?;
+        uow.commit().await?;
+        Ok(is_signed_up)
+    }
 }
 
-#[derive(Debug, Modal)]
-#[name = "誕生日の通知登録"] // 最初のタイトル
+/// 誕生日を入力するモーダル
+///
+/// タイトルを言語ごとに切り替えるため、derive せずに実装する。`locale` は表示にのみ使う。
+#[derive(Debug)]
 struct BirthSignupModal {
-    #[name = "自身の誕生日を入力するのだ"] // フィールドのタイトル
-    #[placeholder = "02/01"]
-    #[min_length = 5]
-    #[max_length = 5]
     birth_input: String,
+    locale: Locale,
+}
+
+impl Modal for BirthSignupModal {
+    fn create(defaults: Option<Self>, custom_id: String) -> CreateInteractionResponse {
+        let locale = defaults
+            .as_ref()
+            .map(|defaults| defaults.locale)
+            .unwrap_or_default();
+        let birth_input = CreateInputText::new(
+            InputTextStyle::Short,
+            Message::SignupModalField.text(locale),
+            "birth_input",
+        )
+        .placeholder("02/01")
+        .max_length(20);
+        CreateInteractionResponse::Modal(
+            CreateModal::new(custom_id, Message::SignupModalTitle.text(locale))
+                .components(vec![CreateActionRow::InputText(birth_input)]),
+        )
+    }
+
+    fn parse(mut data: ModalInteractionData) -> Result<Self, &'static str> {
+        let birth_input = poise::modal::find_modal_text(&mut data, "birth_input")
+            .ok_or("birth_input is required")?;
+        Ok(BirthSignupModal {
+            birth_input,
+            locale: Locale::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BirthSignupUsecase;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::fake_discord_gateway::FakeDiscordGateway;
+    use crate::data::memory_store::MemoryStore;
+    use chrono::{NaiveDate, NaiveTime};
+    use std::sync::Arc;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(1970, month, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn signup_registers_birth_for_first_time_member() {
+        let store = Arc::new(MemoryStore::new());
+        let usecase = BirthSignupUsecase::new(
+            store.clone(),
+            Arc::new(FakeDiscordGateway::new()),
+            NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+        )
+        .unwrap();
+
+        let is_signed_up = usecase.signup(1, "guild", 10, date(2, 1)).await.unwrap();
+
+        assert!(is_signed_up);
+        assert_eq!(store.select_guild_ids().await.unwrap(), vec![1]);
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, Some(date(2, 1)));
+    }
+
+    #[tokio::test]
+    async fn signup_does_not_overwrite_registered_birth() {
+        let store = Arc::new(MemoryStore::new());
+        let usecase = BirthSignupUsecase::new(
+            store.clone(),
+            Arc::new(FakeDiscordGateway::new()),
+            NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+        )
+        .unwrap();
+        usecase.signup(1, "guild", 10, date(2, 1)).await.unwrap();
+
+        let is_signed_up = usecase.signup(1, "guild", 10, date(3, 1)).await.unwrap();
+
+        assert!(!is_signed_up);
+        let member = store.select_member_by_id(1, 10).await.unwrap().unwrap();
+        assert_eq!(member.birth, Some(date(2, 1)));
+    }
 }
@@ -0,0 +1,192 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
+use crate::data::guild_repository::GuildRepository;
+use crate::models::common::{Context, Error};
+use crate::models::data::CelebrationLine;
+use crate::res::colors::{EMBED_COLOR_ERROR, EMBED_COLOR_SUCCESS, EMBED_COLOR_WARNING};
+use crate::res::messages::Message;
+use poise::CreateReply;
+use serenity::all::CreateEmbed;
+use std::sync::Arc;
+
+/// ギルドごとに登録できるセリフの上限
+const MAX_LINES: usize = 20;
+/// セリフ1件の最大文字数
+const MAX_CHARS: usize = 200;
+
+/// セリフを追加した結果
+#[derive(Debug, PartialEq)]
+enum AddOutcome {
+    Added(CelebrationLine),
+    TooLong,
+    LimitReached,
+}
+
+/// ギルドが独自のお祝いのセリフを登録・確認・削除するためのユースケース
+pub struct CelebrationUsecase {
+    guild_repo: GuildRepository,
+}
+
+impl CelebrationUsecase {
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
+        Ok(CelebrationUsecase { guild_repo })
+    }
+
+    pub async fn add(&self, poise_ctx: Context<'_>, line: String) -> anyhow::Result<(), Error> {
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let embed = match self.add_line(i64::from(guild_id), &line).await? {
+            AddOutcome::Added(line) => CreateEmbed::new()
+                .title(
+                    Message::CelebrationAdded {
+                        line_no: line.line_no,
+                    }
+                    .text(locale),
+                )
+                .description(line.line)
+                .color(EMBED_COLOR_SUCCESS), // 正常系の色
+            AddOutcome::TooLong => CreateEmbed::new()
+                .title(
+                    Message::CelebrationTooLong {
+                        max_chars: MAX_CHARS,
+                    }
+                    .text(locale),
+                )
+                .color(EMBED_COLOR_ERROR), // 異常系の色
+            AddOutcome::LimitReached => CreateEmbed::new()
+                .title(
+                    Message::CelebrationLimitReached {
+                        max_lines: MAX_LINES,
+                    }
+                    .text(locale),
+                )
+                .color(EMBED_COLOR_WARNING), // 警告系の色
+        };
+        poise_ctx
+            .send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list(&self, poise_ctx: Context<'_>) -> anyhow::Result<(), Error> {
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let lines = self
+            .guild_repo
+            .get_celebration_lines(i64::from(guild_id))
+            .await?;
+        let embed = if lines.is_empty() {
+            CreateEmbed::new()
+                .title(Message::CelebrationListEmpty.text(locale))
+                .color(EMBED_COLOR_WARNING) // 警告系の色
+        } else {
+            let description = lines
+                .iter()
+                .map(|line| format!("#{}: {}\n", line.line_no, line.line))
+                .collect::<String>();
+            CreateEmbed::new()
+                .title(Message::CelebrationListTitle.text(locale))
+                .description(description)
+                .color(EMBED_COLOR_SUCCESS) // 正常系の色
+        };
+        poise_ctx
+            .send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, poise_ctx: Context<'_>, line_no: i32) -> anyhow::Result<(), Error> {
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+
+        let is_removed = self
+            .guild_repo
+            .remove_celebration_line(i64::from(guild_id), line_no)
+            .await?;
+        let embed = if is_removed {
+            CreateEmbed::new()
+                .title(Message::CelebrationRemoved { line_no }.text(locale))
+                .color(EMBED_COLOR_SUCCESS) // 正常系の色
+        } else {
+            CreateEmbed::new()
+                .title(Message::CelebrationNotFound { line_no }.text(locale))
+                .color(EMBED_COLOR_WARNING) // 警告系の色
+        };
+        poise_ctx
+            .send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// 文字数と件数の上限を確認してからセリフを追加する
+    async fn add_line(&self, guild_id: i64, line: &str) -> anyhow::Result<AddOutcome> {
+        let line = line.trim();
+        if line.is_empty() || line.chars().count() > MAX_CHARS {
+            return Ok(AddOutcome::TooLong);
+        }
+        if self.guild_repo.get_celebration_lines(guild_id).await?.len() >= MAX_LINES {
+            return Ok(AddOutcome::LimitReached);
+        }
+        let line = self.guild_repo.add_celebration_line(guild_id, line).await?;
+        Ok(AddOutcome::Added(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddOutcome, CelebrationUsecase, MAX_LINES};
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::fake_discord_gateway::FakeDiscordGateway;
+    use crate::data::memory_store::MemoryStore;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn lines_are_numbered_per_guild_and_limited() {
+        let store = Arc::new(MemoryStore::new());
+        let usecase =
+            CelebrationUsecase::new(store.clone(), Arc::new(FakeDiscordGateway::new())).unwrap();
+
+        let AddOutcome::Added(first) = usecase.add_line(1, " ずんだ餅なのだ ").await.unwrap()
+        else {
+            panic!("the first line must be added");
+        };
+        assert_eq!((first.line_no, first.line.as_str()), (1, "ずんだ餅なのだ"));
+        assert_eq!(
+            usecase.add_line(1, &"あ".repeat(201)).await.unwrap(),
+            AddOutcome::TooLong
+        );
+        for _ in 1..MAX_LINES {
+            usecase.add_line(1, "おめでとうなのだ").await.unwrap();
+        }
+        assert_eq!(
+            usecase.add_line(1, "おめでとうなのだ").await.unwrap(),
+            AddOutcome::LimitReached
+        );
+
+        // 削除しても番号は詰めず、他のギルドの番号は1から始まる
+        assert!(store.delete_celebration_line(1, 1).await.unwrap());
+        let AddOutcome::Added(other) = usecase.add_line(2, "おめでとうなのだ").await.unwrap()
+        else {
+            panic!("another guild has its own limit");
+        };
+        assert_eq!(other.line_no, 1);
+        let lines = store.select_celebration_lines_by_guild_id(1).await.unwrap();
+        assert_eq!(lines.len(), MAX_LINES - 1);
+        assert_eq!(lines[0].line_no, 2);
+    }
+}
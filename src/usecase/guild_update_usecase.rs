@@ -1,24 +1,37 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
 use crate::data::guild_repository::GuildRepository;
 use crate::models::common::Error;
 use crate::models::data::GuildMember;
 use crate::models::domain::{MyGuild, MyGuildMember};
+use crate::services::metrics::{metrics, outcome_label, GuildSyncChanges};
 use poise::futures_util::future::join_all;
-use serenity::all::Http;
-use sqlx::PgPool;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
 pub struct GuildUpdateUsecase {
     guild_repo: GuildRepository,
 }
 
 impl GuildUpdateUsecase {
-    pub fn new(pool: Arc<PgPool>, http: Arc<Http>) -> anyhow::Result<Self> {
-        let guild_repo = GuildRepository::new(pool, http)?;
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
         Ok(GuildUpdateUsecase { guild_repo })
     }
 
     pub async fn invoke(&self) -> anyhow::Result<(), Error> {
+        let start = Instant::now();
+        let result = self.fetch_and_sync().await;
+        let changes = result.as_ref().copied().unwrap_or_default();
+        metrics().record_guild_sync(outcome_label(&result), start.elapsed(), changes);
+        result.map(|_| ())
+    }
+
+    async fn fetch_and_sync(&self) -> anyhow::Result<GuildSyncChanges, Error> {
         // --- ギルド情報取得  ------------------------------------------------------------------------
         // guildテーブルから「ギルドID」のリストを取得
         let local_guild_ids = self.guild_repo.get_guild_ids().await?;
@@ -29,13 +42,37 @@ impl GuildUpdateUsecase {
         let latest_guild_futures = latest_guild_ids
             .iter()
             .map(|guild_id| self.guild_repo.fetch_my_guild(guild_id));
-        let latest_my_guilds: Vec<MyGuild> = join_all(latest_guild_futures)
-            .await
+        let mut latest_my_guilds: Vec<MyGuild> = Vec::new();
+        let mut unavailable_guild_ids: HashSet<i64> = HashSet::new();
+        for (guild_id, result) in latest_guild_ids
+            .iter()
+            .zip(join_all(latest_guild_futures).await)
+        {
+            match result {
+                Ok(my_guild) => latest_my_guilds.push(my_guild),
+                Err(e) => {
+                    tracing::warn!(guild_id = %guild_id, "Failed to fetch guild for sync: {}", e);
+                    unavailable_guild_ids.insert(i64::from(*guild_id));
+                }
+            }
+        }
+        // -----------------------------------------------------------------------------------------------------
+
+        // 取得に失敗したギルドは「退出した」と誤判定して削除しないよう、同期対象から外す
+        let local_guild_ids = local_guild_ids
             .into_iter()
-            .filter_map(Result::ok)
+            .filter(|id| !unavailable_guild_ids.contains(id))
             .collect();
-        // -----------------------------------------------------------------------------------------------------
+        self.sync_guilds(local_guild_ids, &latest_my_guilds).await
+    }
 
+    /// APIから取得した最新のギルド情報をguild/guild_memberテーブルへ反映し、変更件数を返す
+    async fn sync_guilds(
+        &self,
+        local_guild_ids: Vec<i64>,
+        latest_my_guilds: &[MyGuild],
+    ) -> anyhow::Result<GuildSyncChanges, Error> {
+        let mut changes = GuildSyncChanges::default();
         // --- ギルド情報更新  ------------------------------------------------------------------------
         // guildテーブルから取得したギルドIDのリストに
         // 「APIで取得したギルドIDが存在するか」一つずつ検索
@@ -61,6 +98,7 @@ impl GuildUpdateUsecase {
         for &id in local_guild_id_set.difference(&latest_guild_id_set) {
             // 該当するギルドIDをguildテーブルから削除
             self.guild_repo.delete_guild(id).await?;
+            changes.guilds_removed += 1;
         }
 
         // APIに存在し、guildテーブルにないギルドIDが存在
@@ -70,6 +108,7 @@ impl GuildUpdateUsecase {
             self.guild_repo
                 .add_guild(id, my_guild.map(|g| g.name.as_str()))
                 .await?;
+            changes.guilds_added += 1;
         }
         // -----------------------------------------------------------------------------------------------------
 
@@ -96,7 +135,7 @@ impl GuildUpdateUsecase {
             id,
             name: _,
             members: _,
-        } in &latest_my_guilds
+        } in latest_my_guilds
         {
             let latest_guild_id = *id;
             let latest_guild_members_set_by_guild: HashSet<&MyGuildMember> = latest_my_guilds
@@ -132,6 +171,7 @@ impl GuildUpdateUsecase {
             for &id in local_member_id_set.difference(&latest_member_id_set) {
                 // 該当するメンバーIDをguild_memberテーブルから削除
                 self.guild_repo.delete_member(latest_guild_id, id).await?;
+                changes.members_removed += 1;
             }
 
             // APIに存在し、guild_memberテーブルにないメンバーIDが存在
@@ -144,11 +184,105 @@ impl GuildUpdateUsecase {
                     self.guild_repo
                         .add_member(latest_guild_id, member.member_id, member.birth)
                         .await?;
+                    changes.members_added += 1;
                 }
             }
         }
         // -----------------------------------------------------------------------------------------------------
 
-        Ok(())
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GuildUpdateUsecase;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::fake_discord_gateway::FakeDiscordGateway;
+    use crate::data::memory_store::MemoryStore;
+    use crate::models::domain::{MyGuild, MyGuildMember};
+    use crate::services::metrics::GuildSyncChanges;
+    use chrono::NaiveDate;
+    use std::sync::Arc;
+
+    fn my_guild(id: i64, name: &str, member_ids: &[i64]) -> MyGuild {
+        MyGuild {
+            id,
+            name: name.to_string(),
+            members: member_ids
+                .iter()
+                .map(|&member_id| MyGuildMember {
+                    guild_id: id,
+                    member_id,
+                    birth: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_guilds_applies_latest_guilds_and_members() {
+        let store = Arc::new(MemoryStore::new());
+        let birth = NaiveDate::from_ymd_opt(1970, 2, 1).unwrap();
+        store.insert_guild(1, Some("old name")).await.unwrap();
+        store.insert_guild_member(1, 10, None).await.unwrap();
+        store.insert_guild_member(1, 11, Some(birth)).await.unwrap();
+        store.insert_guild(3, Some("left guild")).await.unwrap();
+        store.insert_guild_member(3, 10, None).await.unwrap();
+        let usecase =
+            GuildUpdateUsecase::new(store.clone(), Arc::new(FakeDiscordGateway::new())).unwrap();
+
+        let latest = vec![
+            my_guild(1, "new name", &[11, 12]),
+            my_guild(2, "joined guild", &[10]),
+        ];
+        let local_guild_ids = store.select_guild_ids().await.unwrap();
+        let changes = usecase.sync_guilds(local_guild_ids, &latest).await.unwrap();
+
+        assert_eq!(
+            changes,
+            GuildSyncChanges {
+                guilds_added: 1,
+                guilds_removed: 1,
+                members_added: 2,
+                members_removed: 1,
+            }
+        );
+
+        assert_eq!(store.select_guild_ids().await.unwrap(), vec![1, 2]);
+        let members = store
+            .select_members()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.guild_id, m.member_id, m.birth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            members,
+            vec![(1, 11, Some(birth)), (1, 12, None), (2, 10, None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn invoke_keeps_guilds_that_could_not_be_fetched() {
+        let store = Arc::new(MemoryStore::new());
+        let birth = NaiveDate::from_ymd_opt(1970, 2, 1).unwrap();
+        store.insert_guild(1, Some("guild")).await.unwrap();
+        store.insert_guild_member(1, 10, Some(birth)).await.unwrap();
+        store.insert_guild(2, Some("unavailable")).await.unwrap();
+        store.insert_guild_member(2, 20, Some(birth)).await.unwrap();
+        let discord = Arc::new(FakeDiscordGateway::new());
+        discord.add_guild(1, "guild");
+        discord.add_member(1, 10, "ずんだもん");
+        discord.add_member(1, 11, "めたん");
+        discord.make_guild_unavailable(2);
+        let usecase = GuildUpdateUsecase::new(store.clone(), discord).unwrap();
+
+        usecase.invoke().await.unwrap();
+
+        assert_eq!(store.select_guild_ids().await.unwrap(), vec![1, 2]);
+        assert_eq!(store.select_members_by_guild_id(1).await.unwrap().len(), 2);
+        let kept = store.select_member_by_id(2, 20).await.unwrap().unwrap();
+        assert_eq!(kept.birth, Some(birth));
     }
 }
@@ -1,5 +1,11 @@
+pub mod admin_usecase;
+pub mod birth_history_usecase;
 pub mod birth_list_usecase;
 pub mod birth_notify_usecase;
 pub mod birth_reset_usecase;
+pub mod birth_set_usecase;
+pub mod birth_show_usecase;
 pub mod birth_signup_usecase;
+pub mod celebration_usecase;
 pub mod guild_update_usecase;
+pub mod settings_usecase;
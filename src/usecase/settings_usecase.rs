@@ -0,0 +1,92 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::data::discord_gateway::DiscordGateway;
+use crate::data::guild_repository::GuildRepository;
+use crate::models::common::{Context, Error};
+use crate::res::colors::EMBED_COLOR_SUCCESS;
+use crate::res::messages::{Locale, Message};
+use poise::CreateReply;
+use serenity::all::CreateEmbed;
+use std::sync::Arc;
+
+/// サーバーの管理者が Discord からギルドの設定を変更するためのユースケース
+pub struct SettingsUsecase {
+    guild_repo: GuildRepository,
+}
+
+impl SettingsUsecase {
+    pub fn new(
+        store: Arc<dyn BirthdayStore>,
+        discord: Arc<dyn DiscordGateway>,
+    ) -> anyhow::Result<Self> {
+        let guild_repo = GuildRepository::new(store, discord)?;
+        Ok(SettingsUsecase { guild_repo })
+    }
+
+    /// 誕生日のお知らせの言語を設定する
+    pub async fn set_locale(
+        &self,
+        poise_ctx: Context<'_>,
+        locale: Locale,
+    ) -> anyhow::Result<(), Error> {
+        let guild_id = self
+            .guild_repo
+            .fetch_guild_id_from_command(poise_ctx)
+            .await?;
+        let guild_id = i64::from(guild_id);
+        let guild_name = poise_ctx
+            .guild()
+            .map(|guild| guild.name.clone())
+            .unwrap_or_else(|| format!("guild-{guild_id}"));
+
+        self.save_locale(guild_id, &guild_name, locale).await?;
+        tracing::info!(%locale, "guild locale was set by an admin");
+
+        let reply_locale = self.guild_repo.fetch_locale_from_command(poise_ctx).await;
+        let embed = CreateEmbed::new()
+            .title(Message::GuildLocaleSet { locale }.text(reply_locale))
+            .color(EMBED_COLOR_SUCCESS); // 正常系の色
+        poise_ctx
+            .send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// ギルドがまだ同期されていない場合も設定できるよう、先にギルドを登録してから言語を保存する
+    async fn save_locale(
+        &self,
+        guild_id: i64,
+        guild_name: &str,
+        locale: Locale,
+    ) -> Result<(), Error> {
+        self.guild_repo
+            .add_guild(guild_id, Some(guild_name))
+            .await?;
+        self.guild_repo
+            .set_guild_locale(guild_id, Some(locale))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SettingsUsecase;
+    use crate::data::fake_discord_gateway::FakeDiscordGateway;
+    use crate::data::guild_repository::GuildRepository;
+    use crate::data::memory_store::MemoryStore;
+    use crate::res::messages::Locale;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn locale_is_saved_even_before_the_guild_is_synced() {
+        let store = Arc::new(MemoryStore::new());
+        let discord = Arc::new(FakeDiscordGateway::new());
+        let usecase = SettingsUsecase::new(store.clone(), discord.clone()).unwrap();
+
+        usecase.save_locale(1, "guild", Locale::En).await.unwrap();
+
+        let guild_repo = GuildRepository::new(store, discord).unwrap();
+        assert_eq!(guild_repo.get_guild_locale(1).await.unwrap(), Locale::En);
+        usecase.save_locale(1, "guild", Locale::Ja).await.unwrap();
+        assert_eq!(guild_repo.get_guild_locale(1).await.unwrap(), Locale::Ja);
+    }
+}
@@ -1,34 +1,164 @@
 use crate::models::common::Error;
+use crate::services::clock::Clock;
+use crate::services::shutdown::Shutdown;
 use crate::usecase::birth_notify_usecase::BirthNotifyUsecase;
-use chrono::{Local, NaiveTime, TimeZone};
-use chrono_tz::Asia::Tokyo;
+use crate::worker::leader_election::LeaderElection;
+use chrono::{DateTime, NaiveTime};
+use chrono_tz::Tz;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+/// 誕生日チェックの合間に、送信待ちの通知を再開する間隔
+const RESUME_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 pub struct AnnualBirthdayNotifier;
 
+/// 誕生日通知ワーカーの実行状況 (ステータスエンドポイントで公開する)
+#[derive(Debug, Default)]
+pub struct NotifierStatus {
+    inner: Mutex<NotifierStatusInner>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct NotifierStatusInner {
+    pub last_run: Option<DateTime<Tz>>,
+    pub last_run_succeeded: Option<bool>,
+    pub next_run: Option<DateTime<Tz>>,
+}
+
+impl NotifierStatus {
+    pub fn snapshot(&self) -> NotifierStatusInner {
+        self.inner.lock().unwrap().clone()
+    }
+
+    fn record_next_run(&self, next_run: DateTime<Tz>) {
+        self.inner.lock().unwrap().next_run = Some(next_run);
+    }
+
+    fn record_run(&self, ran_at: DateTime<Tz>, succeeded: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_run = Some(ran_at);
+        inner.last_run_succeeded = Some(succeeded);
+    }
+}
+
 impl AnnualBirthdayNotifier {
-    pub async fn run(birth_notify_usecase: BirthNotifyUsecase) -> anyhow::Result<(), Error> {
-        // 初回の誕生日チェックまでの時間を調節
-        let now = Tokyo.from_utc_datetime(&Local::now().naive_utc());
-        let noon = NaiveTime::from_hms_opt(12, 0, 0).expect("Invalid time.");
-        let today_noon = now.date_naive().and_time(noon);
-        let next_noon = if now.naive_local() < today_noon {
-            today_noon
-        } else {
-            // 通知チェックの時刻を過ぎていた場合は、チェック時刻を明日に振替
-            today_noon + chrono::Duration::days(1)
-        };
-        let wait = u64::try_from((next_noon - now.naive_local()).num_seconds().max(0))?;
-
-        tokio::time::sleep(Duration::from_secs(wait)).await;
-
-        // 誕生日チェック
-        birth_notify_usecase.invoke().await?;
-
-        // 以降は24時間ごとに正午(12:00)のタイミングで誕生日チェック実行
+    pub async fn run(
+        birth_notify_usecase: Arc<BirthNotifyUsecase>,
+        clock: Arc<dyn Clock>,
+        status: Arc<NotifierStatus>,
+        notify_time: NaiveTime,
+        shutdown: Arc<Shutdown>,
+        leader: Arc<LeaderElection>,
+    ) -> anyhow::Result<(), Error> {
+        // 毎日設定した時刻 (既定は正午) のタイミングで誕生日チェック実行
+        // その間も一定間隔で、送信に失敗した通知や他のインスタンスが送信途中で落ちた通知を再開する
         loop {
-            tokio::time::sleep(Duration::from_secs(60 * 60 * 24)).await;
-            birth_notify_usecase.invoke().await?;
+            // 次回の誕生日チェックまでの時間を調節
+            let now = clock.now();
+            let until_daily_run = duration_until_next(now, notify_time);
+            if let Ok(wait) = chrono::Duration::from_std(until_daily_run) {
+                status.record_next_run(now + wait);
+            }
+            let is_daily_run = until_daily_run <= RESUME_INTERVAL;
+            tokio::select! {
+                _ = tokio::time::sleep(until_daily_run.min(RESUME_INTERVAL)) => {}
+                _ = shutdown.requested() => break,
+            }
+            // 開始した通知は、停止要求があっても最終通知日の記録まで終わらせる
+            let Some(_in_flight) = shutdown.track() else {
+                break;
+            };
+            // 複数インスタンスで同じ通知を重複して送らないよう、リーダーのみ実行する
+            // 直前にリースを確認し、期限切れのリーダー状態のまま実行しないようにする
+            leader.renew().await;
+            if !leader.is_leader() {
+                if is_daily_run {
+                    tracing::info!(
+                        "Skipping birthday notification: another instance is the leader"
+                    );
+                }
+                continue;
+            }
+
+            if !is_daily_run {
+                match birth_notify_usecase.resume_pending().await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!(count, "Resumed pending birthday notifications"),
+                    Err(e) => tracing::error!("Failed to resume birthday notifications: {}", e),
+                }
+                continue;
+            }
+
+            // 誕生日チェック
+            // 失敗しても翌日のチェックは続ける
+            let ran_at = clock.now();
+            let result = birth_notify_usecase.invoke().await;
+            if let Err(e) = &result {
+                tracing::error!("Birthday notification run failed: {}", e);
+            }
+            status.record_run(ran_at, result.is_ok());
         }
+        tracing::info!("Birthday notifier stopped");
+        Ok(())
+    }
+}
+
+/// 現在日時から、次に`at`の時刻になるまでの待ち時間を返す
+///
+/// 現在時刻がすでに`at`以降の場合は翌日の`at`までの時間になる。
+fn duration_until_next(now: DateTime<Tz>, at: NaiveTime) -> Duration {
+    let today_at = now.date_naive().and_time(at);
+    let next_at = if now.naive_local() < today_at {
+        today_at
+    } else {
+        // 通知チェックの時刻を過ぎていた場合は、チェック時刻を明日に振替
+        today_at + chrono::Duration::days(1)
+    };
+    (next_at - now.naive_local()).to_std().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::duration_until_next;
+    use crate::services::clock::{Clock, FixedClock};
+    use chrono::NaiveTime;
+    use std::time::Duration;
+
+    fn noon() -> NaiveTime {
+        NaiveTime::from_hms_opt(12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn duration_until_next_waits_for_noon_today() {
+        let now = FixedClock::at(2025, 2, 1, 9, 30).now();
+
+        let wait = duration_until_next(now, noon());
+
+        assert_eq!(wait, Duration::from_secs(150 * 60));
+    }
+
+    #[test]
+    fn duration_until_next_moves_to_tomorrow_at_or_after_noon() {
+        let at_noon = FixedClock::at(2025, 2, 1, 12, 0).now();
+        let after_noon = FixedClock::at(2025, 2, 1, 18, 0).now();
+
+        assert_eq!(
+            duration_until_next(at_noon, noon()),
+            Duration::from_secs(24 * 60 * 60)
+        );
+        assert_eq!(
+            duration_until_next(after_noon, noon()),
+            Duration::from_secs(18 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn duration_until_next_crosses_year_boundary() {
+        let now = FixedClock::at(2024, 12, 31, 13, 0).now();
+
+        let wait = duration_until_next(now, noon());
+
+        assert_eq!(wait, Duration::from_secs(23 * 60 * 60));
     }
 }
@@ -0,0 +1,124 @@
+use crate::data::birthday_store::BirthdayStore;
+use crate::services::shutdown::Shutdown;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 定期処理を実行するインスタンスを決めるリースの名前
+const LEASE_NAME: &str = "scheduled_workers";
+/// リースの有効期間。リーダーが落ちた場合、この時間が過ぎると他のインスタンスが引き継ぐ
+const LEASE_TTL: Duration = Duration::from_secs(30);
+/// リースを延長する間隔。延長に1回失敗しても期限が切れないよう、有効期間より十分短くする
+const RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 複数インスタンス(スケールアウトやデプロイ中の新旧リビジョン)のうち、1台だけを定期処理のリーダーにする
+///
+/// ストアのリースを定期的に取得・延長し、取得できている間だけリーダーとして扱う。
+pub struct LeaderElection {
+    store: Arc<dyn BirthdayStore>,
+    holder: String,
+    is_leader: AtomicBool,
+}
+
+impl LeaderElection {
+    pub fn new(store: Arc<dyn BirthdayStore>) -> Self {
+        LeaderElection {
+            store,
+            holder: holder_id(),
+            is_leader: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::SeqCst)
+    }
+
+    /// 停止が要求されるまでリースの取得・延長を繰り返す
+    pub async fn run(self: Arc<Self>, shutdown: Arc<Shutdown>) {
+        tracing::info!(holder = %self.holder, "Leader election started");
+        loop {
+            self.renew().await;
+            tokio::select! {
+                _ = tokio::time::sleep(RENEW_INTERVAL) => {}
+                _ = shutdown.requested() => break,
+            }
+        }
+    }
+
+    /// リースの取得・延長を1回試みる
+    ///
+    /// 延長に失敗した場合は、他のインスタンスが引き継いでいる可能性があるためリーダーをやめる。
+    pub async fn renew(&self) {
+        let acquired = match self
+            .store
+            .try_acquire_lease(LEASE_NAME, &self.holder, LEASE_TTL)
+            .await
+        {
+            Ok(acquired) => acquired,
+            Err(e) => {
+                tracing::warn!("Failed to renew leader lease: {}", e);
+                false
+            }
+        };
+        let was_leader = self.is_leader.swap(acquired, Ordering::SeqCst);
+        match (was_leader, acquired) {
+            (false, true) => tracing::info!(holder = %self.holder, "Became the leader"),
+            (true, false) => tracing::warn!(holder = %self.holder, "Lost leadership"),
+            _ => {}
+        }
+    }
+
+    /// リースを手放し、他のインスタンスがすぐに引き継げるようにする
+    pub async fn release(&self) {
+        if !self.is_leader.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        match self.store.release_lease(LEASE_NAME, &self.holder).await {
+            Ok(()) => tracing::info!(holder = %self.holder, "Released leader lease"),
+            Err(e) => tracing::warn!("Failed to release leader lease: {}", e),
+        }
+    }
+}
+
+/// インスタンスを区別する ID (ホスト名・プロセスID・起動時刻)
+fn holder_id() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "local".to_string());
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{host}-{}-{started_at}", std::process::id())
+}
+
+// リースを複数のプロセスで共有できるストアでのみ意味があるため、SQLite で確認する
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::LeaderElection;
+    use crate::data::birthday_store::BirthdayStore;
+    use crate::data::sqlite_database::SqliteDatabase;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn only_one_instance_is_the_leader_until_it_releases() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let store: Arc<dyn BirthdayStore> = Arc::new(SqliteDatabase::new(Arc::new(pool)).unwrap());
+        store.migrate_up().await.unwrap();
+        let first = LeaderElection::new(store.clone());
+        let second = LeaderElection::new(store.clone());
+
+        first.renew().await;
+        second.renew().await;
+        assert!(first.is_leader());
+        assert!(!second.is_leader());
+
+        first.release().await;
+        second.renew().await;
+        assert!(!first.is_leader());
+        assert!(second.is_leader());
+    }
+}
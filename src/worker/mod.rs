@@ -1 +1,2 @@
 pub mod annual_birthday_notifier;
+pub mod leader_election;